@@ -0,0 +1,395 @@
+//! Bindings that can be triggered from the keyboard or from a controller.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::controller::{ControllerCombination, ControllerTracker};
+use crate::error::{self, ParseError};
+use crate::key::{
+    match_policy, parse_options, Hotkey, InputSource, Key, MatchPolicy, Pretty, Strokes, Trigger,
+    TriggerState, DEFAULT_STROKE_TIMEOUT,
+};
+
+/// Prefix forcing an alternative to be read as a controller combination, for
+/// the names shared with keys, e.g. `pad:a` or `pad:up`.
+const CONTROLLER_PREFIX: &str = "pad:";
+
+/// One or more alternative inputs for the same action, separated by `|`, e.g.
+/// `"f1 | l1+r1+a"`.
+///
+/// Each alternative is a keyboard [`Hotkey`] or a [`ControllerCombination`].
+/// Keyboard names are tried first, so a controller combination made only of
+/// names shared with keys needs the `pad:` prefix. The binding can be
+/// preceded by a [`Trigger`] mode and a [`MatchPolicy`], which apply to all
+/// alternatives, e.g. `"hold:500ms f5 | pad:start"`, and by a stroke timeout
+/// for the keyboard sequences, e.g. `"timeout:800ms ctrl+k, ctrl+s | back"`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct Binding {
+    keys: Vec<Hotkey>,
+    buttons: Vec<ControllerCombination>,
+    trigger: Trigger,
+    policy: Option<MatchPolicy>,
+    tracker: ControllerTracker,
+    trigger_state: TriggerState,
+}
+
+impl PartialEq for Binding {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+            && self.buttons == other.buttons
+            && self.trigger == other.trigger
+            && self.policy == other.policy
+    }
+}
+
+impl Eq for Binding {}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(policy) = self.policy {
+            write!(f, "{policy} ")?;
+        }
+        if self.trigger != Trigger::Press {
+            write!(f, "{} ", self.trigger)?;
+        }
+        let timeout = self.keys.first().map_or(DEFAULT_STROKE_TIMEOUT, Hotkey::timeout);
+        if timeout != DEFAULT_STROKE_TIMEOUT {
+            write!(f, "timeout:{}ms ", timeout.as_millis())?;
+        }
+
+        let keys = self.keys.iter().map(|key| Strokes(key.strokes()).to_string());
+        let buttons = self.buttons.iter().map(|buttons| {
+            let repr = buttons.to_string();
+            if Hotkey::parse_strokes(&repr).is_ok() {
+                format!("{CONTROLLER_PREFIX}{repr}")
+            } else {
+                repr
+            }
+        });
+
+        for (i, alternative) in keys.chain(buttons).enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{alternative}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Pretty<'_, Binding> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Pretty(&self.0.trigger))?;
+
+        let keys = self.0.keys.iter().map(|key| key.pretty().to_string());
+        let buttons = self.0.buttons.iter().map(|buttons| buttons.pretty().to_string());
+
+        for (i, alternative) in keys.chain(buttons).enumerate() {
+            if i > 0 {
+                write!(f, " / ")?;
+            }
+            write!(f, "{alternative}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl From<Hotkey> for Binding {
+    fn from(hotkey: Hotkey) -> Self {
+        let key = Hotkey::new(hotkey.strokes().to_vec()).with_timeout(hotkey.timeout());
+        let binding = Self::new(vec![key], Vec::new()).with_trigger(hotkey.trigger());
+        Self { policy: hotkey.policy(), ..binding }
+    }
+}
+
+impl From<Key> for Binding {
+    fn from(key: Key) -> Self {
+        Self::new(vec![key.into()], Vec::new())
+    }
+}
+
+impl From<ControllerCombination> for Binding {
+    fn from(buttons: ControllerCombination) -> Self {
+        Self::new(Vec::new(), vec![buttons])
+    }
+}
+
+impl TryFrom<&str> for Binding {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (options, offset) = parse_options(input)?;
+        let timeout = options.timeout.unwrap_or(DEFAULT_STROKE_TIMEOUT);
+        let mut keys = Vec::new();
+        let mut buttons = Vec::new();
+
+        for (alternative, span) in error::tokens(&input[offset..], '|') {
+            let start = offset + span.start;
+
+            if alternative.is_empty() {
+                return Err(ParseError::Empty { span: start..start });
+            }
+
+            if let Some(rest) = strip_prefix_ignore_case(alternative, CONTROLLER_PREFIX) {
+                let start = start + CONTROLLER_PREFIX.len();
+                buttons.push(ControllerCombination::try_from(rest).map_err(|e| e.offset(start))?);
+                continue;
+            }
+
+            let key = Hotkey::parse_strokes(alternative);
+            match (key, ControllerCombination::try_from(alternative)) {
+                (Ok(key), _) => keys.push(key.with_timeout(timeout)),
+                (Err(_), Ok(combination)) => buttons.push(combination),
+                // Report the error of the parser that got further.
+                (Err(key_err), Err(button_err)) => {
+                    let err = if button_err.span().start > key_err.span().start {
+                        button_err
+                    } else {
+                        key_err
+                    };
+                    return Err(err.offset(start));
+                },
+            }
+        }
+
+        let binding = Self::new(keys, buttons).with_trigger(options.trigger);
+        Ok(Self { policy: options.policy, ..binding })
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then(|| &s[prefix.len()..])
+}
+
+impl Binding {
+    fn new(keys: Vec<Hotkey>, buttons: Vec<ControllerCombination>) -> Self {
+        Self {
+            tracker: Self::tracker(&buttons),
+            keys,
+            buttons,
+            trigger: Trigger::Press,
+            policy: None,
+            trigger_state: TriggerState::default(),
+        }
+    }
+
+    fn tracker(buttons: &[ControllerCombination]) -> ControllerTracker {
+        let mut tracker = ControllerTracker::new();
+        buttons.iter().for_each(|&buttons| tracker.track(buttons));
+        tracker
+    }
+
+    /// Displays the binding with the names meant for labels, e.g.
+    /// `Ctrl+F1 / L1+R1+A`.
+    pub fn pretty(&self) -> Pretty<'_, Binding> {
+        Pretty(self)
+    }
+
+    /// Sets when the binding fires.
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    /// Overrides the global [`match_policy`] for the keyboard alternatives.
+    pub fn with_policy(mut self, policy: MatchPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Replaces the keyboard alternatives, keeping the controller ones.
+    pub fn with_keys(mut self, keys: Vec<Hotkey>) -> Self {
+        self.keys = keys;
+        self.reset();
+        self
+    }
+
+    /// Replaces the controller alternatives, keeping the keyboard ones.
+    pub fn with_buttons(mut self, buttons: Vec<ControllerCombination>) -> Self {
+        self.tracker = Self::tracker(&buttons);
+        self.buttons = buttons;
+        self.reset();
+        self
+    }
+
+    pub fn keys(&self) -> &[Hotkey] {
+        &self.keys
+    }
+
+    pub fn buttons(&self) -> &[ControllerCombination] {
+        &self.buttons
+    }
+
+    pub fn trigger(&self) -> Trigger {
+        self.trigger
+    }
+
+    /// The policy set on this binding, if it overrides the global one.
+    pub fn policy(&self) -> Option<MatchPolicy> {
+        self.policy
+    }
+
+    /// The policy the keyboard alternatives are matched with.
+    pub fn effective_policy(&self) -> MatchPolicy {
+        self.policy.unwrap_or_else(match_policy)
+    }
+
+    /// Clears any partially typed sequence and held state.
+    pub fn reset(&mut self) {
+        self.keys.iter_mut().for_each(Hotkey::reset);
+        self.tracker.reset();
+        self.trigger_state = TriggerState::default();
+    }
+
+    /// Polls all the alternatives and returns `true` on the frames the
+    /// binding fires according to its [`Trigger`]. Like [`Hotkey`], it must
+    /// be called once per frame.
+    pub fn is_pressed(&mut self, input: &dyn InputSource) -> bool {
+        let policy = self.effective_policy();
+        let (mut pressed, mut down) = (false, false);
+
+        for key in &mut self.keys {
+            let (key_pressed, key_down) = key.poll(input, policy);
+            pressed |= key_pressed;
+            down |= key_down;
+        }
+
+        self.tracker.update_from(input);
+        for buttons in &self.buttons {
+            pressed |= self.tracker.just_pressed(buttons);
+            down |= self.tracker.is_down(buttons);
+        }
+
+        self.trigger_state.update(self.trigger, pressed, down, input.time())
+    }
+
+    /// Whether any alternative was held on the last call to
+    /// [`Binding::is_pressed`], e.g. for widgets open only while it is.
+    pub fn is_held(&self) -> bool {
+        self.trigger_state.is_held()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use windows::Win32::UI::Input::XboxController::*;
+
+    use super::*;
+    use crate::key::ScriptedInput;
+
+    fn controller_state(buttons: XINPUT_GAMEPAD_BUTTON_FLAGS) -> XINPUT_STATE {
+        XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD { wButtons: buttons, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let binding: Binding = "f1 | l1+r1+a".parse().unwrap();
+        assert_eq!(binding.keys(), &["f1".parse().unwrap()]);
+        assert_eq!(binding.buttons(), &[ControllerCombination::try_from("l1+r1+a").unwrap()]);
+
+        for s in [
+            "f1",
+            "l1+r1+a",
+            "f1 | l1+r1+a",
+            "ctrl+k, ctrl+s | pad:a | start",
+            "lenient hold:500ms f5 | pad:up",
+            "f2 | l3+rstick_up | back+lstick_left",
+            "hold:1000ms f3 | exclusive l2>75%+a",
+            "f4 | pad1:l1+a | pad:a",
+            "timeout:800ms ctrl+k, ctrl+s | back",
+        ] {
+            assert_eq!(s.parse::<Binding>().unwrap().to_string(), s);
+        }
+
+        let binding: Binding = "pad1:cross | f4".parse().unwrap();
+        assert_eq!(binding.to_string(), "f4 | pad1:a");
+        let binding: Binding = "a | pad:a".parse().unwrap();
+        assert_eq!((binding.keys().len(), binding.buttons().len()), (1, 1));
+        assert_eq!(
+            Binding::from("hold:1s ctrl+f".parse::<Hotkey>().unwrap()),
+            "hold:1s ctrl+f".parse().unwrap()
+        );
+        let binding: Binding = "timeout:2s ctrl+k, ctrl+s | f1".parse().unwrap();
+        assert!(binding.keys().iter().all(|key| key.timeout() == Duration::from_secs(2)));
+        assert_eq!(
+            Binding::from("timeout:2s ctrl+k, ctrl+s".parse::<Hotkey>().unwrap()).to_string(),
+            "timeout:2000ms ctrl+k, ctrl+s"
+        );
+
+        assert_eq!("f1 |".parse::<Binding>(), Err(ParseError::Empty { span: 4..4 }));
+        let err = "f1 | l1+r1+strat".parse::<Binding>().unwrap_err();
+        assert_eq!((err.token(), err.span(), err.suggestions()), ("strat", 11..16, &["start"][..]));
+        let err = "f1 | ctrl+pgdwn".parse::<Binding>().unwrap_err();
+        assert_eq!((err.token(), err.span()), ("pgdwn", 10..15));
+        assert!("f1 | hold f2".parse::<Binding>().is_err());
+        let err = "pad:l1+ctrl".parse::<Binding>().unwrap_err();
+        assert_eq!((err.token(), err.span()), ("ctrl", 7..11));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let pretty = |s: &str| s.parse::<Binding>().unwrap().pretty().to_string();
+        assert_eq!(pretty("f1 | l1+r1+a"), "F1 / L1+R1+A");
+        assert_eq!(pretty("doubletap esc | pad:lb+start"), "Double-tap Esc / L1+Start");
+    }
+
+    #[test]
+    fn test_is_pressed() {
+        let mut binding: Binding = "f1 | l1+a".parse().unwrap();
+        let mut input = ScriptedInput::new();
+        let frame = |input: &mut ScriptedInput, binding: &mut Binding| {
+            let fired = binding.is_pressed(input);
+            input.next_frame(Duration::from_millis(10));
+            fired
+        };
+
+        input.press(imgui::Key::F1);
+        assert!(frame(&mut input, &mut binding));
+        input.release_all();
+        assert!(!frame(&mut input, &mut binding));
+
+        input.set_controller_state(Some(controller_state(XINPUT_GAMEPAD_A)));
+        assert!(!frame(&mut input, &mut binding));
+        let pressed = XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_LEFT_SHOULDER;
+        input.set_controller_state(Some(controller_state(pressed)));
+        assert!(frame(&mut input, &mut binding));
+        // Held buttons fire only once.
+        assert!(!frame(&mut input, &mut binding));
+        input.set_controller_state(None);
+        assert!(!frame(&mut input, &mut binding));
+        input.set_controller_state(Some(controller_state(pressed)));
+        assert!(frame(&mut input, &mut binding));
+    }
+}
@@ -1,69 +1,799 @@
-use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU16, AtomicU8, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize, Serializer};
 use windows::Win32::UI::Input::XboxController::*;
 
+use crate::error::{self, ParseError};
+use crate::key::{
+    all_names, config_name, display_name, find_by_name, InputSource, NameMap, Pretty,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Button(XINPUT_GAMEPAD_BUTTON_FLAGS),
+    LeftTrigger,
+    RightTrigger,
+    Stick(StickDirection),
+}
+
+/// A direction of one of the analog sticks. Combinations store them as bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum StickDirection {
+    LeftUp,
+    LeftDown,
+    LeftLeft,
+    LeftRight,
+    RightUp,
+    RightDown,
+    RightLeft,
+    RightRight,
+}
+
+impl StickDirection {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Whether the stick is pushed in this direction past `deadzone`. Each
+    /// axis is checked on its own, so diagonals push two directions at once.
+    fn is_pushed(self, gamepad: &XINPUT_GAMEPAD, deadzone: u16) -> bool {
+        let deadzone = i32::from(deadzone);
+        let (x, y) = match self {
+            StickDirection::LeftUp
+            | StickDirection::LeftDown
+            | StickDirection::LeftLeft
+            | StickDirection::LeftRight => (gamepad.sThumbLX, gamepad.sThumbLY),
+            _ => (gamepad.sThumbRX, gamepad.sThumbRY),
+        };
+        let (x, y) = (i32::from(x), i32::from(y));
+
+        match self {
+            StickDirection::LeftUp | StickDirection::RightUp => y > deadzone,
+            StickDirection::LeftDown | StickDirection::RightDown => y < -deadzone,
+            StickDirection::LeftLeft | StickDirection::RightLeft => x < -deadzone,
+            StickDirection::LeftRight | StickDirection::RightRight => x > deadzone,
+        }
+    }
+}
+
+/// Names of the controller inputs, in display order: the canonical config
+/// name followed by its aliases, then the name displayed in labels.
+const REPR_MAP: &NameMap<Input> = &[
+    (Input::Button(XINPUT_GAMEPAD_LEFT_SHOULDER), &["l1", "lb"], "L1"),
+    (Input::LeftTrigger, &["l2", "lt"], "L2"),
+    (Input::Button(XINPUT_GAMEPAD_LEFT_THUMB), &["l3", "ls"], "L3"),
+    (Input::Button(XINPUT_GAMEPAD_RIGHT_SHOULDER), &["r1", "rb"], "R1"),
+    (Input::RightTrigger, &["r2", "rt"], "R2"),
+    (Input::Button(XINPUT_GAMEPAD_RIGHT_THUMB), &["r3", "rs"], "R3"),
+    (Input::Button(XINPUT_GAMEPAD_DPAD_DOWN), &["down", "dpad_down"], "Down"),
+    (Input::Button(XINPUT_GAMEPAD_DPAD_UP), &["up", "dpad_up"], "Up"),
+    (Input::Button(XINPUT_GAMEPAD_DPAD_RIGHT), &["right", "dpad_right"], "Right"),
+    (Input::Button(XINPUT_GAMEPAD_DPAD_LEFT), &["left", "dpad_left"], "Left"),
+    (Input::Button(XINPUT_GAMEPAD_A), &["a", "cross"], "A"),
+    (Input::Button(XINPUT_GAMEPAD_B), &["b", "circle"], "B"),
+    (Input::Button(XINPUT_GAMEPAD_X), &["x", "square"], "X"),
+    (Input::Button(XINPUT_GAMEPAD_Y), &["y", "triangle"], "Y"),
+    // DualShock drivers map both the share button and the touchpad click to
+    // back, since XInput has no touchpad.
+    (Input::Button(XINPUT_GAMEPAD_BACK), &["back", "select", "view", "share", "touchpad"], "Back"),
+    (Input::Button(XINPUT_GAMEPAD_START), &["start", "options"], "Start"),
+    (Input::Stick(StickDirection::LeftUp), &["lstick_up", "ls_up"], "Left Stick Up"),
+    (Input::Stick(StickDirection::LeftDown), &["lstick_down", "ls_down"], "Left Stick Down"),
+    (Input::Stick(StickDirection::LeftLeft), &["lstick_left", "ls_left"], "Left Stick Left"),
+    (Input::Stick(StickDirection::LeftRight), &["lstick_right", "ls_right"], "Left Stick Right"),
+    (Input::Stick(StickDirection::RightUp), &["rstick_up", "rs_up"], "Right Stick Up"),
+    (Input::Stick(StickDirection::RightDown), &["rstick_down", "rs_down"], "Right Stick Down"),
+    (Input::Stick(StickDirection::RightLeft), &["rstick_left", "rs_left"], "Right Stick Left"),
+    (Input::Stick(StickDirection::RightRight), &["rstick_right", "rs_right"], "Right Stick Right"),
+];
+
+/// Display names of the [`NamingScheme::PlayStation`] scheme, for the inputs
+/// whose name differs from the default one.
+const PLAYSTATION_NAMES: &[(Input, &str)] = &[
+    (Input::Button(XINPUT_GAMEPAD_A), "Cross"),
+    (Input::Button(XINPUT_GAMEPAD_B), "Circle"),
+    (Input::Button(XINPUT_GAMEPAD_X), "Square"),
+    (Input::Button(XINPUT_GAMEPAD_Y), "Triangle"),
+    (Input::Button(XINPUT_GAMEPAD_BACK), "Share"),
+    (Input::Button(XINPUT_GAMEPAD_START), "Options"),
+];
+
+/// Number of controllers XInput can report, see
+/// [`InputSource::controller_state_at`].
+pub const MAX_CONTROLLERS: u8 = 4;
+
+const SLOT_PREFIX: &str = "pad";
+
+/// Which button names are displayed in labels. Config strings accept the
+/// names of every scheme regardless.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "String")]
+pub enum NamingScheme {
+    /// Face buttons are A, B, X and Y.
+    #[default]
+    Xbox,
+    /// Face buttons are Cross, Circle, Square and Triangle.
+    PlayStation,
+}
+
+const NAMING_SCHEME_REPR_MAP: &[(NamingScheme, &str)] =
+    &[(NamingScheme::Xbox, "xbox"), (NamingScheme::PlayStation, "playstation")];
+
+impl std::fmt::Display for NamingScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let repr = NAMING_SCHEME_REPR_MAP
+            .iter()
+            .find_map(|&(scheme, val)| if scheme == *self { Some(val) } else { None })
+            .unwrap_or("???");
+        write!(f, "{repr}")
+    }
+}
+
+impl Serialize for NamingScheme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for NamingScheme {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMING_SCHEME_REPR_MAP
+            .iter()
+            .find_map(
+                |&(scheme, val)| if val.eq_ignore_ascii_case(s) { Some(scheme) } else { None },
+            )
+            .ok_or_else(|| {
+                ParseError::unknown_scheme(
+                    s,
+                    0..s.len(),
+                    NAMING_SCHEME_REPR_MAP.iter().map(|&(_, val)| val),
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for NamingScheme {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+static NAMING_SCHEME: AtomicU8 = AtomicU8::new(NamingScheme::Xbox as u8);
+
+/// The scheme button names are displayed with.
+pub fn naming_scheme() -> NamingScheme {
+    match NAMING_SCHEME.load(Ordering::Relaxed) {
+        1 => NamingScheme::PlayStation,
+        _ => NamingScheme::Xbox,
+    }
+}
+
+/// Sets the scheme button names are displayed with, e.g. from the tool's
+/// config at startup.
+pub fn set_naming_scheme(scheme: NamingScheme) {
+    NAMING_SCHEME.store(scheme as u8, Ordering::Relaxed);
+}
+
+fn scheme_name(scheme: NamingScheme, input: &Input) -> &'static str {
+    let overrides = match scheme {
+        NamingScheme::Xbox => &[][..],
+        NamingScheme::PlayStation => PLAYSTATION_NAMES,
+    };
+
+    overrides
+        .iter()
+        .find_map(|(i, name)| (i == input).then_some(*name))
+        .unwrap_or_else(|| display_name(REPR_MAP, input))
+}
+
+/// Splits the `padN:` prefix naming a controller slot off a combination.
+fn split_slot(s: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = s.split_once(':')?;
+    let head = prefix.get(..SLOT_PREFIX.len())?;
+    let digits = &prefix[SLOT_PREFIX.len()..];
+
+    (head.eq_ignore_ascii_case(SLOT_PREFIX) && digits.chars().all(|c| c.is_ascii_digit()))
+        .then_some((digits, rest))
+}
+
+/// Whether `s` starts with a controller prefix, `pad:` or `padN:`.
+pub(crate) fn has_slot_prefix(s: &str) -> bool {
+    split_slot(s).is_some()
+}
+
+/// The imgui keys backends report the buttons of a gamepad with.
+const GAMEPAD_KEY_MAP: &[(XINPUT_GAMEPAD_BUTTON_FLAGS, imgui::Key)] = &[
+    (XINPUT_GAMEPAD_DPAD_UP, imgui::Key::GamepadDpadUp),
+    (XINPUT_GAMEPAD_DPAD_DOWN, imgui::Key::GamepadDpadDown),
+    (XINPUT_GAMEPAD_DPAD_LEFT, imgui::Key::GamepadDpadLeft),
+    (XINPUT_GAMEPAD_DPAD_RIGHT, imgui::Key::GamepadDpadRight),
+    (XINPUT_GAMEPAD_START, imgui::Key::GamepadStart),
+    (XINPUT_GAMEPAD_BACK, imgui::Key::GamepadBack),
+    (XINPUT_GAMEPAD_LEFT_THUMB, imgui::Key::GamepadL3),
+    (XINPUT_GAMEPAD_RIGHT_THUMB, imgui::Key::GamepadR3),
+    (XINPUT_GAMEPAD_LEFT_SHOULDER, imgui::Key::GamepadL1),
+    (XINPUT_GAMEPAD_RIGHT_SHOULDER, imgui::Key::GamepadR1),
+    (XINPUT_GAMEPAD_A, imgui::Key::GamepadFaceDown),
+    (XINPUT_GAMEPAD_B, imgui::Key::GamepadFaceRight),
+    (XINPUT_GAMEPAD_X, imgui::Key::GamepadFaceLeft),
+    (XINPUT_GAMEPAD_Y, imgui::Key::GamepadFaceUp),
+];
+
+/// Reads the gamepad from imgui's gamepad keys and their analog values, for
+/// backends other than XInput, e.g. SDL.
+///
+/// Triggers and stick directions are scaled back to XInput's ranges, so that
+/// thresholds and deadzones mean the same with either backend.
+pub fn gamepad_from_keys(input: &dyn InputSource) -> XINPUT_GAMEPAD {
+    let analog = |key| input.key_analog_value(key).clamp(0., 1.);
+    let trigger = |key| (analog(key) * 255.).round() as u8;
+    let axis = |negative, positive| ((analog(positive) - analog(negative)) * 32767.).round() as i16;
+
+    let buttons = GAMEPAD_KEY_MAP
+        .iter()
+        .filter(|&&(_, key)| input.is_key_down(key))
+        .fold(XINPUT_GAMEPAD_BUTTON_FLAGS(0), |buttons, &(button, _)| buttons | button);
+
+    XINPUT_GAMEPAD {
+        wButtons: buttons,
+        bLeftTrigger: trigger(imgui::Key::GamepadL2),
+        bRightTrigger: trigger(imgui::Key::GamepadR2),
+        sThumbLX: axis(imgui::Key::GamepadLStickLeft, imgui::Key::GamepadLStickRight),
+        sThumbLY: axis(imgui::Key::GamepadLStickDown, imgui::Key::GamepadLStickUp),
+        sThumbRX: axis(imgui::Key::GamepadRStickLeft, imgui::Key::GamepadRStickRight),
+        sThumbRY: axis(imgui::Key::GamepadRStickDown, imgui::Key::GamepadRStickUp),
+    }
+}
+
+/// Queues the gamepad's state as imgui's gamepad keys, like backends other
+/// than XInput report it. The inverse of [`gamepad_from_keys`].
+pub fn feed_gamepad_keys(io: &mut imgui::Io, gamepad: &XINPUT_GAMEPAD) {
+    for &(button, key) in GAMEPAD_KEY_MAP {
+        io.add_key_event(key, gamepad.wButtons.0 & button.0 != 0);
+    }
+
+    let trigger = |value: u8| f32::from(value) / 255.;
+    let axis = |value: i16| (f32::from(value) / 32767.).clamp(-1., 1.);
+    for (key, value) in [
+        (imgui::Key::GamepadL2, trigger(gamepad.bLeftTrigger)),
+        (imgui::Key::GamepadR2, trigger(gamepad.bRightTrigger)),
+        (imgui::Key::GamepadLStickLeft, -axis(gamepad.sThumbLX)),
+        (imgui::Key::GamepadLStickRight, axis(gamepad.sThumbLX)),
+        (imgui::Key::GamepadLStickUp, axis(gamepad.sThumbLY)),
+        (imgui::Key::GamepadLStickDown, -axis(gamepad.sThumbLY)),
+        (imgui::Key::GamepadRStickLeft, -axis(gamepad.sThumbRX)),
+        (imgui::Key::GamepadRStickRight, axis(gamepad.sThumbRX)),
+        (imgui::Key::GamepadRStickUp, axis(gamepad.sThumbRY)),
+        (imgui::Key::GamepadRStickDown, -axis(gamepad.sThumbRY)),
+    ] {
+        let value = value.clamp(0., 1.);
+        io.add_key_analog_event(key, value > 0.1, value);
+    }
+}
+
+/// Whether the key is one of imgui's gamepad keys.
+pub(crate) fn is_gamepad_key(key: imgui::Key) -> bool {
+    (imgui::Key::GamepadStart as u32..=imgui::Key::GamepadRStickDown as u32).contains(&(key as u32))
+}
+
+static STICK_DEADZONE: AtomicU16 = AtomicU16::new(XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE.0);
+
+/// How far a stick must be pushed along an axis for a stick direction to be
+/// pressed. Defaults to the deadzone XInput recommends for the right stick,
+/// which is the larger of the two.
+pub fn stick_deadzone() -> u16 {
+    STICK_DEADZONE.load(Ordering::Relaxed)
+}
+
+/// Sets the deadzone used by [`ControllerCombination::is_pressed`], e.g. from
+/// the tool's config at startup.
+pub fn set_stick_deadzone(deadzone: u16) {
+    STICK_DEADZONE.store(deadzone, Ordering::Relaxed);
+}
+
+/// How far a trigger must be pulled when its binding doesn't set a threshold.
+const DEFAULT_TRIGGER_THRESHOLD: u8 = XINPUT_GAMEPAD_TRIGGER_THRESHOLD.0 as u8;
+
+const EXCLUSIVE: &str = "exclusive";
+
+/// A set of controller inputs held together, e.g. `l1+r1+a`.
+///
+/// Triggers can set how far they must be pulled, e.g. `l2>75%+a`. An
+/// `exclusive` combination only fires while no other input is held, so that
+/// `exclusive a` doesn't fire during `l1+a`. A combination can name the slot
+/// of the controller it listens to, e.g. `pad1:l1+a`; otherwise it listens to
+/// the primary one.
 #[derive(Default, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
 #[serde(try_from = "String")]
 pub struct ControllerCombination {
     buttons: XINPUT_GAMEPAD_BUTTON_FLAGS,
-    left_trigger: bool,
-    right_trigger: bool,
+    left_trigger: Option<u8>,
+    right_trigger: Option<u8>,
+    sticks: u8,
+    exclusive: bool,
+    slot: Option<u8>,
 }
 
 impl ControllerCombination {
+    /// Whether all the inputs are held, with stick directions matched against
+    /// the global [`stick_deadzone`].
     pub fn is_pressed(&self, state: &XINPUT_STATE) -> bool {
-        state.Gamepad.wButtons.contains(self.buttons)
-            && (!self.left_trigger
-                || state.Gamepad.bLeftTrigger > XINPUT_GAMEPAD_TRIGGER_THRESHOLD.0 as u8)
-            && (!self.right_trigger
-                || state.Gamepad.bRightTrigger > XINPUT_GAMEPAD_TRIGGER_THRESHOLD.0 as u8)
+        self.is_pressed_with(state, stick_deadzone())
+    }
+
+    pub fn is_pressed_with(&self, state: &XINPUT_STATE, deadzone: u16) -> bool {
+        let gamepad = &state.Gamepad;
+        let trigger_pulled = |threshold: Option<u8>, value| threshold.is_none_or(|t| value > t);
+
+        gamepad.wButtons.contains(self.buttons)
+            && trigger_pulled(self.left_trigger, gamepad.bLeftTrigger)
+            && trigger_pulled(self.right_trigger, gamepad.bRightTrigger)
+            && self.stick_directions().all(|d| d.is_pushed(gamepad, deadzone))
+            && (!self.exclusive || Self::held(gamepad, deadzone).is_subset(self))
+    }
+
+    /// The inputs held in `gamepad`, with triggers at their default threshold.
+    pub(crate) fn held(gamepad: &XINPUT_GAMEPAD, deadzone: u16) -> ControllerCombination {
+        let trigger =
+            |value| (value > DEFAULT_TRIGGER_THRESHOLD).then_some(DEFAULT_TRIGGER_THRESHOLD);
+        let mut held = ControllerCombination {
+            buttons: gamepad.wButtons,
+            left_trigger: trigger(gamepad.bLeftTrigger),
+            right_trigger: trigger(gamepad.bRightTrigger),
+            ..Default::default()
+        };
+
+        for &(input, ..) in REPR_MAP {
+            if let Input::Stick(direction) = input {
+                if direction.is_pushed(gamepad, deadzone) {
+                    held.insert(input);
+                }
+            }
+        }
+
+        held
+    }
+
+    /// Whether pressing `other` also presses all the inputs of this one.
+    /// Thresholds are ignored, since pulling a trigger all the way passes all
+    /// of them.
+    pub(crate) fn is_subset(&self, other: &ControllerCombination) -> bool {
+        // The primary controller may be in any slot.
+        let same_controller =
+            self.slot.is_none() || other.slot.is_none() || self.slot == other.slot;
+
+        let inputs_subset = same_controller
+            && other.buttons.contains(self.buttons)
+            && (self.left_trigger.is_none() || other.left_trigger.is_some())
+            && (self.right_trigger.is_none() || other.right_trigger.is_some())
+            && other.sticks & self.sticks == self.sticks;
+
+        inputs_subset && (!self.exclusive || other.is_subset(&self.inclusive()))
+    }
+
+    /// The inputs of both combinations, e.g. to accumulate the inputs pressed
+    /// one after the other while capturing a binding.
+    pub(crate) fn union(&self, other: &ControllerCombination) -> ControllerCombination {
+        ControllerCombination {
+            buttons: self.buttons | other.buttons,
+            left_trigger: self.left_trigger.or(other.left_trigger),
+            right_trigger: self.right_trigger.or(other.right_trigger),
+            sticks: self.sticks | other.sticks,
+            ..*self
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buttons.0 == 0
+            && self.left_trigger.is_none()
+            && self.right_trigger.is_none()
+            && self.sticks == 0
+    }
+
+    fn inclusive(&self) -> ControllerCombination {
+        ControllerCombination { exclusive: false, ..*self }
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    pub fn with_exclusive(self, exclusive: bool) -> Self {
+        ControllerCombination { exclusive, ..self }
+    }
+
+    /// The slot of the controller this combination listens to, `None` for
+    /// the primary one.
+    pub fn slot(&self) -> Option<u8> {
+        self.slot
+    }
+
+    pub fn with_slot(self, slot: Option<u8>) -> Self {
+        ControllerCombination { slot, ..self }
+    }
+
+    /// Whether all the inputs are held on the gamepad imgui's backend reports,
+    /// whatever the slot of the combination.
+    pub fn is_pressed_on_keys(&self, input: &dyn InputSource) -> bool {
+        let state = XINPUT_STATE { dwPacketNumber: 0, Gamepad: gamepad_from_keys(input) };
+        self.is_pressed(&state)
+    }
+
+    /// The state of the controller this combination listens to.
+    pub fn controller_state(&self, input: &dyn InputSource) -> Option<XINPUT_STATE> {
+        match self.slot {
+            Some(slot) => input.controller_state_at(slot),
+            None => input.controller_state(),
+        }
+    }
+
+    fn stick_directions(&self) -> impl Iterator<Item = StickDirection> + '_ {
+        REPR_MAP.iter().filter_map(|&(input, ..)| match input {
+            Input::Stick(direction) if self.contains(input) => Some(direction),
+            _ => None,
+        })
+    }
+
+    fn contains(&self, input: Input) -> bool {
+        match input {
+            Input::Button(button) => self.buttons.contains(button),
+            Input::LeftTrigger => self.left_trigger.is_some(),
+            Input::RightTrigger => self.right_trigger.is_some(),
+            Input::Stick(direction) => self.sticks & direction.bit() != 0,
+        }
+    }
+
+    fn insert(&mut self, input: Input) {
+        match input {
+            Input::Button(button) => self.buttons |= button,
+            Input::LeftTrigger => self.left_trigger = Some(DEFAULT_TRIGGER_THRESHOLD),
+            Input::RightTrigger => self.right_trigger = Some(DEFAULT_TRIGGER_THRESHOLD),
+            Input::Stick(direction) => self.sticks |= direction.bit(),
+        }
+    }
+}
+
+impl ControllerCombination {
+    pub fn pretty(&self) -> Pretty<'_, ControllerCombination> {
+        Pretty(self)
+    }
+
+    /// Formats the config string, or the label if a naming `scheme` is given.
+    fn fmt_with(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        scheme: Option<NamingScheme>,
+    ) -> std::fmt::Result {
+        match (self.slot, scheme) {
+            (Some(slot), None) => write!(f, "{SLOT_PREFIX}{slot}:")?,
+            (Some(slot), Some(_)) => write!(f, "Pad {slot}: ")?,
+            (None, _) => {},
+        }
+
+        if self.exclusive {
+            write!(f, "{} ", if scheme.is_some() { "Exclusive" } else { EXCLUSIVE })?;
+        }
+
+        let name = |input| match scheme {
+            Some(scheme) => scheme_name(scheme, input),
+            None => config_name(REPR_MAP, input),
+        };
+
+        let inputs = REPR_MAP.iter().filter(|&&(input, ..)| self.contains(input));
+
+        for (i, (input, ..)) in inputs.enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+            write!(f, "{}", name(input))?;
+
+            let threshold = match input {
+                Input::LeftTrigger => self.left_trigger,
+                Input::RightTrigger => self.right_trigger,
+                _ => None,
+            };
+            if let Some(threshold) = threshold.filter(|&t| t != DEFAULT_TRIGGER_THRESHOLD) {
+                write!(f, ">{}%", (u32::from(threshold) * 100 + 127) / 255)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ControllerCombination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, None)
+    }
+}
+
+impl std::fmt::Display for Pretty<'_, ControllerCombination> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_with(f, Some(naming_scheme()))
+    }
+}
+
+impl Serialize for ControllerCombination {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
 impl TryFrom<&str> for ControllerCombination {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let value = value.to_ascii_lowercase();
-        let value = value.split('+');
         let mut combination = ControllerCombination::default();
+        let mut offset = 0;
 
-        for s in value {
-            match s.trim() {
-                "l1" => combination.buttons |= XINPUT_GAMEPAD_LEFT_SHOULDER,
-                "l2" => combination.left_trigger = true,
-                "l3" => combination.buttons |= XINPUT_GAMEPAD_LEFT_THUMB,
-                "r1" => combination.buttons |= XINPUT_GAMEPAD_RIGHT_SHOULDER,
-                "r2" => combination.right_trigger = true,
-                "r3" => combination.buttons |= XINPUT_GAMEPAD_RIGHT_THUMB,
-                "down" => combination.buttons |= XINPUT_GAMEPAD_DPAD_DOWN,
-                "up" => combination.buttons |= XINPUT_GAMEPAD_DPAD_UP,
-                "right" => combination.buttons |= XINPUT_GAMEPAD_DPAD_RIGHT,
-                "left" => combination.buttons |= XINPUT_GAMEPAD_DPAD_LEFT,
-                "a" => combination.buttons |= XINPUT_GAMEPAD_A,
-                "b" => combination.buttons |= XINPUT_GAMEPAD_B,
-                "x" => combination.buttons |= XINPUT_GAMEPAD_X,
-                "y" => combination.buttons |= XINPUT_GAMEPAD_Y,
-                "back" => combination.buttons |= XINPUT_GAMEPAD_BACK,
-                "start" => combination.buttons |= XINPUT_GAMEPAD_START,
-                other => return Err(format!("Not a controller button: {other}")),
+        let trimmed = value.trim_start();
+        if let Some((digits, rest)) = split_slot(trimmed) {
+            let start = value.len() - trimmed.len() + SLOT_PREFIX.len();
+            let span = start..start + digits.len();
+            if !digits.is_empty() {
+                combination.slot =
+                    Some(digits.parse().ok().filter(|&slot| slot < MAX_CONTROLLERS).ok_or_else(
+                        || ParseError::InvalidSlot { token: digits.to_string(), span },
+                    )?);
             }
+            offset = value.len() - rest.len();
+        }
+
+        let trimmed = value[offset..].trim_start();
+        if let Some((word, _)) = trimmed.split_once(char::is_whitespace) {
+            if word.eq_ignore_ascii_case(EXCLUSIVE) {
+                combination.exclusive = true;
+                offset = value.len() - trimmed.len() + word.len();
+            }
+        }
+
+        for (token, span) in error::tokens(&value[offset..], '+') {
+            let span = span.start + offset..span.end + offset;
+            let (name, threshold) = match token.split_once('>') {
+                Some((name, threshold)) => (name.trim_end(), Some(threshold)),
+                None => (token, None),
+            };
+
+            if name.is_empty() {
+                return Err(ParseError::Empty { span: span.start..span.start });
+            }
+
+            let name_span = span.start..span.start + name.len();
+            let input = find_by_name(REPR_MAP, name)
+                .ok_or_else(|| ParseError::unknown_button(name, name_span, all_names(REPR_MAP)))?;
+
+            combination.insert(input);
+
+            let Some(threshold) = threshold else {
+                continue;
+            };
+            let threshold_span = span.end - threshold.trim_start().len()..span.end;
+            let threshold = threshold.trim();
+            let trigger = match input {
+                Input::LeftTrigger => &mut combination.left_trigger,
+                Input::RightTrigger => &mut combination.right_trigger,
+                _ => {
+                    return Err(ParseError::UnexpectedThreshold { token: token.to_string(), span })
+                },
+            };
+            *trigger = Some(parse_threshold(threshold).ok_or_else(|| {
+                ParseError::InvalidThreshold { token: threshold.to_string(), span: threshold_span }
+            })?);
         }
 
         Ok(combination)
     }
 }
 
+/// Parses a percentage like `75%` into a raw trigger value.
+fn parse_threshold(s: &str) -> Option<u8> {
+    let percent = s.strip_suffix('%')?.trim_end().parse::<u32>().ok().filter(|&p| p < 100)?;
+    Some(((percent * 255 + 50) / 100) as u8)
+}
+
 impl TryFrom<String> for ControllerCombination {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         value.as_str().try_into()
     }
 }
 
+/// Turns successive controller snapshots into per-combination edges.
+///
+/// [`ControllerCombination::is_pressed`] is level-triggered: it stays true for
+/// as long as the inputs are held. The tracker remembers, for each tracked
+/// combination, whether it was held on the previous update and since when, so
+/// that a widget polling it every frame fires once per press. Snapshots whose
+/// `dwPacketNumber` didn't change since the last update are not evaluated
+/// again.
+#[derive(Debug, Clone, Default)]
+pub struct ControllerTracker {
+    tracked: Vec<Tracked>,
+    deadzone: Option<u16>,
+    now: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tracked {
+    combination: ControllerCombination,
+    packet_number: Option<u32>,
+    down: bool,
+    was_down: bool,
+    held_since: Option<f64>,
+}
+
+impl ControllerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the global [`stick_deadzone`] for the tracked combinations.
+    pub fn with_deadzone(mut self, deadzone: u16) -> Self {
+        self.deadzone = Some(deadzone);
+        self
+    }
+
+    /// Starts tracking `combination`, from the next update on. Tracking the
+    /// same combination twice has no effect.
+    pub fn track(&mut self, combination: ControllerCombination) {
+        if self.find(&combination).is_none() {
+            self.tracked.push(Tracked {
+                combination,
+                packet_number: None,
+                down: false,
+                was_down: false,
+                held_since: None,
+            });
+        }
+    }
+
+    /// Feeds the state of a single controller, `None` if it is disconnected,
+    /// and the time in seconds. All the combinations are matched against it,
+    /// whatever their slot.
+    pub fn update(&mut self, state: Option<&XINPUT_STATE>, now: f64) {
+        self.update_with(|_| state.copied(), now);
+    }
+
+    /// Feeds the controller state and time of the current frame of `input`.
+    /// Each combination is matched against the controller in its slot.
+    pub fn update_from(&mut self, input: &dyn InputSource) {
+        self.update_with(|combination| combination.controller_state(input), input.time());
+    }
+
+    fn update_with(
+        &mut self,
+        state_of: impl Fn(&ControllerCombination) -> Option<XINPUT_STATE>,
+        now: f64,
+    ) {
+        let deadzone = self.deadzone.unwrap_or_else(stick_deadzone);
+        self.now = now;
+
+        for tracked in &mut self.tracked {
+            let state = state_of(&tracked.combination);
+            let packet_number = state.map(|state| state.dwPacketNumber);
+            let changed = packet_number.is_none() || packet_number != tracked.packet_number;
+
+            tracked.was_down = tracked.down;
+            tracked.packet_number = packet_number;
+
+            if changed {
+                tracked.down = state
+                    .map(|state| tracked.combination.is_pressed_with(&state, deadzone))
+                    .unwrap_or(false);
+            }
+
+            match (tracked.was_down, tracked.down) {
+                (false, true) => tracked.held_since = Some(now),
+                (true, false) => tracked.held_since = None,
+                _ => {},
+            }
+        }
+    }
+
+    /// Forgets the held state, e.g. when the widget polling it is hidden.
+    pub fn reset(&mut self) {
+        for tracked in &mut self.tracked {
+            tracked.packet_number = None;
+            tracked.down = false;
+            tracked.was_down = false;
+            tracked.held_since = None;
+        }
+    }
+
+    pub fn is_down(&self, combination: &ControllerCombination) -> bool {
+        self.find(combination).is_some_and(|tracked| tracked.down)
+    }
+
+    /// Whether the combination became held on the last update.
+    pub fn just_pressed(&self, combination: &ControllerCombination) -> bool {
+        self.find(combination).is_some_and(|tracked| tracked.down && !tracked.was_down)
+    }
+
+    /// Whether the combination stopped being held on the last update.
+    pub fn just_released(&self, combination: &ControllerCombination) -> bool {
+        self.find(combination).is_some_and(|tracked| !tracked.down && tracked.was_down)
+    }
+
+    /// For how long the combination has been held, if it is.
+    pub fn held_for(&self, combination: &ControllerCombination) -> Option<Duration> {
+        let since = self.find(combination)?.held_since?;
+        Some(Duration::from_secs_f64((self.now - since).max(0.)))
+    }
+
+    fn find(&self, combination: &ControllerCombination) -> Option<&Tracked> {
+        self.tracked.iter().find(|tracked| tracked.combination == *combination)
+    }
+}
+
+/// An [`InputSource`] along with the state of the controllers polled by the
+/// host, e.g. through `XInputGetState`.
+pub struct WithController<'a> {
+    input: &'a dyn InputSource,
+    primary: Option<XINPUT_STATE>,
+    states: [Option<XINPUT_STATE>; MAX_CONTROLLERS as usize],
+}
+
+impl<'a> WithController<'a> {
+    /// Adds the state of a single controller, which is both the primary one
+    /// and the one in slot 0.
+    pub fn new(input: &'a dyn InputSource, state: Option<XINPUT_STATE>) -> Self {
+        let mut states = [None; MAX_CONTROLLERS as usize];
+        states[0] = state;
+        Self { input, primary: state, states }
+    }
+
+    /// Adds the state of the controller in each slot. The primary controller
+    /// is the first connected one.
+    pub fn with_slots(
+        input: &'a dyn InputSource,
+        states: [Option<XINPUT_STATE>; MAX_CONTROLLERS as usize],
+    ) -> Self {
+        Self { input, primary: states.iter().find_map(|state| *state), states }
+    }
+}
+
+impl InputSource for WithController<'_> {
+    fn is_key_down(&self, key: imgui::Key) -> bool {
+        self.input.is_key_down(key)
+    }
+
+    fn is_key_pressed(&self, key: imgui::Key) -> bool {
+        self.input.is_key_pressed(key)
+    }
+
+    fn is_key_pressed_no_repeat(&self, key: imgui::Key) -> bool {
+        self.input.is_key_pressed_no_repeat(key)
+    }
+
+    fn time(&self) -> f64 {
+        self.input.time()
+    }
+
+    fn key_analog_value(&self, key: imgui::Key) -> f32 {
+        self.input.key_analog_value(key)
+    }
+
+    fn controller_state_at(&self, slot: u8) -> Option<XINPUT_STATE> {
+        self.states.get(slot as usize).copied().flatten()
+    }
+
+    fn controller_state(&self) -> Option<XINPUT_STATE> {
+        self.primary
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::key::ScriptedInput;
 
     #[test]
     fn test_parse() {
@@ -91,5 +821,352 @@ mod tests {
         assert!(ControllerCombination::try_from("l2+ left").unwrap().is_pressed(&state));
         assert!(ControllerCombination::try_from(" l2 + left ").unwrap().is_pressed(&state));
         assert!(!ControllerCombination::try_from("l1+r1").unwrap().is_pressed(&state));
+        assert!(ControllerCombination::try_from("lb+ls+dpad_left").unwrap().is_pressed(&state));
+
+        let combination = ControllerCombination::try_from("LT+select+rs").unwrap();
+        assert_eq!(combination.to_string(), "l2+r3+back");
+        assert_eq!(combination.pretty().to_string(), "L2+R3+Back");
+    }
+
+    #[test]
+    fn test_sticks() {
+        let state = |buttons, lx, ly, rx, ry| XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD {
+                wButtons: buttons,
+                bLeftTrigger: 0,
+                bRightTrigger: 0,
+                sThumbLX: lx,
+                sThumbLY: ly,
+                sThumbRX: rx,
+                sThumbRY: ry,
+            },
+        };
+        let pressed = |combination: &str, state: &XINPUT_STATE, deadzone| {
+            ControllerCombination::try_from(combination).unwrap().is_pressed_with(state, deadzone)
+        };
+
+        let up = state(XINPUT_GAMEPAD_LEFT_THUMB, 0, 0, 0, 20000);
+        assert!(pressed("l3+rstick_up", &up, 8000));
+        assert!(!pressed("l3+rstick_up", &up, 25000));
+        assert!(!pressed("l3+rstick_down", &up, 8000));
+        assert!(!pressed("l3+lstick_up", &up, 8000));
+        assert!(!pressed("rstick_up", &state(XINPUT_GAMEPAD_LEFT_THUMB, 0, 0, 0, 8000), 8000));
+
+        let down_left = state(XINPUT_GAMEPAD_BACK, i16::MIN, i16::MIN, 0, 0);
+        assert!(pressed("back+lstick_left", &down_left, 8000));
+        assert!(pressed("back+ls_left+ls_down", &down_left, 8000));
+        assert!(!pressed("back+lstick_right", &down_left, 8000));
+        assert!(!pressed("lstick_left", &state(XINPUT_GAMEPAD_BACK, -8000, 0, 0, 0), 8000));
+
+        let combination = ControllerCombination::try_from("RS_UP+l3").unwrap();
+        assert_eq!(combination.to_string(), "l3+rstick_up");
+        assert_eq!(combination.pretty().to_string(), "L3+Right Stick Up");
+
+        let rstick_up = ControllerCombination::try_from("rstick_up").unwrap();
+        assert!(rstick_up.is_subset(&combination));
+        assert!(!combination.is_subset(&rstick_up));
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let state = |buttons, left_trigger, right_trigger| XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD {
+                wButtons: buttons,
+                bLeftTrigger: left_trigger,
+                bRightTrigger: right_trigger,
+                sThumbLX: 0,
+                sThumbLY: 0,
+                sThumbRX: 0,
+                sThumbRY: 0,
+            },
+        };
+        let pressed = |combination: &str, state: &XINPUT_STATE| {
+            ControllerCombination::try_from(combination).unwrap().is_pressed_with(state, 8000)
+        };
+
+        let half = state(XINPUT_GAMEPAD_A, 128, 0);
+        assert!(pressed("l2+a", &half));
+        assert!(pressed("l2>25%+a", &half));
+        assert!(!pressed("l2>75%+a", &half));
+        assert!(pressed("l2 > 75% + a", &state(XINPUT_GAMEPAD_A, 255, 0)));
+        assert!(pressed("r2>0%", &state(XINPUT_GAMEPAD_A, 0, 1)));
+
+        let combination = ControllerCombination::try_from("a+L2>75%+r2>12%").unwrap();
+        assert_eq!(combination.to_string(), "l2>75%+r2>12%+a");
+        assert_eq!(combination.pretty().to_string(), "L2>75%+R2>12%+A");
+        for percent in 0..100 {
+            let s = format!("l2>{percent}%");
+            assert_eq!(ControllerCombination::try_from(s.as_str()).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_exclusive() {
+        let state = |buttons| XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD {
+                wButtons: buttons,
+                bLeftTrigger: 0,
+                bRightTrigger: 0,
+                sThumbLX: 0,
+                sThumbLY: 20000,
+                sThumbRX: 0,
+                sThumbRY: 0,
+            },
+        };
+        let pressed = |combination: &str, state: &XINPUT_STATE| {
+            ControllerCombination::try_from(combination).unwrap().is_pressed_with(state, 25000)
+        };
+
+        let a = state(XINPUT_GAMEPAD_A);
+        let l1_a = state(XINPUT_GAMEPAD_LEFT_SHOULDER | XINPUT_GAMEPAD_A);
+        assert!(pressed("a", &l1_a));
+        assert!(pressed("exclusive a", &a));
+        assert!(!pressed("exclusive a", &l1_a));
+        assert!(pressed("exclusive l1+a", &l1_a));
+        assert!(!ControllerCombination::try_from("exclusive a").unwrap().is_pressed_with(&a, 8000));
+
+        let combination = ControllerCombination::try_from(" Exclusive  a+l1").unwrap();
+        assert!(combination.is_exclusive());
+        assert_eq!(combination.to_string(), "exclusive l1+a");
+        assert_eq!(combination.pretty().to_string(), "Exclusive L1+A");
+
+        let subset = |a: &str, b: &str| {
+            ControllerCombination::try_from(a)
+                .unwrap()
+                .is_subset(&ControllerCombination::try_from(b).unwrap())
+        };
+        assert!(!subset("exclusive a", "l1+a"));
+        assert!(subset("exclusive a", "a"));
+        assert!(subset("a", "exclusive l1+a"));
+        assert!(subset("l2+a", "l2>75%+a"));
+        assert!(subset("l2>75%+a", "l2+a"));
+    }
+
+    #[test]
+    fn test_tracker() {
+        let state = |packet_number, buttons| XINPUT_STATE {
+            dwPacketNumber: packet_number,
+            Gamepad: XINPUT_GAMEPAD { wButtons: buttons, ..Default::default() },
+        };
+        let l1_a = ControllerCombination::try_from("l1+a").unwrap();
+        let a = ControllerCombination::try_from("a").unwrap();
+        let b = ControllerCombination::try_from("b").unwrap();
+
+        let mut tracker = ControllerTracker::new();
+        tracker.track(l1_a);
+        tracker.track(a);
+        tracker.track(a);
+
+        tracker.update(Some(&state(1, XINPUT_GAMEPAD_A)), 0.);
+        assert!(tracker.just_pressed(&a) && tracker.is_down(&a));
+        assert!(!tracker.just_pressed(&l1_a) && !tracker.is_down(&l1_a));
+        assert_eq!(tracker.held_for(&a), Some(Duration::ZERO));
+
+        let both = XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_LEFT_SHOULDER;
+        tracker.update(Some(&state(2, both)), 0.5);
+        assert!(!tracker.just_pressed(&a) && tracker.is_down(&a));
+        assert!(tracker.just_pressed(&l1_a));
+        assert_eq!(tracker.held_for(&a), Some(Duration::from_millis(500)));
+        assert_eq!(tracker.held_for(&l1_a), Some(Duration::ZERO));
+
+        // An unchanged packet is not evaluated again, and its edges are gone.
+        tracker.update(Some(&state(2, XINPUT_GAMEPAD_B)), 1.);
+        assert!(!tracker.just_pressed(&l1_a) && tracker.is_down(&l1_a));
+        assert_eq!(tracker.held_for(&l1_a), Some(Duration::from_millis(500)));
+
+        tracker.update(Some(&state(3, XINPUT_GAMEPAD_A)), 1.5);
+        assert!(tracker.just_released(&l1_a) && !tracker.is_down(&l1_a));
+        assert!(!tracker.just_released(&a));
+        assert_eq!(tracker.held_for(&l1_a), None);
+
+        tracker.update(None, 2.);
+        assert!(tracker.just_released(&a));
+        tracker.update(None, 2.5);
+        assert!(!tracker.just_released(&a));
+
+        // Untracked combinations are never pressed.
+        tracker.update(Some(&state(4, XINPUT_GAMEPAD_B)), 3.);
+        assert!(!tracker.just_pressed(&b) && !tracker.is_down(&b));
+        tracker.track(b);
+        tracker.update(Some(&state(4, XINPUT_GAMEPAD_B)), 3.5);
+        assert!(tracker.just_pressed(&b));
+    }
+
+    #[test]
+    fn test_slots() {
+        let state = |buttons| XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD { wButtons: buttons, ..Default::default() },
+        };
+        let combination = |s| ControllerCombination::try_from(s).unwrap();
+
+        let pad1 = combination("pad1:l1+a");
+        assert_eq!(pad1.slot(), Some(1));
+        assert_eq!(pad1.to_string(), "pad1:l1+a");
+        assert_eq!(combination("PAD3: exclusive a").to_string(), "pad3:exclusive a");
+        assert_eq!(combination("pad:a"), combination("a"));
+
+        let mut input = ScriptedInput::new();
+        input.set_controller_state(Some(state(XINPUT_GAMEPAD_A)));
+        input.set_controller_state_at(1, Some(state(XINPUT_GAMEPAD_LEFT_SHOULDER)));
+        input.set_controller_state_at(MAX_CONTROLLERS, Some(state(XINPUT_GAMEPAD_B)));
+        assert_eq!(input.controller_state_at(MAX_CONTROLLERS), None);
+        let state_of = |s| combination(s).controller_state(&input).map(|s| s.Gamepad.wButtons);
+        assert_eq!(state_of("a"), Some(XINPUT_GAMEPAD_A));
+        assert_eq!(state_of("pad0:a"), Some(XINPUT_GAMEPAD_A));
+        assert_eq!(state_of("pad1:a"), Some(XINPUT_GAMEPAD_LEFT_SHOULDER));
+        assert_eq!(state_of("pad2:a"), None);
+
+        let mut tracker = ControllerTracker::new();
+        tracker.track(combination("pad1:l1"));
+        tracker.track(combination("l1"));
+        tracker.update_from(&input);
+        assert!(tracker.just_pressed(&combination("pad1:l1")));
+        assert!(!tracker.is_down(&combination("l1")));
+
+        let input =
+            WithController::with_slots(&input, [None, None, Some(state(XINPUT_GAMEPAD_B)), None]);
+        assert_eq!(input.controller_state().map(|s| s.Gamepad.wButtons), Some(XINPUT_GAMEPAD_B));
+        assert_eq!(input.controller_state_at(0), None);
+
+        // Analog keys read through the wrapper keep their values.
+        let mut keys = ScriptedInput::new();
+        keys.set_analog(imgui::Key::GamepadL2, 0.6);
+        keys.set_analog(imgui::Key::GamepadRStickLeft, 0.5);
+        let input = WithController::new(&keys, None);
+        assert_eq!(input.key_analog_value(imgui::Key::GamepadL2), 0.6);
+        assert!(combination("l2>50%").is_pressed_on_keys(&input));
+        assert!(!combination("l2>75%").is_pressed_on_keys(&input));
+        assert!(combination("rstick_left").is_pressed_on_keys(&input));
+
+        assert!(combination("l1").is_subset(&combination("pad1:l1+a")));
+        assert!(combination("pad1:l1").is_subset(&combination("pad1:l1+a")));
+        assert!(!combination("pad0:l1").is_subset(&combination("pad1:l1+a")));
+
+        let err = ControllerCombination::try_from("pad4:a").unwrap_err();
+        assert_eq!(err, ParseError::InvalidSlot { token: "4".into(), span: 3..4 });
+    }
+
+    #[test]
+    fn test_naming_schemes() {
+        struct Label(ControllerCombination, NamingScheme);
+
+        impl std::fmt::Display for Label {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt_with(f, Some(self.1))
+            }
+        }
+
+        let label =
+            |s, scheme| Label(ControllerCombination::try_from(s).unwrap(), scheme).to_string();
+
+        let combination = ControllerCombination::try_from("cross+l1").unwrap();
+        assert_eq!(combination, ControllerCombination::try_from("l1+a").unwrap());
+        assert_eq!(combination.to_string(), "l1+a");
+        assert_eq!(label("l1+cross", NamingScheme::PlayStation), "L1+Cross");
+        assert_eq!(label("l1+cross", NamingScheme::Xbox), "L1+A");
+        assert_eq!(
+            label("circle+square+triangle+options+share", NamingScheme::PlayStation),
+            "Circle+Square+Triangle+Share+Options"
+        );
+        assert_eq!(
+            ControllerCombination::try_from("touchpad").unwrap(),
+            ControllerCombination::try_from("back").unwrap()
+        );
+        assert_eq!(
+            label("pad2:exclusive r2>50%+y", NamingScheme::PlayStation),
+            "Pad 2: Exclusive R2>50%+Triangle"
+        );
+
+        assert_eq!("PlayStation".parse::<NamingScheme>().unwrap(), NamingScheme::PlayStation);
+        assert_eq!(NamingScheme::PlayStation.to_string(), "playstation");
+        assert_eq!("xobx".parse::<NamingScheme>().unwrap_err().suggestions(), ["xbox"]);
+    }
+
+    #[test]
+    fn test_gamepad_keys() {
+        let pressed = |s, input: &ScriptedInput| {
+            ControllerCombination::try_from(s).unwrap().is_pressed_on_keys(input)
+        };
+
+        let mut input = ScriptedInput::new();
+        input.press(imgui::Key::GamepadL1);
+        input.press(imgui::Key::GamepadFaceDown);
+        input.set_analog(imgui::Key::GamepadL2, 0.5);
+        input.set_analog(imgui::Key::GamepadRStickUp, 0.8);
+        input.set_analog(imgui::Key::GamepadLStickLeft, 0.05);
+
+        assert!(pressed("l1+a", &input));
+        assert!(pressed("l1+cross+l2", &input));
+        assert!(pressed("l2>40%+a", &input));
+        assert!(!pressed("l2>60%+a", &input));
+        assert!(!pressed("l1+b", &input));
+        assert!(pressed("rstick_up", &input));
+        assert!(!pressed("lstick_left", &input));
+        assert!(!pressed("exclusive a", &input));
+
+        let gamepad = gamepad_from_keys(&input);
+        assert_eq!(gamepad.wButtons, XINPUT_GAMEPAD_LEFT_SHOULDER | XINPUT_GAMEPAD_A);
+        assert_eq!((gamepad.bLeftTrigger, gamepad.bRightTrigger), (128, 0));
+        assert_eq!((gamepad.sThumbLX, gamepad.sThumbLY), (-1638, 0));
+        assert_eq!((gamepad.sThumbRX, gamepad.sThumbRY), (0, 26214));
+
+        input.release_all();
+        input.press(imgui::Key::GamepadFaceDown);
+        assert!(pressed("exclusive a", &input));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(_, names, _) in REPR_MAP {
+            let repr = names[0];
+            let combination = ControllerCombination::try_from(repr).unwrap();
+            assert_eq!(combination.to_string(), repr);
+
+            for &(_, names2, _) in REPR_MAP {
+                let repr2 = names2[0];
+                let combination =
+                    ControllerCombination::try_from(format!("{repr}+{repr2}")).unwrap();
+                let formatted = combination.to_string();
+                let reparsed = ControllerCombination::try_from(formatted.as_str()).unwrap();
+                assert_eq!(reparsed, combination);
+                assert_eq!(reparsed.to_string(), formatted);
+            }
+        }
+
+        assert_eq!(ControllerCombination::try_from(" A + L1 ").unwrap().to_string(), "l1+a");
+        assert_eq!(
+            ControllerCombination::try_from("start+r2+l2+a+up").unwrap().to_string(),
+            "l2+r2+up+a+start"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(ControllerCombination::try_from(""), Err(ParseError::Empty { span: 0..0 }));
+        assert_eq!(ControllerCombination::try_from("l1+ "), Err(ParseError::Empty { span: 4..4 }));
+
+        let err = ControllerCombination::try_from("l1 + strat").unwrap_err();
+        assert_eq!(err.token(), "strat");
+        assert_eq!(err.span(), 5..10);
+        assert_eq!(err.suggestions(), ["start"]);
+        assert_eq!(
+            err.to_string(),
+            "Not a controller button: \"strat\" at 5..10, did you mean `start`?"
+        );
+
+        let err = ControllerCombination::try_from("a+l2>lots").unwrap_err();
+        assert_eq!(err, ParseError::InvalidThreshold { token: "lots".into(), span: 5..9 });
+        let err = ControllerCombination::try_from("l2> 100%").unwrap_err();
+        assert_eq!((err.token(), err.span()), ("100%", 4..8));
+        let err = ControllerCombination::try_from("exclusive l2+a>50%").unwrap_err();
+        assert_eq!(err, ParseError::UnexpectedThreshold { token: "a>50%".into(), span: 13..18 });
+        let err = ControllerCombination::try_from("l1+>50%").unwrap_err();
+        assert_eq!(err, ParseError::Empty { span: 3..3 });
+        let err = ControllerCombination::try_from("exclusiv a").unwrap_err();
+        assert_eq!(err.token(), "exclusiv a");
     }
 }
@@ -0,0 +1,388 @@
+//! Errors from parsing key and controller bindings, and widget configs.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+/// A binding string that could not be parsed.
+///
+/// Spans are byte ranges into the whole string that was parsed, so that an
+/// editor or a config loader can point at the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The binding, a stroke or a `+`-separated part of it is empty.
+    Empty {
+        span: Range<usize>,
+    },
+    UnknownKey {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    UnknownModifier {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    UnknownButton {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    UnknownTrigger {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    UnknownPolicy {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    UnknownScheme {
+        token: String,
+        span: Range<usize>,
+        suggestions: Vec<&'static str>,
+    },
+    /// A duration without a unit, negative, or not a number.
+    InvalidDuration {
+        token: String,
+        span: Range<usize>,
+    },
+    /// A duration given to a trigger that takes none, e.g. `press:1s`.
+    UnexpectedDuration {
+        token: String,
+        span: Range<usize>,
+    },
+    /// A trigger threshold that is not a percentage from 0% to 99%.
+    InvalidThreshold {
+        token: String,
+        span: Range<usize>,
+    },
+    /// A threshold given to an input that is not analog, e.g. `a>50%`.
+    UnexpectedThreshold {
+        token: String,
+        span: Range<usize>,
+    },
+    /// A controller slot that is not a number from 0 to 3, e.g. `pad4:a`.
+    InvalidSlot {
+        token: String,
+        span: Range<usize>,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn unknown_key(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownKey { token: token.to_string(), span, suggestions }
+    }
+
+    pub(crate) fn unknown_modifier(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownModifier { token: token.to_string(), span, suggestions }
+    }
+
+    pub(crate) fn unknown_button(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownButton { token: token.to_string(), span, suggestions }
+    }
+
+    pub(crate) fn unknown_trigger(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownTrigger { token: token.to_string(), span, suggestions }
+    }
+
+    pub(crate) fn unknown_policy(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownPolicy { token: token.to_string(), span, suggestions }
+    }
+
+    pub(crate) fn unknown_scheme(
+        token: &str,
+        span: Range<usize>,
+        names: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        let suggestions = suggest(token, names);
+        ParseError::UnknownScheme { token: token.to_string(), span, suggestions }
+    }
+
+    /// The offending token; empty for [`ParseError::Empty`].
+    pub fn token(&self) -> &str {
+        match self {
+            ParseError::Empty { .. } => "",
+            ParseError::UnknownKey { token, .. }
+            | ParseError::UnknownModifier { token, .. }
+            | ParseError::UnknownButton { token, .. }
+            | ParseError::UnknownTrigger { token, .. }
+            | ParseError::UnknownPolicy { token, .. }
+            | ParseError::UnknownScheme { token, .. }
+            | ParseError::InvalidDuration { token, .. }
+            | ParseError::UnexpectedDuration { token, .. }
+            | ParseError::InvalidThreshold { token, .. }
+            | ParseError::UnexpectedThreshold { token, .. }
+            | ParseError::InvalidSlot { token, .. } => token,
+        }
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ParseError::Empty { span }
+            | ParseError::UnknownKey { span, .. }
+            | ParseError::UnknownModifier { span, .. }
+            | ParseError::UnknownButton { span, .. }
+            | ParseError::UnknownTrigger { span, .. }
+            | ParseError::UnknownPolicy { span, .. }
+            | ParseError::UnknownScheme { span, .. }
+            | ParseError::InvalidDuration { span, .. }
+            | ParseError::UnexpectedDuration { span, .. }
+            | ParseError::InvalidThreshold { span, .. }
+            | ParseError::UnexpectedThreshold { span, .. }
+            | ParseError::InvalidSlot { span, .. } => span.clone(),
+        }
+    }
+
+    /// Known names close to the offending token, best match first.
+    pub fn suggestions(&self) -> &[&'static str] {
+        match self {
+            ParseError::UnknownKey { suggestions, .. }
+            | ParseError::UnknownModifier { suggestions, .. }
+            | ParseError::UnknownButton { suggestions, .. }
+            | ParseError::UnknownTrigger { suggestions, .. }
+            | ParseError::UnknownPolicy { suggestions, .. }
+            | ParseError::UnknownScheme { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
+
+    /// Moves the span by `offset` bytes, for errors from parsing a substring.
+    pub(crate) fn offset(mut self, offset: usize) -> Self {
+        match &mut self {
+            ParseError::Empty { span }
+            | ParseError::UnknownKey { span, .. }
+            | ParseError::UnknownModifier { span, .. }
+            | ParseError::UnknownButton { span, .. }
+            | ParseError::UnknownTrigger { span, .. }
+            | ParseError::UnknownPolicy { span, .. }
+            | ParseError::UnknownScheme { span, .. }
+            | ParseError::InvalidDuration { span, .. }
+            | ParseError::UnexpectedDuration { span, .. }
+            | ParseError::InvalidThreshold { span, .. }
+            | ParseError::UnexpectedThreshold { span, .. }
+            | ParseError::InvalidSlot { span, .. } => {
+                *span = span.start + offset..span.end + offset
+            },
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.span();
+        match self {
+            ParseError::Empty { .. } => write!(f, "Empty binding at {span:?}")?,
+            ParseError::UnknownKey { token, .. } => {
+                write!(f, "Could not find key \"{token}\" at {span:?}")?
+            },
+            ParseError::UnknownModifier { token, .. } => {
+                write!(f, "Could not find modifier \"{token}\" at {span:?}")?
+            },
+            ParseError::UnknownButton { token, .. } => {
+                write!(f, "Not a controller button: \"{token}\" at {span:?}")?
+            },
+            ParseError::UnknownTrigger { token, .. } => {
+                write!(f, "Could not find trigger \"{token}\" at {span:?}")?
+            },
+            ParseError::UnknownPolicy { token, .. } => {
+                write!(f, "Could not find match policy \"{token}\" at {span:?}")?
+            },
+            ParseError::UnknownScheme { token, .. } => {
+                write!(f, "Could not find naming scheme \"{token}\" at {span:?}")?
+            },
+            ParseError::InvalidDuration { token, .. } => write!(
+                f,
+                "Could not parse duration \"{token}\" at {span:?}, expected e.g. 500ms or 1.5s"
+            )?,
+            ParseError::UnexpectedDuration { token, .. } => {
+                write!(f, "Trigger takes no duration: \"{token}\" at {span:?}")?
+            },
+            ParseError::InvalidThreshold { token, .. } => {
+                write!(f, "Could not parse threshold \"{token}\" at {span:?}, expected e.g. 75%")?
+            },
+            ParseError::UnexpectedThreshold { token, .. } => {
+                write!(f, "Only l2 and r2 take a threshold: \"{token}\" at {span:?}")?
+            },
+            ParseError::InvalidSlot { token, .. } => {
+                write!(f, "Controller slot must be 0 to 3: \"{token}\" at {span:?}")?
+            },
+        }
+
+        match self.suggestions() {
+            [] => Ok(()),
+            [suggestion] => write!(f, ", did you mean `{suggestion}`?"),
+            [suggestions @ .., last] => {
+                write!(f, ", did you mean ")?;
+                for suggestion in suggestions {
+                    write!(f, "`{suggestion}`, ")?;
+                }
+                write!(f, "or `{last}`?")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An entry of a widget config that could not be built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Where the entry is in the config, e.g. `[2].children[0]`.
+    pub path: String,
+    /// The label of the entry, if it has one, to find it in the file.
+    pub label: Option<String>,
+    pub kind: ConfigErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigErrorKind {
+    /// A provider name that the game didn't register, e.g. a misspelled flag.
+    UnknownProvider { provider: &'static str, name: String, suggestions: Vec<String> },
+    /// A binding field that could not be parsed.
+    InvalidBinding { field: &'static str, error: ParseError },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Widget {}", self.path)?;
+        if let Some(label) = &self.label {
+            write!(f, " (\"{label}\")")?;
+        }
+
+        match &self.kind {
+            ConfigErrorKind::UnknownProvider { provider, name, suggestions } => {
+                write!(f, ": could not find {provider} \"{name}\"")?;
+                match suggestions.as_slice() {
+                    [] => Ok(()),
+                    [suggestion] => write!(f, ", did you mean `{suggestion}`?"),
+                    [suggestions @ .., last] => {
+                        write!(f, ", did you mean ")?;
+                        for suggestion in suggestions {
+                            write!(f, "`{suggestion}`, ")?;
+                        }
+                        write!(f, "or `{last}`?")
+                    },
+                }
+            },
+            ConfigErrorKind::InvalidBinding { field, error } => {
+                write!(f, ": invalid {field}: {error}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Splits `s` on `separator` into trimmed tokens along with their spans.
+pub(crate) fn tokens(s: &str, separator: char) -> impl Iterator<Item = (&str, Range<usize>)> {
+    let mut start = 0;
+    s.split(separator).map(move |token| {
+        let offset = start + token.len() - token.trim_start().len();
+        start += token.len() + separator.len_utf8();
+        let token = token.trim();
+        (token, offset..offset + token.len())
+    })
+}
+
+/// The names closest to `token` by edit distance, best first.
+pub(crate) fn suggest<'a>(token: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    const MAX_SUGGESTIONS: usize = 3;
+
+    let token = token.to_lowercase();
+    let max_distance = (token.chars().count() / 3).max(1);
+
+    // Tables can list the same name twice, e.g. as a key and a modifier.
+    let mut seen = HashSet::new();
+    let mut candidates = names
+        .into_iter()
+        .filter(|name| seen.insert(*name))
+        .map(|name| (edit_distance(&token, name), name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|&(distance, _)| distance);
+    candidates.into_iter().take(MAX_SUGGESTIONS).map(|(_, name)| name).collect()
+}
+
+/// Edit distance counting insertions, deletions, substitutions and swaps of
+/// adjacent chars, so that typos like `ctlr` are one edit away from `ctrl`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(edit_distance("pgdwn", "pgdown"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("ctlr", "ctrl"), 1);
+
+        let suggest = |token| suggest(token, ["pgup", "pgdown", "home", "end", "f1", "f2"]);
+        assert_eq!(suggest("pgdwn"), ["pgdown"]);
+        assert_eq!(suggest("PageDown"), ["pgdown"]);
+        assert_eq!(suggest("f3"), ["f1", "f2"]);
+        assert_eq!(suggest("xyzzy"), Vec::<&str>::new());
+
+        assert_eq!(super::suggest("f3", ["f1", "f2", "f1", "f4", "f1"]), ["f1", "f2", "f4"]);
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(tokens(" ctrl + f", '+').collect::<Vec<_>>(), [("ctrl", 1..5), ("f", 8..9)]);
+        assert_eq!(tokens("a+", '+').collect::<Vec<_>>(), [("a", 0..1), ("", 2..2)]);
+        assert_eq!(tokens("", '+').collect::<Vec<_>>(), [("", 0..0)]);
+    }
+}
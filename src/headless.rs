@@ -0,0 +1,505 @@
+//! Runs widgets on a bare imgui context, without a window or a renderer, so
+//! that their behavior can be asserted from `cargo test`.
+//!
+//! Input is scripted relative to the next frame, e.g. a key tap presses the
+//! key on the next frame and releases it on the one after. The items drawn on
+//! the last frame can be queried by their text: imgui doesn't expose its item
+//! tree, so it is rebuilt from the glyphs in the draw data.
+//!
+//! With the `snapshot` feature, the last frame can also be rendered to an
+//! image with `Harness::snapshot`, see the `snapshot` module.
+//!
+//! Only one imgui context can exist at a time, so harnesses in the same
+//! process wait for each other.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, Sender};
+use imgui::{Condition, DrawData, ImColor32, MouseButton, StyleColor, Ui, WindowFlags};
+use parking_lot::{Mutex, MutexGuard};
+use windows::Win32::UI::Input::XboxController::{
+    XINPUT_GAMEPAD, XINPUT_GAMEPAD_BUTTON_FLAGS, XINPUT_STATE,
+};
+
+use crate::key::{Key, ScriptedInput};
+use crate::log::LogEvent;
+use crate::replay::{feed_frame, RecordedFrame};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::{Image, Rasterizer, Texture};
+use crate::widgets::Widget;
+
+static CONTEXT: Mutex<()> = Mutex::new(());
+
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+/// Position and RGBA color of a vertex.
+type Vertex = ([f32; 2], [u8; 4]);
+
+/// A line of text drawn on the last frame, e.g. the label of a button.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub text: String,
+    /// Top left corner of the text.
+    pub min: [f32; 2],
+    /// Bottom right corner of the text.
+    pub max: [f32; 2],
+    /// RGBA color of the text.
+    pub color: [u8; 4],
+}
+
+impl Item {
+    pub fn center(&self) -> [f32; 2] {
+        [(self.min[0] + self.max[0]) * 0.5, (self.min[1] + self.max[1]) * 0.5]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Press(Key),
+    Release(Key),
+    MouseMove([f32; 2]),
+    Mouse(MouseButton, bool),
+    Controller(Option<XINPUT_STATE>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    codepoint: char,
+    x0: f32,
+    y0: f32,
+    advance_x: f32,
+    uv1: [f32; 2],
+}
+
+/// The glyphs of the default font, by the top left corner of their texture
+/// coordinates.
+struct Glyphs {
+    glyphs: HashMap<(u32, u32), Glyph>,
+    font_size: f32,
+    space_advance: f32,
+}
+
+impl Glyphs {
+    fn new(ctx: &mut imgui::Context) -> Self {
+        let atlas = ctx.fonts();
+        let id = atlas.fonts()[0];
+        let font = atlas.get_font(id).unwrap();
+        // SAFETY: `Font` has the memory layout of `ImFont`.
+        let font = unsafe { &*(font as *const imgui::Font as *const imgui::sys::ImFont) };
+        let raw =
+            unsafe { std::slice::from_raw_parts(font.Glyphs.Data, font.Glyphs.Size as usize) };
+
+        let mut glyphs = HashMap::new();
+        let mut space_advance = font.FallbackAdvanceX;
+        for glyph in raw {
+            let Some(codepoint) = char::from_u32(glyph.Codepoint()) else {
+                continue;
+            };
+            if codepoint == ' ' {
+                space_advance = glyph.AdvanceX;
+            }
+            if glyph.Visible() != 0 {
+                glyphs.insert((glyph.U0.to_bits(), glyph.V0.to_bits()), Glyph {
+                    codepoint,
+                    x0: glyph.X0,
+                    y0: glyph.Y0,
+                    advance_x: glyph.AdvanceX,
+                    uv1: [glyph.U1, glyph.V1],
+                });
+            }
+        }
+
+        Self { glyphs, font_size: font.FontSize, space_advance }
+    }
+
+    /// Finds the text runs in the draw data, and the vertices of everything
+    /// else.
+    fn scan(&self, draw_data: &DrawData) -> (Vec<Item>, Vec<Vertex>) {
+        let mut items = Vec::new();
+        let mut shapes = Vec::new();
+
+        for draw_list in draw_data.draw_lists() {
+            let vtx = draw_list.vtx_buffer();
+            let mut run: Option<(Item, f32)> = None;
+            let mut interrupted = false;
+            let mut i = 0;
+
+            while i < vtx.len() {
+                let v = &vtx[i];
+                let glyph = self
+                    .glyphs
+                    .get(&(v.uv[0].to_bits(), v.uv[1].to_bits()))
+                    .filter(|glyph| vtx.get(i + 2).is_some_and(|v2| v2.uv == glyph.uv1));
+
+                let Some(glyph) = glyph else {
+                    shapes.push((v.pos, v.col));
+                    interrupted = true;
+                    i += 1;
+                    continue;
+                };
+
+                let pen = [v.pos[0] - glyph.x0, v.pos[1] - glyph.y0];
+
+                // Continue the current run if nothing else was drawn since the
+                // previous glyph, and the glyph follows it on the same line, after
+                // at most a few spaces.
+                let gap = run.as_ref().filter(|_| !interrupted).and_then(|(item, end)| {
+                    let gap = pen[0] - end;
+                    let spaces = (gap / self.space_advance).round();
+                    ((pen[1] - item.min[1]).abs() < 0.01
+                        && (gap - spaces * self.space_advance).abs() < 0.01
+                        && (0. ..=4.).contains(&spaces))
+                    .then_some(spaces as usize)
+                });
+                interrupted = false;
+
+                match (&mut run, gap) {
+                    (Some((item, end)), Some(spaces)) => {
+                        item.text.extend(std::iter::repeat_n(' ', spaces));
+                        item.text.push(glyph.codepoint);
+                        *end = pen[0] + glyph.advance_x;
+                        item.max[0] = *end;
+                    },
+                    _ => {
+                        items.extend(run.take().map(|(item, _)| item));
+                        let end = pen[0] + glyph.advance_x;
+                        let item = Item {
+                            text: glyph.codepoint.to_string(),
+                            min: pen,
+                            max: [end, pen[1] + self.font_size],
+                            color: v.col,
+                        };
+                        run = Some((item, end));
+                    },
+                }
+
+                i += 4;
+            }
+
+            items.extend(run.map(|(item, _)| item));
+        }
+
+        (items, shapes)
+    }
+}
+
+/// A bare imgui context driven frame by frame with scripted input.
+pub struct Harness {
+    ctx: imgui::Context,
+    _lock: MutexGuard<'static, ()>,
+    glyphs: Glyphs,
+    #[cfg(feature = "snapshot")]
+    rasterizer: Rasterizer,
+    input: ScriptedInput,
+    previous: Option<RecordedFrame>,
+    script: VecDeque<Vec<Step>>,
+    items: Vec<Item>,
+    shapes: Vec<Vertex>,
+    tx: Sender<LogEvent>,
+    rx: Receiver<LogEvent>,
+    logs: Vec<LogEvent>,
+}
+
+impl Harness {
+    /// Creates a harness with a 1280x720 display, waiting for any other
+    /// harness to be dropped.
+    pub fn new() -> Self {
+        let lock = CONTEXT.lock();
+
+        let mut ctx = imgui::Context::create();
+        ctx.set_ini_filename(None);
+        ctx.io_mut().display_size = [1280., 720.];
+        ctx.io_mut().delta_time = FRAME_TIME.as_secs_f32();
+        #[cfg(feature = "snapshot")]
+        let rasterizer = {
+            let atlas = ctx.fonts();
+            let texture = atlas.build_rgba32_texture();
+            let texture = Texture {
+                width: texture.width,
+                height: texture.height,
+                data: texture.data.to_vec(),
+            };
+            Rasterizer::new().with_texture(atlas.tex_id, texture)
+        };
+        // The fonts must be built before the first frame.
+        #[cfg(not(feature = "snapshot"))]
+        ctx.fonts().build_rgba32_texture();
+        let glyphs = Glyphs::new(&mut ctx);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+
+        Self {
+            ctx,
+            _lock: lock,
+            glyphs,
+            #[cfg(feature = "snapshot")]
+            rasterizer,
+            input: ScriptedInput::new(),
+            previous: None,
+            script: VecDeque::new(),
+            items: Vec::new(),
+            shapes: Vec::new(),
+            tx,
+            rx,
+            logs: Vec::new(),
+        }
+    }
+
+    pub fn with_display_size(mut self, size: [f32; 2]) -> Self {
+        self.ctx.io_mut().display_size = size;
+        self
+    }
+
+    pub fn context(&mut self) -> &mut imgui::Context {
+        &mut self.ctx
+    }
+
+    fn schedule(&mut self, frame: usize, step: Step) {
+        while self.script.len() <= frame {
+            self.script.push_back(Vec::new());
+        }
+        self.script[frame].push(step);
+    }
+
+    /// Holds a key, e.g. `"ctrl+f"`, from the next frame on.
+    pub fn press(&mut self, key: &str) {
+        self.schedule(0, Step::Press(parse_key(key)));
+    }
+
+    /// Releases a key and its modifiers on the next frame.
+    pub fn release(&mut self, key: &str) {
+        self.schedule(0, Step::Release(parse_key(key)));
+    }
+
+    /// Presses a key on the next frame and releases it on the one after.
+    pub fn tap(&mut self, key: &str) {
+        let key = parse_key(key);
+        self.schedule(0, Step::Press(key));
+        self.schedule(1, Step::Release(key));
+    }
+
+    pub fn mouse_move(&mut self, pos: [f32; 2]) {
+        self.schedule(0, Step::MouseMove(pos));
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton) {
+        self.schedule(0, Step::Mouse(button, true));
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton) {
+        self.schedule(0, Step::Mouse(button, false));
+    }
+
+    /// Moves the mouse over the item with the given text on the next frame,
+    /// then clicks it over the two following frames. Returns `false` if there
+    /// was no such item on the last frame.
+    pub fn click(&mut self, text: &str) -> bool {
+        let Some(center) = self.item(text).map(Item::center) else {
+            return false;
+        };
+
+        self.schedule(0, Step::MouseMove(center));
+        self.schedule(1, Step::Mouse(MouseButton::Left, true));
+        self.schedule(2, Step::Mouse(MouseButton::Left, false));
+        true
+    }
+
+    /// Connects the primary controller with the given state from the next
+    /// frame on, or disconnects it.
+    pub fn set_controller_state(&mut self, state: Option<XINPUT_STATE>) {
+        self.schedule(0, Step::Controller(state));
+    }
+
+    /// Holds controller buttons on the next frame and releases them on the
+    /// one after.
+    pub fn tap_buttons(&mut self, buttons: XINPUT_GAMEPAD_BUTTON_FLAGS) {
+        let state = |buttons| XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD { wButtons: buttons, ..Default::default() },
+        };
+        self.schedule(0, Step::Controller(Some(state(buttons))));
+        self.schedule(1, Step::Controller(Some(state(XINPUT_GAMEPAD_BUTTON_FLAGS(0)))));
+    }
+
+    /// Runs a frame, calling `f` inside a window covering the display.
+    pub fn frame(&mut self, f: impl FnOnce(&Ui)) {
+        let steps = self.script.pop_front().unwrap_or_default();
+        for step in &steps {
+            match *step {
+                Step::Press(key) => self.input.press_key(&key),
+                Step::Release(key) => self.input.release_key(&key),
+                Step::Controller(state) => self.input.set_controller_state(state),
+                Step::MouseMove(_) | Step::Mouse(..) => {},
+            }
+        }
+
+        let frame = RecordedFrame::capture(&self.input);
+        let io = self.ctx.io_mut();
+        feed_frame(io, self.previous.as_ref(), &frame);
+        for step in steps {
+            match step {
+                Step::MouseMove(pos) => io.add_mouse_pos_event(pos),
+                Step::Mouse(button, down) => io.add_mouse_button_event(button, down),
+                _ => {},
+            }
+        }
+        let display_size = io.display_size;
+
+        let ui = self.ctx.new_frame();
+        ui.window("##harness")
+            .position([0., 0.], Condition::Always)
+            .size(display_size, Condition::Always)
+            .flags(WindowFlags::NO_DECORATION | WindowFlags::NO_BRING_TO_FRONT_ON_FOCUS)
+            .build(|| f(ui));
+
+        let draw_data = self.ctx.render();
+        (self.items, self.shapes) = self.glyphs.scan(draw_data);
+
+        self.previous = Some(frame);
+        self.input.next_frame(FRAME_TIME);
+    }
+
+    /// Renders the last frame in software. Blank before the first frame.
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self) -> Image {
+        // SAFETY: the draw data of the last frame stays valid until the next
+        // frame starts, which needs `&mut self`.
+        let draw_data = unsafe { imgui::sys::igGetDrawData().as_ref() };
+        match draw_data {
+            Some(draw_data) if draw_data.Valid => {
+                let draw_data = unsafe { &*(draw_data as *const _ as *const DrawData) };
+                self.rasterizer.render(draw_data)
+            },
+            _ => {
+                let [width, height] = self.ctx.io().display_size;
+                Image::new(width as u32, height as u32, self.rasterizer.clear_color())
+            },
+        }
+    }
+
+    /// Runs `frames` frames of the widget as if the menu was open: renders
+    /// it, interacts with it and collects its logs.
+    pub fn run(&mut self, widget: &mut dyn Widget, frames: usize) {
+        for _ in 0..frames {
+            self.frame(|ui| {
+                widget.render(ui);
+                widget.interact(ui);
+            });
+            self.collect_logs(widget);
+        }
+    }
+
+    /// Runs `frames` frames of the widget as if the menu was closed.
+    pub fn run_closed(&mut self, widget: &mut dyn Widget, frames: usize) {
+        for _ in 0..frames {
+            self.frame(|ui| {
+                widget.render_closed(ui);
+                widget.interact(ui);
+            });
+            self.collect_logs(widget);
+        }
+    }
+
+    fn collect_logs(&mut self, widget: &mut dyn Widget) {
+        widget.log(self.tx.clone());
+        self.logs.extend(self.rx.try_iter());
+    }
+
+    /// The events logged by the widgets so far.
+    pub fn logs(&self) -> &[LogEvent] {
+        &self.logs
+    }
+
+    /// The messages logged by the widgets so far.
+    pub fn messages(&self) -> Vec<&str> {
+        self.logs.iter().map(|event| event.message.as_str()).collect()
+    }
+
+    pub fn clear_logs(&mut self) {
+        self.logs.clear();
+    }
+
+    /// The lines of text drawn on the last frame, in drawing order.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// The item with exactly this text that was drawn last, i.e. the topmost
+    /// one, e.g. in an open popup rather than behind it.
+    pub fn item(&self, text: &str) -> Option<&Item> {
+        self.items.iter().rev().find(|item| item.text == text)
+    }
+
+    /// Whether the checkbox with this label is checked, or `None` if there
+    /// is no such item.
+    pub fn is_checked(&self, label: &str) -> Option<bool> {
+        let item = self.item(label)?;
+        let style = self.ctx.style();
+
+        // The box is left of the label, a frame high, and the check mark is
+        // drawn inside it.
+        let size = self.glyphs.font_size + style.frame_padding[1] * 2.;
+        let max_x = item.min[0] - style.item_inner_spacing[0];
+        let min = [max_x - size + 2., item.min[1] - style.frame_padding[1] + 2.];
+        let max = [max_x - 2., min[1] + size - 4.];
+
+        let [r, g, b, _] = ImColor32::from(style[StyleColor::CheckMark]).to_bits().to_le_bytes();
+        Some(self.shapes.iter().any(|&(pos, col)| {
+            col[..3] == [r, g, b]
+                && col[3] > 0
+                && (min[0]..=max[0]).contains(&pos[0])
+                && (min[1]..=max[1]).contains(&pos[1])
+        }))
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_key(key: &str) -> Key {
+    key.parse().unwrap_or_else(|e| panic!("invalid key {key:?}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items() {
+        let mut harness = Harness::new();
+        let mut checked = true;
+        let mut clicks = 0;
+
+        let mut frame = |harness: &mut Harness| {
+            harness.frame(|ui| {
+                ui.text("Hello, world!  two spaces");
+                ui.checkbox("test 1", &mut checked);
+                if ui.button("Click me (ctrl+f)") {
+                    clicks += 1;
+                }
+            })
+        };
+
+        frame(&mut harness);
+        assert!(harness.item("Hello, world!  two spaces").is_some());
+        assert_eq!(harness.is_checked("test 1"), Some(true));
+        assert_eq!(harness.is_checked("test 2"), None);
+
+        assert!(harness.click("test 1"));
+        for _ in 0..4 {
+            frame(&mut harness);
+        }
+        assert_eq!(harness.is_checked("test 1"), Some(false));
+
+        assert!(harness.click("Click me (ctrl+f)"));
+        for _ in 0..4 {
+            frame(&mut harness);
+        }
+        assert_eq!(clicks, 1);
+        assert!(!harness.click("Nope"));
+    }
+}
@@ -1,171 +1,209 @@
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
 
 use imgui::Ui;
-use serde::Deserialize;
-
-const REPR_MAP: &[(imgui::Key, &str)] = &[
-    (imgui::Key::Tab, "tab"),
-    (imgui::Key::LeftArrow, "left"),
-    (imgui::Key::RightArrow, "righ"),
-    (imgui::Key::UpArrow, "up"),
-    (imgui::Key::DownArrow, "down"),
-    (imgui::Key::PageUp, "pgup"),
-    (imgui::Key::PageDown, "pgdown"),
-    (imgui::Key::Home, "home"),
-    (imgui::Key::End, "end"),
-    (imgui::Key::Insert, "insert"),
-    (imgui::Key::Delete, "delete"),
-    (imgui::Key::Backspace, "backspace"),
-    (imgui::Key::Space, "space"),
-    (imgui::Key::Enter, "enter"),
-    (imgui::Key::Escape, "escape"),
-    (imgui::Key::LeftCtrl, "lctrl"),
-    (imgui::Key::LeftShift, "lshift"),
-    (imgui::Key::LeftAlt, "lalt"),
-    (imgui::Key::LeftSuper, "lsuper"),
-    (imgui::Key::RightCtrl, "rctrl"),
-    (imgui::Key::RightShift, "rshift"),
-    (imgui::Key::RightAlt, "ralt"),
-    (imgui::Key::RightSuper, "rsuper"),
-    (imgui::Key::Menu, "menu"),
-    (imgui::Key::Alpha0, "0"),
-    (imgui::Key::Alpha1, "1"),
-    (imgui::Key::Alpha2, "2"),
-    (imgui::Key::Alpha3, "3"),
-    (imgui::Key::Alpha4, "4"),
-    (imgui::Key::Alpha5, "5"),
-    (imgui::Key::Alpha6, "6"),
-    (imgui::Key::Alpha7, "7"),
-    (imgui::Key::Alpha8, "8"),
-    (imgui::Key::Alpha9, "9"),
-    (imgui::Key::A, "a"),
-    (imgui::Key::B, "b"),
-    (imgui::Key::C, "c"),
-    (imgui::Key::D, "d"),
-    (imgui::Key::E, "e"),
-    (imgui::Key::F, "f"),
-    (imgui::Key::G, "g"),
-    (imgui::Key::H, "h"),
-    (imgui::Key::I, "i"),
-    (imgui::Key::J, "j"),
-    (imgui::Key::K, "k"),
-    (imgui::Key::L, "l"),
-    (imgui::Key::M, "m"),
-    (imgui::Key::N, "n"),
-    (imgui::Key::O, "o"),
-    (imgui::Key::P, "p"),
-    (imgui::Key::Q, "q"),
-    (imgui::Key::R, "r"),
-    (imgui::Key::S, "s"),
-    (imgui::Key::T, "t"),
-    (imgui::Key::U, "u"),
-    (imgui::Key::V, "v"),
-    (imgui::Key::W, "w"),
-    (imgui::Key::X, "x"),
-    (imgui::Key::Y, "y"),
-    (imgui::Key::Z, "z"),
-    (imgui::Key::F1, "f1"),
-    (imgui::Key::F2, "f2"),
-    (imgui::Key::F3, "f3"),
-    (imgui::Key::F4, "f4"),
-    (imgui::Key::F5, "f5"),
-    (imgui::Key::F6, "f6"),
-    (imgui::Key::F7, "f7"),
-    (imgui::Key::F8, "f8"),
-    (imgui::Key::F9, "f9"),
-    (imgui::Key::F10, "f10"),
-    (imgui::Key::F11, "f11"),
-    (imgui::Key::F12, "f12"),
-    (imgui::Key::Apostrophe, "'"),
-    (imgui::Key::Comma, "),"),
-    (imgui::Key::Minus, "-"),
-    (imgui::Key::Period, "."),
-    (imgui::Key::Slash, "/"),
-    (imgui::Key::Semicolon, ";"),
-    (imgui::Key::Equal, "="),
-    (imgui::Key::LeftBracket, "["),
-    (imgui::Key::Backslash, "\\"),
-    (imgui::Key::RightBracket, "]"),
-    (imgui::Key::GraveAccent, "`"),
-    (imgui::Key::CapsLock, "capslock"),
-    (imgui::Key::ScrollLock, "scrolllock"),
-    (imgui::Key::NumLock, "numlock"),
-    (imgui::Key::PrintScreen, "printscreen"),
-    (imgui::Key::Pause, "pause"),
-    (imgui::Key::Keypad0, "kp0"),
-    (imgui::Key::Keypad1, "kp1"),
-    (imgui::Key::Keypad2, "kp2"),
-    (imgui::Key::Keypad3, "kp3"),
-    (imgui::Key::Keypad4, "kp4"),
-    (imgui::Key::Keypad5, "kp5"),
-    (imgui::Key::Keypad6, "kp6"),
-    (imgui::Key::Keypad7, "kp7"),
-    (imgui::Key::Keypad8, "kp8"),
-    (imgui::Key::Keypad9, "kp9"),
-    (imgui::Key::KeypadDecimal, "kpdecimal"),
-    (imgui::Key::KeypadDivide, "kpdivide"),
-    (imgui::Key::KeypadMultiply, "kpmultiply"),
-    (imgui::Key::KeypadSubtract, "kpsubtract"),
-    (imgui::Key::KeypadAdd, "kpadd"),
-    (imgui::Key::KeypadEnter, "kpenter"),
-    (imgui::Key::KeypadEqual, "kpequal"),
-    (imgui::Key::GamepadStart, "gamepadstart"),
-    (imgui::Key::GamepadBack, "gamepadback"),
-    (imgui::Key::GamepadFaceLeft, "gamepadfaceleft"),
-    (imgui::Key::GamepadFaceRight, "gamepadfaceright"),
-    (imgui::Key::GamepadFaceUp, "gamepadfaceup"),
-    (imgui::Key::GamepadFaceDown, "gamepadfacedown"),
-    (imgui::Key::GamepadDpadLeft, "gamepaddpadleft"),
-    (imgui::Key::GamepadDpadRight, "gamepaddpadright"),
-    (imgui::Key::GamepadDpadUp, "gamepaddpadup"),
-    (imgui::Key::GamepadDpadDown, "gamepaddpaddown"),
-    (imgui::Key::GamepadL1, "gamepadl1"),
-    (imgui::Key::GamepadR1, "gamepadr1"),
-    (imgui::Key::GamepadL2, "gamepadl2"),
-    (imgui::Key::GamepadR2, "gamepadr2"),
-    (imgui::Key::GamepadL3, "gamepadl3"),
-    (imgui::Key::GamepadR3, "gamepadr3"),
-    (imgui::Key::GamepadLStickLeft, "gamepadlstickleft"),
-    (imgui::Key::GamepadLStickRight, "gamepadlstickright"),
-    (imgui::Key::GamepadLStickUp, "gamepadlstickup"),
-    (imgui::Key::GamepadLStickDown, "gamepadlstickdown"),
-    (imgui::Key::GamepadRStickLeft, "gamepadrstickleft"),
-    (imgui::Key::GamepadRStickRight, "gamepadrstickright"),
-    (imgui::Key::GamepadRStickUp, "gamepadrstickup"),
-    (imgui::Key::GamepadRStickDown, "gamepadrstickdown"),
-    (imgui::Key::MouseLeft, "mouseleft"),
-    (imgui::Key::MouseRight, "mouseright"),
-    (imgui::Key::MouseMiddle, "mousemiddle"),
-    (imgui::Key::MouseX1, "mousex1"),
-    (imgui::Key::MouseX2, "mousex2"),
-    (imgui::Key::MouseWheelX, "mousewheelx"),
-    (imgui::Key::MouseWheelY, "mousewheely"),
-    (imgui::Key::ReservedForModCtrl, "reservedformodctrl"),
-    (imgui::Key::ReservedForModShift, "reservedformodshift"),
-    (imgui::Key::ReservedForModAlt, "reservedformodalt"),
-    (imgui::Key::ReservedForModSuper, "reservedformodsuper"),
-    (imgui::Key::ModCtrl, "ctrl"),
-    (imgui::Key::ModShift, "shift"),
-    (imgui::Key::ModAlt, "alt"),
-    (imgui::Key::ModSuper, "super"),
-    (imgui::Key::ModShortcut, "shortcut"),
+use serde::{Deserialize, Serialize, Serializer};
+use windows::Win32::UI::Input::XboxController::XINPUT_STATE;
+
+use crate::controller::{self, MAX_CONTROLLERS};
+use crate::error::{self, ParseError};
+
+/// Names of the keys: the canonical config name, which is also the one written
+/// back, followed by its aliases; then the name displayed in labels.
+const REPR_MAP: &[(imgui::Key, &[&str], &str)] = &[
+    (imgui::Key::Tab, &["tab"], "Tab"),
+    (imgui::Key::LeftArrow, &["left"], "Left"),
+    (imgui::Key::RightArrow, &["right"], "Right"),
+    (imgui::Key::UpArrow, &["up"], "Up"),
+    (imgui::Key::DownArrow, &["down"], "Down"),
+    (imgui::Key::PageUp, &["pgup", "pageup"], "Page Up"),
+    (imgui::Key::PageDown, &["pgdown", "pagedown"], "Page Down"),
+    (imgui::Key::Home, &["home"], "Home"),
+    (imgui::Key::End, &["end"], "End"),
+    (imgui::Key::Insert, &["insert", "ins"], "Insert"),
+    (imgui::Key::Delete, &["delete", "del"], "Delete"),
+    (imgui::Key::Backspace, &["backspace"], "Backspace"),
+    (imgui::Key::Space, &["space"], "Space"),
+    (imgui::Key::Enter, &["enter", "return"], "Enter"),
+    (imgui::Key::Escape, &["escape", "esc"], "Esc"),
+    (imgui::Key::LeftCtrl, &["lctrl", "lcontrol"], "Left Ctrl"),
+    (imgui::Key::LeftShift, &["lshift"], "Left Shift"),
+    (imgui::Key::LeftAlt, &["lalt"], "Left Alt"),
+    (imgui::Key::LeftSuper, &["lsuper", "lwin"], "Left Super"),
+    (imgui::Key::RightCtrl, &["rctrl", "rcontrol"], "Right Ctrl"),
+    (imgui::Key::RightShift, &["rshift"], "Right Shift"),
+    (imgui::Key::RightAlt, &["ralt"], "Right Alt"),
+    (imgui::Key::RightSuper, &["rsuper", "rwin"], "Right Super"),
+    (imgui::Key::Menu, &["menu"], "Menu"),
+    (imgui::Key::Alpha0, &["0"], "0"),
+    (imgui::Key::Alpha1, &["1"], "1"),
+    (imgui::Key::Alpha2, &["2"], "2"),
+    (imgui::Key::Alpha3, &["3"], "3"),
+    (imgui::Key::Alpha4, &["4"], "4"),
+    (imgui::Key::Alpha5, &["5"], "5"),
+    (imgui::Key::Alpha6, &["6"], "6"),
+    (imgui::Key::Alpha7, &["7"], "7"),
+    (imgui::Key::Alpha8, &["8"], "8"),
+    (imgui::Key::Alpha9, &["9"], "9"),
+    (imgui::Key::A, &["a"], "A"),
+    (imgui::Key::B, &["b"], "B"),
+    (imgui::Key::C, &["c"], "C"),
+    (imgui::Key::D, &["d"], "D"),
+    (imgui::Key::E, &["e"], "E"),
+    (imgui::Key::F, &["f"], "F"),
+    (imgui::Key::G, &["g"], "G"),
+    (imgui::Key::H, &["h"], "H"),
+    (imgui::Key::I, &["i"], "I"),
+    (imgui::Key::J, &["j"], "J"),
+    (imgui::Key::K, &["k"], "K"),
+    (imgui::Key::L, &["l"], "L"),
+    (imgui::Key::M, &["m"], "M"),
+    (imgui::Key::N, &["n"], "N"),
+    (imgui::Key::O, &["o"], "O"),
+    (imgui::Key::P, &["p"], "P"),
+    (imgui::Key::Q, &["q"], "Q"),
+    (imgui::Key::R, &["r"], "R"),
+    (imgui::Key::S, &["s"], "S"),
+    (imgui::Key::T, &["t"], "T"),
+    (imgui::Key::U, &["u"], "U"),
+    (imgui::Key::V, &["v"], "V"),
+    (imgui::Key::W, &["w"], "W"),
+    (imgui::Key::X, &["x"], "X"),
+    (imgui::Key::Y, &["y"], "Y"),
+    (imgui::Key::Z, &["z"], "Z"),
+    (imgui::Key::F1, &["f1"], "F1"),
+    (imgui::Key::F2, &["f2"], "F2"),
+    (imgui::Key::F3, &["f3"], "F3"),
+    (imgui::Key::F4, &["f4"], "F4"),
+    (imgui::Key::F5, &["f5"], "F5"),
+    (imgui::Key::F6, &["f6"], "F6"),
+    (imgui::Key::F7, &["f7"], "F7"),
+    (imgui::Key::F8, &["f8"], "F8"),
+    (imgui::Key::F9, &["f9"], "F9"),
+    (imgui::Key::F10, &["f10"], "F10"),
+    (imgui::Key::F11, &["f11"], "F11"),
+    (imgui::Key::F12, &["f12"], "F12"),
+    (imgui::Key::Apostrophe, &["'"], "'"),
+    (imgui::Key::Comma, &[","], ","),
+    (imgui::Key::Minus, &["-"], "-"),
+    (imgui::Key::Period, &["."], "."),
+    (imgui::Key::Slash, &["/"], "/"),
+    (imgui::Key::Semicolon, &[";"], ";"),
+    (imgui::Key::Equal, &["="], "="),
+    (imgui::Key::LeftBracket, &["["], "["),
+    (imgui::Key::Backslash, &["\\"], "\\"),
+    (imgui::Key::RightBracket, &["]"], "]"),
+    (imgui::Key::GraveAccent, &["`"], "`"),
+    (imgui::Key::CapsLock, &["capslock"], "Caps Lock"),
+    (imgui::Key::ScrollLock, &["scrolllock"], "Scroll Lock"),
+    (imgui::Key::NumLock, &["numlock"], "Num Lock"),
+    (imgui::Key::PrintScreen, &["printscreen", "prtsc"], "Print Screen"),
+    (imgui::Key::Pause, &["pause"], "Pause"),
+    (imgui::Key::Keypad0, &["kp0", "num0"], "Num 0"),
+    (imgui::Key::Keypad1, &["kp1", "num1"], "Num 1"),
+    (imgui::Key::Keypad2, &["kp2", "num2"], "Num 2"),
+    (imgui::Key::Keypad3, &["kp3", "num3"], "Num 3"),
+    (imgui::Key::Keypad4, &["kp4", "num4"], "Num 4"),
+    (imgui::Key::Keypad5, &["kp5", "num5"], "Num 5"),
+    (imgui::Key::Keypad6, &["kp6", "num6"], "Num 6"),
+    (imgui::Key::Keypad7, &["kp7", "num7"], "Num 7"),
+    (imgui::Key::Keypad8, &["kp8", "num8"], "Num 8"),
+    (imgui::Key::Keypad9, &["kp9", "num9"], "Num 9"),
+    (imgui::Key::KeypadDecimal, &["kpdecimal", "num."], "Num ."),
+    (imgui::Key::KeypadDivide, &["kpdivide", "num/"], "Num /"),
+    (imgui::Key::KeypadMultiply, &["kpmultiply", "num*"], "Num *"),
+    (imgui::Key::KeypadSubtract, &["kpsubtract", "num-"], "Num -"),
+    (imgui::Key::KeypadAdd, &["kpadd", "numplus"], "Num +"),
+    (imgui::Key::KeypadEnter, &["kpenter", "numenter"], "Num Enter"),
+    (imgui::Key::KeypadEqual, &["kpequal", "num="], "Num ="),
+    (imgui::Key::GamepadStart, &["gamepadstart"], "Gamepad Start"),
+    (imgui::Key::GamepadBack, &["gamepadback"], "Gamepad Back"),
+    (imgui::Key::GamepadFaceLeft, &["gamepadfaceleft"], "Gamepad Face Left"),
+    (imgui::Key::GamepadFaceRight, &["gamepadfaceright"], "Gamepad Face Right"),
+    (imgui::Key::GamepadFaceUp, &["gamepadfaceup"], "Gamepad Face Up"),
+    (imgui::Key::GamepadFaceDown, &["gamepadfacedown"], "Gamepad Face Down"),
+    (imgui::Key::GamepadDpadLeft, &["gamepaddpadleft"], "Gamepad D-pad Left"),
+    (imgui::Key::GamepadDpadRight, &["gamepaddpadright"], "Gamepad D-pad Right"),
+    (imgui::Key::GamepadDpadUp, &["gamepaddpadup"], "Gamepad D-pad Up"),
+    (imgui::Key::GamepadDpadDown, &["gamepaddpaddown"], "Gamepad D-pad Down"),
+    (imgui::Key::GamepadL1, &["gamepadl1"], "Gamepad L1"),
+    (imgui::Key::GamepadR1, &["gamepadr1"], "Gamepad R1"),
+    (imgui::Key::GamepadL2, &["gamepadl2"], "Gamepad L2"),
+    (imgui::Key::GamepadR2, &["gamepadr2"], "Gamepad R2"),
+    (imgui::Key::GamepadL3, &["gamepadl3"], "Gamepad L3"),
+    (imgui::Key::GamepadR3, &["gamepadr3"], "Gamepad R3"),
+    (imgui::Key::GamepadLStickLeft, &["gamepadlstickleft"], "Gamepad L-stick Left"),
+    (imgui::Key::GamepadLStickRight, &["gamepadlstickright"], "Gamepad L-stick Right"),
+    (imgui::Key::GamepadLStickUp, &["gamepadlstickup"], "Gamepad L-stick Up"),
+    (imgui::Key::GamepadLStickDown, &["gamepadlstickdown"], "Gamepad L-stick Down"),
+    (imgui::Key::GamepadRStickLeft, &["gamepadrstickleft"], "Gamepad R-stick Left"),
+    (imgui::Key::GamepadRStickRight, &["gamepadrstickright"], "Gamepad R-stick Right"),
+    (imgui::Key::GamepadRStickUp, &["gamepadrstickup"], "Gamepad R-stick Up"),
+    (imgui::Key::GamepadRStickDown, &["gamepadrstickdown"], "Gamepad R-stick Down"),
+    (imgui::Key::MouseLeft, &["mouseleft"], "Mouse Left"),
+    (imgui::Key::MouseRight, &["mouseright"], "Mouse Right"),
+    (imgui::Key::MouseMiddle, &["mousemiddle"], "Mouse Middle"),
+    (imgui::Key::MouseX1, &["mousex1"], "Mouse X1"),
+    (imgui::Key::MouseX2, &["mousex2"], "Mouse X2"),
+    (imgui::Key::MouseWheelX, &["mousewheelx"], "Mouse Wheel X"),
+    (imgui::Key::MouseWheelY, &["mousewheely"], "Mouse Wheel Y"),
+    (imgui::Key::ReservedForModCtrl, &["reservedformodctrl"], "Reserved For Mod Ctrl"),
+    (imgui::Key::ReservedForModShift, &["reservedformodshift"], "Reserved For Mod Shift"),
+    (imgui::Key::ReservedForModAlt, &["reservedformodalt"], "Reserved For Mod Alt"),
+    (imgui::Key::ReservedForModSuper, &["reservedformodsuper"], "Reserved For Mod Super"),
+    (imgui::Key::ModCtrl, &["ctrl", "control"], "Ctrl"),
+    (imgui::Key::ModShift, &["shift"], "Shift"),
+    (imgui::Key::ModAlt, &["alt"], "Alt"),
+    (imgui::Key::ModSuper, &["super", "win"], "Super"),
+    (imgui::Key::ModShortcut, &["shortcut"], "Shortcut"),
 ];
 
-const MOD_REPR_MAP: &[(Modifier, &str)] = &[
-    (Modifier::LeftCtrl, "lctrl"),
-    (Modifier::LeftShift, "lshift"),
-    (Modifier::LeftAlt, "lalt"),
-    (Modifier::LeftSuper, "lsuper"),
-    (Modifier::RightCtrl, "rctrl"),
-    (Modifier::RightShift, "rshift"),
-    (Modifier::RightAlt, "ralt"),
-    (Modifier::RightSuper, "rsuper"),
-    (Modifier::ModCtrl, "ctrl"),
-    (Modifier::ModShift, "shift"),
-    (Modifier::ModAlt, "alt"),
-    (Modifier::ModSuper, "super"),
+/// Names of the modifiers, like [`REPR_MAP`], in display order.
+const MOD_REPR_MAP: &[(Modifier, &[&str], &str)] = &[
+    (Modifier::ModCtrl, &["ctrl", "control"], "Ctrl"),
+    (Modifier::LeftCtrl, &["lctrl", "lcontrol"], "Left Ctrl"),
+    (Modifier::RightCtrl, &["rctrl", "rcontrol"], "Right Ctrl"),
+    (Modifier::ModShift, &["shift"], "Shift"),
+    (Modifier::LeftShift, &["lshift"], "Left Shift"),
+    (Modifier::RightShift, &["rshift"], "Right Shift"),
+    (Modifier::ModAlt, &["alt"], "Alt"),
+    (Modifier::LeftAlt, &["lalt"], "Left Alt"),
+    (Modifier::RightAlt, &["ralt"], "Right Alt"),
+    (Modifier::ModSuper, &["super", "win"], "Super"),
+    (Modifier::LeftSuper, &["lsuper", "lwin"], "Left Super"),
+    (Modifier::RightSuper, &["rsuper", "rwin"], "Right Super"),
 ];
 
+/// A table of names like [`REPR_MAP`].
+pub(crate) type NameMap<T> = [(T, &'static [&'static str], &'static str)];
+
+/// Finds the value named `name`, or one of its aliases, ignoring case.
+pub(crate) fn find_by_name<T: Copy>(map: &NameMap<T>, name: &str) -> Option<T> {
+    map.iter().find_map(|&(value, names, _)| {
+        names.iter().any(|n| n.eq_ignore_ascii_case(name)).then_some(value)
+    })
+}
+
+/// The canonical config name of `value`.
+pub(crate) fn config_name<T: PartialEq>(map: &NameMap<T>, value: &T) -> &'static str {
+    map.iter().find_map(|(v, names, _)| (v == value).then_some(names[0])).unwrap_or("???")
+}
+
+/// The name of `value` displayed in labels.
+pub(crate) fn display_name<T: PartialEq>(map: &NameMap<T>, value: &T) -> &'static str {
+    map.iter().find_map(|(v, _, display)| (v == value).then_some(*display)).unwrap_or("???")
+}
+
+/// All the config names and aliases, e.g. for suggestions.
+pub(crate) fn all_names<T>(map: &NameMap<T>) -> impl Iterator<Item = &'static str> + '_ {
+    map.iter().flat_map(|&(_, names, _)| names.iter().copied())
+}
+
+/// Displays a binding with the names meant for labels, e.g. `Ctrl+F1` or
+/// `Num *`, rather than the config names.
+pub struct Pretty<'a, T>(pub(crate) &'a T);
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Modifier {
@@ -185,12 +223,7 @@ enum Modifier {
 
 impl std::fmt::Display for Modifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let repr = MOD_REPR_MAP
-            .iter()
-            .find_map(|&(key, val)| if key == *self { Some(val) } else { None })
-            .unwrap_or("???");
-
-        write!(f, "{repr}")
+        write!(f, "{}", config_name(MOD_REPR_MAP, self))
     }
 }
 
@@ -214,33 +247,77 @@ impl From<Modifier> for imgui::Key {
 }
 
 impl FromStr for Modifier {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        MOD_REPR_MAP
-            .iter()
-            .find_map(|&(key, val)| if val == s { Some(key) } else { None })
-            .ok_or_else(|| format!("Could not find modifier: \"{s}\""))
+        find_by_name(MOD_REPR_MAP, s)
+            .ok_or_else(|| ParseError::unknown_modifier(s, 0..s.len(), all_names(MOD_REPR_MAP)))
     }
 }
 
 impl Modifier {
-    pub fn is_down(&self, ui: &Ui) -> bool {
+    /// The modifiers bound to a physical key.
+    const SIDES: [Modifier; 8] = [
+        Modifier::LeftCtrl,
+        Modifier::RightCtrl,
+        Modifier::LeftShift,
+        Modifier::RightShift,
+        Modifier::LeftAlt,
+        Modifier::RightAlt,
+        Modifier::LeftSuper,
+        Modifier::RightSuper,
+    ];
+
+    /// The side-agnostic modifier this one belongs to, e.g. `ctrl` for `lctrl`.
+    fn generic(&self) -> Modifier {
         match self {
-            Modifier::LeftCtrl
-            | Modifier::LeftShift
-            | Modifier::LeftAlt
-            | Modifier::LeftSuper
-            | Modifier::RightCtrl
-            | Modifier::RightShift
-            | Modifier::RightAlt
-            | Modifier::RightSuper => ui.is_key_down((*self).into()),
-            Modifier::ModCtrl => ui.io().key_ctrl,
-            Modifier::ModShift => ui.io().key_shift,
-            Modifier::ModAlt => ui.io().key_alt,
-            Modifier::ModSuper => ui.io().key_super,
+            Modifier::LeftCtrl | Modifier::RightCtrl | Modifier::ModCtrl => Modifier::ModCtrl,
+            Modifier::LeftShift | Modifier::RightShift | Modifier::ModShift => Modifier::ModShift,
+            Modifier::LeftAlt | Modifier::RightAlt | Modifier::ModAlt => Modifier::ModAlt,
+            Modifier::LeftSuper | Modifier::RightSuper | Modifier::ModSuper => Modifier::ModSuper,
         }
     }
+
+    pub fn is_down(&self, input: &dyn InputSource) -> bool {
+        input.is_key_down((*self).into())
+    }
+}
+
+/// Set of modifiers, iterated in [`MOD_REPR_MAP`] order.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+struct Modifiers(u16);
+
+impl Modifiers {
+    fn bit(modifier: Modifier) -> u16 {
+        let index = MOD_REPR_MAP.iter().position(|&(m, ..)| m == modifier).unwrap_or_default();
+        1 << index
+    }
+
+    fn insert(&mut self, modifier: Modifier) {
+        self.0 |= Self::bit(modifier);
+    }
+
+    fn contains(self, modifier: Modifier) -> bool {
+        self.0 & Self::bit(modifier) != 0
+    }
+
+    fn iter(self) -> impl Iterator<Item = Modifier> {
+        MOD_REPR_MAP.iter().map(|&(m, ..)| m).filter(move |&m| self.contains(m))
+    }
+}
+
+impl FromIterator<Modifier> for Modifiers {
+    fn from_iter<T: IntoIterator<Item = Modifier>>(iter: T) -> Self {
+        let mut modifiers = Modifiers::default();
+        iter.into_iter().for_each(|modifier| modifiers.insert(modifier));
+        modifiers
+    }
+}
+
+impl std::fmt::Debug for Modifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -251,24 +328,32 @@ struct ModifierState {
     key_super: bool,
 }
 
-impl From<&Ui> for ModifierState {
-    fn from(value: &Ui) -> Self {
-        let io = value.io();
+impl ModifierState {
+    fn is_subset(&self, other: &ModifierState) -> bool {
+        (!self.key_ctrl || other.key_ctrl)
+            && (!self.key_shift || other.key_shift)
+            && (!self.key_alt || other.key_alt)
+            && (!self.key_super || other.key_super)
+    }
+}
+
+impl From<&dyn InputSource> for ModifierState {
+    fn from(input: &dyn InputSource) -> Self {
         Self {
-            key_ctrl: io.key_ctrl,
-            key_shift: io.key_shift,
-            key_alt: io.key_alt,
-            key_super: io.key_super,
+            key_ctrl: input.is_key_down(imgui::Key::ModCtrl),
+            key_shift: input.is_key_down(imgui::Key::ModShift),
+            key_alt: input.is_key_down(imgui::Key::ModAlt),
+            key_super: input.is_key_down(imgui::Key::ModSuper),
         }
     }
 }
 
-impl From<[Option<Modifier>; 3]> for ModifierState {
-    fn from(value: [Option<Modifier>; 3]) -> Self {
+impl From<Modifiers> for ModifierState {
+    fn from(value: Modifiers) -> Self {
         let mut modifier_state =
             Self { key_ctrl: false, key_shift: false, key_alt: false, key_super: false };
 
-        for modifier in value.into_iter().flatten() {
+        for modifier in value.iter() {
             match modifier {
                 Modifier::LeftCtrl | Modifier::RightCtrl | Modifier::ModCtrl => {
                     modifier_state.key_ctrl = true
@@ -293,56 +378,64 @@ impl From<[Option<Modifier>; 3]> for ModifierState {
 #[serde(try_from = "String")]
 pub struct Key {
     key: imgui::Key,
-    modifiers: [Option<Modifier>; 3],
+    modifiers: Modifiers,
 }
 
 impl std::fmt::Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let repr = REPR_MAP
-            .iter()
-            .find_map(|&(key, val)| if key == self.key { Some(val) } else { None })
-            .unwrap_or("???");
-
-        for modifier in self.modifiers.into_iter().flatten() {
+        for modifier in self.modifiers.iter() {
             write!(f, "{modifier}+")?;
         }
 
-        write!(f, "{repr}")
+        write!(f, "{}", config_name(REPR_MAP, &self.key))
     }
 }
 
-impl TryFrom<&str> for Key {
-    type Error = String;
+impl std::fmt::Display for Pretty<'_, Key> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for modifier in self.0.modifiers.iter() {
+            write!(f, "{}+", display_name(MOD_REPR_MAP, &modifier))?;
+        }
 
-    fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let mut chunks = s.split('+').rev();
-        let key_chunk =
-            chunks.next().ok_or_else(|| format!("Could not parse key: \"{s}\""))?.to_lowercase();
+        write!(f, "{}", display_name(REPR_MAP, &self.0.key))
+    }
+}
 
-        let key = REPR_MAP
-            .iter()
-            .find_map(|&(key, val)| if val == key_chunk { Some(key) } else { None })
-            .ok_or_else(|| format!("Could not find key: \"{key_chunk}\""))?;
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
-        let mut modifier_chunks = chunks.rev().map(Modifier::from_str);
+impl TryFrom<&str> for Key {
+    type Error = ParseError;
 
-        let mut modifiers = [None; 3];
-        if let Some(modifier) = modifier_chunks.next() {
-            modifiers[0] = Some(modifier?);
-        }
-        if let Some(modifier) = modifier_chunks.next() {
-            modifiers[1] = Some(modifier?);
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut tokens = error::tokens(s, '+').collect::<Vec<_>>();
+        let (key_token, key_span) = tokens.pop().unwrap_or_default();
+
+        let mut modifiers = Modifiers::default();
+        for (token, span) in tokens {
+            if token.is_empty() {
+                return Err(ParseError::Empty { span });
+            }
+            let modifier = token.parse::<Modifier>().map_err(|e| e.offset(span.start))?;
+            modifiers.insert(modifier);
         }
-        if let Some(modifier) = modifier_chunks.next() {
-            modifiers[2] = Some(modifier?);
+
+        if key_token.is_empty() {
+            return Err(ParseError::Empty { span: key_span });
         }
 
+        let key = find_by_name(REPR_MAP, key_token)
+            .ok_or_else(|| ParseError::unknown_key(key_token, key_span, all_names(REPR_MAP)))?;
+
         Ok(Self { key, modifiers })
     }
 }
 
 impl TryFrom<String> for Key {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         value.as_str().try_into()
@@ -350,7 +443,7 @@ impl TryFrom<String> for Key {
 }
 
 impl FromStr for Key {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         s.try_into()
@@ -358,9 +451,13 @@ impl FromStr for Key {
 }
 
 impl Key {
+    pub fn pretty(&self) -> Pretty<'_, Key> {
+        Pretty(self)
+    }
+
     pub fn keys(&self, out: &mut Vec<imgui::Key>) {
         out.push(self.key);
-        self.modifiers.iter().filter_map(Option::as_ref).copied().for_each(|modif| {
+        self.modifiers.iter().for_each(|modif| {
             match modif {
                 Modifier::LeftCtrl | Modifier::RightCtrl => out.push(imgui::Key::ModCtrl),
                 Modifier::LeftShift | Modifier::RightShift => out.push(imgui::Key::ModShift),
@@ -372,20 +469,912 @@ impl Key {
         });
     }
 
-    pub fn is_down(&self, ui: &Ui) -> bool {
-        ui.is_key_down(self.key)
-            && self.modifiers.iter().all(|modifier| modifier.map(|k| k.is_down(ui)).unwrap_or(true))
-            && ModifierState::from(ui) == ModifierState::from(self.modifiers)
+    /// Whether the key is held, matching modifiers with the global
+    /// [`match_policy`].
+    pub fn is_down(&self, input: &dyn InputSource) -> bool {
+        self.is_down_with(input, match_policy())
+    }
+
+    pub fn is_up(&self, input: &dyn InputSource) -> bool {
+        !self.is_down(input)
+    }
+
+    /// Whether the key was pressed on this frame, matching modifiers with the
+    /// global [`match_policy`].
+    pub fn is_pressed(&self, input: &dyn InputSource) -> bool {
+        self.is_pressed_with(input, match_policy())
+    }
+
+    pub fn is_down_with(&self, input: &dyn InputSource, policy: MatchPolicy) -> bool {
+        input.is_key_down(self.key) && self.modifiers_match(input, policy)
+    }
+
+    pub fn is_pressed_with(&self, input: &dyn InputSource, policy: MatchPolicy) -> bool {
+        input.is_key_pressed(self.key) && self.modifiers_match(input, policy)
+    }
+
+    fn modifiers_match(&self, input: &dyn InputSource, policy: MatchPolicy) -> bool {
+        let held = ModifierState::from(input);
+        let expected = ModifierState::from(self.modifiers);
+
+        self.modifiers.iter().all(|modifier| modifier.is_down(input))
+            && match policy {
+                MatchPolicy::Strict => held == expected,
+                MatchPolicy::Lenient => true,
+                MatchPolicy::SideSpecific => {
+                    held == expected
+                        && Modifier::SIDES.iter().all(|&side| {
+                            !side.is_down(input)
+                                || self.modifiers.contains(side)
+                                || self.modifiers.contains(side.generic())
+                        })
+                },
+            }
+    }
+
+    /// The key pressed on this frame, if any, along with the modifiers being
+    /// held. Modifier, mouse and gamepad keys alone are ignored. Used to
+    /// capture a new binding from the user.
+    pub fn capture(input: &dyn InputSource) -> Option<Key> {
+        let key = REPR_MAP
+            .iter()
+            .map(|&(key, ..)| key)
+            .filter(|&key| is_capturable(key))
+            .find(|&key| input.is_key_pressed_no_repeat(key))?;
+
+        let modifiers =
+            [Modifier::ModCtrl, Modifier::ModShift, Modifier::ModAlt, Modifier::ModSuper]
+                .into_iter()
+                .filter(|modifier| modifier.is_down(input))
+                .collect();
+
+        Some(Key { key, modifiers })
+    }
+
+    /// Whether this key would fire when `other` is pressed, i.e. when exactly
+    /// the modifiers of `other` are held.
+    pub(crate) fn fires_on(&self, other: &Key, policy: MatchPolicy) -> bool {
+        let modifiers = ModifierState::from(self.modifiers);
+        let other_modifiers = ModifierState::from(other.modifiers);
+
+        let modifiers_match = match policy {
+            MatchPolicy::Strict => modifiers == other_modifiers,
+            MatchPolicy::Lenient => modifiers.is_subset(&other_modifiers),
+            // Extra held sides are rejected, unless they are the generic
+            // modifier of this key.
+            MatchPolicy::SideSpecific => {
+                modifiers == other_modifiers
+                    && other.modifiers.iter().all(|modifier| {
+                        modifier == modifier.generic()
+                            || self.modifiers.contains(modifier)
+                            || self.modifiers.contains(modifier.generic())
+                    })
+            },
+        };
+
+        // A side-specific modifier is satisfied by the same side, or by the
+        // generic modifier which could be either.
+        self.key == other.key
+            && modifiers_match
+            && self.modifiers.iter().all(|modifier| {
+                modifier == modifier.generic()
+                    || other.modifiers.iter().any(|m| m == modifier || m == modifier.generic())
+            })
+    }
+}
+
+/// Gamepad keys are captured as controller combinations instead.
+fn is_capturable(key: imgui::Key) -> bool {
+    use imgui::Key::*;
+
+    !controller::is_gamepad_key(key)
+        && !matches!(
+            key,
+            LeftCtrl
+                | LeftShift
+                | LeftAlt
+                | LeftSuper
+                | RightCtrl
+                | RightShift
+                | RightAlt
+                | RightSuper
+                | MouseLeft
+                | MouseRight
+                | MouseMiddle
+                | MouseX1
+                | MouseX2
+                | MouseWheelX
+                | MouseWheelY
+                | ReservedForModCtrl
+                | ReservedForModShift
+                | ReservedForModAlt
+                | ReservedForModSuper
+                | ModCtrl
+                | ModShift
+                | ModAlt
+                | ModSuper
+                | ModShortcut
+        )
+}
+
+/// How the modifiers held by the user are matched against a [`Key`].
+///
+/// The policy is global, see [`set_match_policy`], and can be overridden per
+/// [`Hotkey`], e.g. `"lenient f5"`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "String")]
+pub enum MatchPolicy {
+    /// The held modifiers must be exactly the ones of the key, on either side
+    /// unless the key names one, e.g. `lctrl+f`.
+    #[default]
+    Strict,
+    /// Extra held modifiers are allowed, e.g. `f5` fires during `shift+f5`.
+    Lenient,
+    /// Like strict, but the sides held must be the ones of the key too, e.g.
+    /// `lctrl+f` doesn't fire while both control keys are held.
+    SideSpecific,
+}
+
+const MATCH_POLICY_REPR_MAP: &[(MatchPolicy, &str)] = &[
+    (MatchPolicy::Strict, "strict"),
+    (MatchPolicy::Lenient, "lenient"),
+    (MatchPolicy::SideSpecific, "side-specific"),
+];
+
+impl std::fmt::Display for MatchPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let repr = MATCH_POLICY_REPR_MAP
+            .iter()
+            .find_map(|&(policy, val)| if policy == *self { Some(val) } else { None })
+            .unwrap_or("???");
+        write!(f, "{repr}")
+    }
+}
+
+impl Serialize for MatchPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for MatchPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MATCH_POLICY_REPR_MAP
+            .iter()
+            .find_map(
+                |&(policy, val)| if val.eq_ignore_ascii_case(s) { Some(policy) } else { None },
+            )
+            .ok_or_else(|| {
+                ParseError::unknown_policy(
+                    s,
+                    0..s.len(),
+                    MATCH_POLICY_REPR_MAP.iter().map(|&(_, val)| val),
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for MatchPolicy {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+static MATCH_POLICY: AtomicU8 = AtomicU8::new(MatchPolicy::Strict as u8);
+
+/// The policy used by bindings that don't set their own.
+pub fn match_policy() -> MatchPolicy {
+    match MATCH_POLICY.load(Ordering::Relaxed) {
+        1 => MatchPolicy::Lenient,
+        2 => MatchPolicy::SideSpecific,
+        _ => MatchPolicy::Strict,
+    }
+}
+
+/// Sets the policy used by bindings that don't set their own, e.g. from the
+/// tool's config at startup.
+pub fn set_match_policy(policy: MatchPolicy) {
+    MATCH_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Default maximum delay between two consecutive strokes of a [`Hotkey`].
+pub const DEFAULT_STROKE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// When a [`Hotkey`] fires, relative to its last stroke being pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Trigger {
+    /// Fires when the key is pressed, and on the OS key repeat while held.
+    #[default]
+    Press,
+    /// Fires when the key is released.
+    Release,
+    /// Fires once after the key has been held for the given duration.
+    Hold(Duration),
+    /// Fires on the second press, if it comes within the given duration.
+    DoubleTap(Duration),
+    /// Fires on press and then at the given interval while held.
+    Repeat(Duration),
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Press => write!(f, "press"),
+            Trigger::Release => write!(f, "release"),
+            Trigger::Hold(d) => write!(f, "hold:{}ms", d.as_millis()),
+            Trigger::DoubleTap(d) => write!(f, "doubletap:{}ms", d.as_millis()),
+            Trigger::Repeat(d) => write!(f, "repeat:{}ms", d.as_millis()),
+        }
+    }
+}
+
+const TRIGGER_NAMES: &[&str] = &["press", "release", "hold", "doubletap", "repeat"];
+
+/// Displayed as a prefix, e.g. `Hold Ctrl+F`; durations are left out.
+impl std::fmt::Display for Pretty<'_, Trigger> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Trigger::Press => Ok(()),
+            Trigger::Release => write!(f, "Release "),
+            Trigger::Hold(_) => write!(f, "Hold "),
+            Trigger::DoubleTap(_) => write!(f, "Double-tap "),
+            Trigger::Repeat(_) => write!(f, "Repeat "),
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => {
+                let duration = parse_duration(value).map_err(|e| e.offset(name.len() + 1))?;
+                (name, Some(duration))
+            },
+            None => (s, None),
+        };
+
+        match (name.to_lowercase().as_str(), value) {
+            ("press", None) => Ok(Trigger::Press),
+            ("release", None) => Ok(Trigger::Release),
+            ("hold", value) => Ok(Trigger::Hold(value.unwrap_or(Duration::from_millis(500)))),
+            ("doubletap", value) => {
+                Ok(Trigger::DoubleTap(value.unwrap_or(Duration::from_millis(300))))
+            },
+            ("repeat", value) => Ok(Trigger::Repeat(value.unwrap_or(Duration::from_millis(100)))),
+            ("press" | "release", Some(_)) => {
+                Err(ParseError::UnexpectedDuration { token: s.to_string(), span: 0..s.len() })
+            },
+            _ => {
+                Err(ParseError::unknown_trigger(name, 0..name.len(), TRIGGER_NAMES.iter().copied()))
+            },
+        }
+    }
+}
+
+/// Parses durations like `500ms`, `1s` or `1.5s`.
+fn parse_duration(s: &str) -> Result<Duration, ParseError> {
+    let invalid = || ParseError::InvalidDuration { token: s.to_string(), span: 0..s.len() };
+
+    let (value, scale) = if let Some(value) = s.strip_suffix("ms") {
+        (value, 0.001)
+    } else if let Some(value) = s.strip_suffix('s') {
+        (value, 1.)
+    } else {
+        return Err(invalid());
+    };
+
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.)
+        .map(|value| Duration::from_secs_f64(value * scale))
+        .ok_or_else(invalid)
+}
+
+/// Frame-to-frame bookkeeping for a [`Trigger`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TriggerState {
+    held_since: Option<f64>,
+    last_tap: Option<f64>,
+    next_repeat: f64,
+    fired: bool,
+}
+
+impl TriggerState {
+    /// Feeds one frame of input and returns whether the trigger fired.
+    ///
+    /// `pressed` is true on the frames the binding was pressed (including key
+    /// repeats), `down` while it is held, and `now` is the time in seconds.
+    pub(crate) fn update(&mut self, trigger: Trigger, pressed: bool, down: bool, now: f64) -> bool {
+        let was_held = self.held_since.is_some();
+        let rising = pressed && !was_held;
+        let falling = was_held && !down && !pressed;
+
+        if rising {
+            self.held_since = Some(now);
+            self.fired = false;
+        } else if falling {
+            self.held_since = None;
+        }
+
+        match trigger {
+            Trigger::Press => pressed,
+            Trigger::Release => falling,
+            Trigger::Hold(duration) => match self.held_since {
+                Some(since) if !self.fired && now - since >= duration.as_secs_f64() => {
+                    self.fired = true;
+                    true
+                },
+                _ => false,
+            },
+            Trigger::DoubleTap(window) => {
+                if !rising {
+                    return false;
+                }
+
+                match self.last_tap.take() {
+                    Some(last_tap) if now - last_tap <= window.as_secs_f64() => true,
+                    _ => {
+                        self.last_tap = Some(now);
+                        false
+                    },
+                }
+            },
+            Trigger::Repeat(interval) => {
+                if rising {
+                    self.next_repeat = now + interval.as_secs_f64();
+                    true
+                } else if self.held_since.is_some() && now >= self.next_repeat {
+                    self.next_repeat += interval.as_secs_f64();
+                    true
+                } else {
+                    false
+                }
+            },
+        }
+    }
+
+    /// Whether the input was held on the last update, since it was pressed.
+    pub(crate) fn is_held(&self) -> bool {
+        self.held_since.is_some()
+    }
+}
+
+/// A key binding made of one or more [`Key`] strokes, e.g. `"ctrl+k, ctrl+s"`.
+///
+/// The strokes can be preceded by a [`Trigger`] mode, e.g. `"hold:500ms f5"`
+/// or `"doubletap:300ms ctrl+q"`; the default is to fire on press. They can
+/// also be preceded by a [`MatchPolicy`], e.g. `"lenient f5"`; the default is
+/// the global [`match_policy`].
+///
+/// Unlike [`Key`], a hotkey is stateful: it keeps track of how much of the
+/// sequence has been typed and for how long it has been held across frames, so
+/// [`Hotkey::is_pressed`] must be called once per frame and takes `&mut self`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct Hotkey {
+    strokes: Vec<Key>,
+    timeout: Duration,
+    trigger: Trigger,
+    policy: Option<MatchPolicy>,
+    progress: usize,
+    last_stroke: f64,
+    trigger_state: TriggerState,
+}
+
+impl PartialEq for Hotkey {
+    fn eq(&self, other: &Self) -> bool {
+        self.strokes == other.strokes
+            && self.timeout == other.timeout
+            && self.trigger == other.trigger
+            && self.policy == other.policy
+    }
+}
+
+impl Eq for Hotkey {}
+
+impl std::fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(policy) = self.policy {
+            write!(f, "{policy} ")?;
+        }
+        if self.trigger != Trigger::Press {
+            write!(f, "{} ", self.trigger)?;
+        }
+        if self.timeout != DEFAULT_STROKE_TIMEOUT {
+            write!(f, "timeout:{}ms ", self.timeout.as_millis())?;
+        }
+
+        write!(f, "{}", Strokes(&self.strokes))
+    }
+}
+
+/// Displays strokes separated by commas, without any options.
+pub(crate) struct Strokes<'a>(pub(crate) &'a [Key]);
+
+impl std::fmt::Display for Strokes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, stroke) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{stroke}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Pretty<'_, Hotkey> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Pretty(&self.0.trigger))?;
+        for (i, stroke) in self.0.strokes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", stroke.pretty())?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Hotkey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl From<Key> for Hotkey {
+    fn from(key: Key) -> Self {
+        Self::new(vec![key])
+    }
+}
+
+impl TryFrom<&str> for Hotkey {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (options, offset) = parse_options(input)?;
+        let hotkey = Self::parse_strokes(&input[offset..]).map_err(|e| e.offset(offset))?;
+        Ok(Self {
+            policy: options.policy,
+            timeout: options.timeout.unwrap_or(DEFAULT_STROKE_TIMEOUT),
+            ..hotkey.with_trigger(options.trigger)
+        })
+    }
+}
+
+impl TryFrom<String> for Hotkey {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+/// The words preceding the strokes of a binding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Options {
+    pub(crate) trigger: Trigger,
+    pub(crate) policy: Option<MatchPolicy>,
+    /// The stroke timeout, e.g. `timeout:800ms`.
+    pub(crate) timeout: Option<Duration>,
+}
+
+const TIMEOUT_PREFIX: &str = "timeout:";
+
+/// Parses the [`Trigger`], [`MatchPolicy`] and stroke timeout words at the
+/// start of a binding and returns them along with the offset of what follows.
+pub(crate) fn parse_options(input: &str) -> Result<(Options, usize), ParseError> {
+    let mut s = input.trim_start();
+    let mut options = Options { trigger: Trigger::Press, policy: None, timeout: None };
+
+    // Options are whitespace-separated words before the strokes; key names
+    // never contain a colon, nor are they trigger or policy names. Controller
+    // prefixes like `pad1:` do, but they start an alternative.
+    while let Some((word, rest)) = s.split_once(char::is_whitespace) {
+        let offset = input.len() - s.len();
+        if let Ok(word) = word.parse::<MatchPolicy>() {
+            options.policy = Some(word);
+        } else if let Some(value) = word
+            .get(..TIMEOUT_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(TIMEOUT_PREFIX))
+            .map(|_| &word[TIMEOUT_PREFIX.len()..])
+        {
+            let offset = offset + TIMEOUT_PREFIX.len();
+            options.timeout = Some(parse_duration(value).map_err(|e| e.offset(offset))?);
+        } else if (word.contains(':') && !controller::has_slot_prefix(word))
+            || word.parse::<Trigger>().is_ok()
+        {
+            options.trigger = word.parse().map_err(|e: ParseError| e.offset(offset))?;
+        } else {
+            break;
+        }
+        s = rest.trim_start();
+    }
+
+    Ok((options, input.len() - s.len()))
+}
+
+/// Splits a sequence on the commas that separate its strokes, along with the
+/// offset of each stroke. A comma at the start of a stroke or right after a
+/// `+` is the comma key itself.
+fn split_strokes(s: &str) -> Vec<(usize, &str)> {
+    let mut strokes = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        let stroke = s[start..i].trim();
+        if c == ',' && !stroke.is_empty() && !stroke.ends_with('+') {
+            strokes.push((start, &s[start..i]));
+            start = i + 1;
+        }
+    }
+
+    strokes.push((start, &s[start..]));
+    strokes
+}
+
+impl Hotkey {
+    pub(crate) fn new(strokes: Vec<Key>) -> Self {
+        Self {
+            strokes,
+            timeout: DEFAULT_STROKE_TIMEOUT,
+            trigger: Trigger::Press,
+            policy: None,
+            progress: 0,
+            last_stroke: 0.,
+            trigger_state: TriggerState::default(),
+        }
+    }
+
+    /// Parses a sequence of strokes without any options.
+    pub(crate) fn parse_strokes(s: &str) -> Result<Self, ParseError> {
+        let strokes = split_strokes(s)
+            .into_iter()
+            .map(|(start, stroke)| Key::try_from(stroke).map_err(|e| e.offset(start)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(strokes))
+    }
+
+    /// Sets the maximum delay allowed between two consecutive strokes, which
+    /// can also be written as a `timeout:` prefix, e.g. `"timeout:800ms ctrl+k,
+    /// ctrl+s"`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets when the hotkey fires.
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    /// Overrides the global [`match_policy`] for this hotkey.
+    pub fn with_policy(mut self, policy: MatchPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn pretty(&self) -> Pretty<'_, Hotkey> {
+        Pretty(self)
+    }
+
+    pub fn strokes(&self) -> &[Key] {
+        &self.strokes
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn trigger(&self) -> Trigger {
+        self.trigger
+    }
+
+    /// The policy set on this hotkey, if it overrides the global one.
+    pub fn policy(&self) -> Option<MatchPolicy> {
+        self.policy
+    }
+
+    /// The policy this hotkey is matched with.
+    pub fn effective_policy(&self) -> MatchPolicy {
+        self.policy.unwrap_or_else(match_policy)
+    }
+
+    /// Whether the first strokes of a sequence have been typed and the
+    /// hotkey is waiting for the next one.
+    pub fn is_partial(&self) -> bool {
+        self.progress > 0
+    }
+
+    /// Clears any partially typed sequence and held state.
+    pub fn reset(&mut self) {
+        self.progress = 0;
+        self.trigger_state = TriggerState::default();
+    }
+
+    /// Advances the sequence and trigger state and returns `true` on the
+    /// frames the hotkey fires according to its [`Trigger`].
+    pub fn is_pressed(&mut self, input: &dyn InputSource) -> bool {
+        let (completed, down) = self.poll(input, self.effective_policy());
+        self.trigger_state.update(self.trigger, completed, down, input.time())
+    }
+
+    /// Advances the sequence and returns whether it was completed on this
+    /// frame and whether its last stroke is held, ignoring the trigger.
+    pub(crate) fn poll(&mut self, input: &dyn InputSource, policy: MatchPolicy) -> (bool, bool) {
+        let now = input.time();
+
+        if self.progress > 0 && now - self.last_stroke > self.timeout.as_secs_f64() {
+            self.progress = 0;
+        }
+
+        if self.strokes[self.progress].is_pressed_with(input, policy) {
+            self.progress += 1;
+            self.last_stroke = now;
+        } else if self.progress > 0 && self.strokes[0].is_pressed_with(input, policy) {
+            // The sequence was started over: count this as its first stroke.
+            self.progress = 1;
+            self.last_stroke = now;
+        }
+
+        let completed = self.progress == self.strokes.len();
+        if completed {
+            self.progress = 0;
+        }
+
+        let down = self.strokes[self.strokes.len() - 1].is_down_with(input, policy);
+        (completed, down)
+    }
+}
+
+/// Where keyboard state is read from, so that bindings can be evaluated
+/// against a live imgui frame or against scripted input in tests.
+///
+/// The generic modifier keys (`imgui::Key::ModCtrl` etc.) are down whenever
+/// either side of the modifier is.
+pub trait InputSource {
+    fn is_key_down(&self, key: imgui::Key) -> bool;
+
+    /// Whether the key was pressed on this frame, including OS key repeats.
+    fn is_key_pressed(&self, key: imgui::Key) -> bool;
+
+    /// Whether the key was pressed on this frame, excluding OS key repeats.
+    fn is_key_pressed_no_repeat(&self, key: imgui::Key) -> bool;
+
+    /// Time elapsed since the start, in seconds.
+    fn time(&self) -> f64;
+
+    /// How far an analog key such as `imgui::Key::GamepadL2` is pushed, from
+    /// 0 to 1. Digital keys are either 0 or 1.
+    fn key_analog_value(&self, key: imgui::Key) -> f32 {
+        if self.is_key_down(key) {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    /// State of the controller in `slot`, from 0 to 3, if one is connected.
+    fn controller_state_at(&self, _slot: u8) -> Option<XINPUT_STATE> {
+        None
+    }
+
+    /// State of the primary controller, if one is connected. Combinations
+    /// that don't name a slot are matched against it.
+    fn controller_state(&self) -> Option<XINPUT_STATE> {
+        self.controller_state_at(0)
+    }
+}
+
+impl InputSource for Ui {
+    fn is_key_down(&self, key: imgui::Key) -> bool {
+        let io = self.io();
+        match key {
+            imgui::Key::ModCtrl => io.key_ctrl,
+            imgui::Key::ModShift => io.key_shift,
+            imgui::Key::ModAlt => io.key_alt,
+            imgui::Key::ModSuper => io.key_super,
+            key => Ui::is_key_down(self, key),
+        }
+    }
+
+    fn is_key_pressed(&self, key: imgui::Key) -> bool {
+        Ui::is_key_pressed(self, key)
+    }
+
+    fn is_key_pressed_no_repeat(&self, key: imgui::Key) -> bool {
+        Ui::is_key_pressed_no_repeat(self, key)
+    }
+
+    fn time(&self) -> f64 {
+        Ui::time(self)
+    }
+
+    /// Modifier keys such as [`imgui::Key::ModCtrl`] have no key data and
+    /// read as fully down or up.
+    fn key_analog_value(&self, key: imgui::Key) -> f32 {
+        // SAFETY: the context is alive for as long as the frame is.
+        let keys_data = unsafe { &(*imgui::sys::igGetIO()).KeysData };
+        match keys_data.get(key as usize) {
+            Some(data) => data.AnalogValue,
+            None if InputSource::is_key_down(self, key) => 1.,
+            None => 0.,
+        }
+    }
+
+    /// The gamepad imgui's backend reports, if it reports one. The packet
+    /// number is the frame count, so that the state is read on every frame.
+    fn controller_state_at(&self, slot: u8) -> Option<XINPUT_STATE> {
+        let has_gamepad = self.io().backend_flags.contains(imgui::BackendFlags::HAS_GAMEPAD);
+        (slot == 0 && has_gamepad).then(|| XINPUT_STATE {
+            dwPacketNumber: self.frame_count() as u32,
+            Gamepad: controller::gamepad_from_keys(self),
+        })
+    }
+}
+
+/// In-memory [`InputSource`] driven frame by frame, e.g. from tests.
+///
+/// Keys pressed or repeated since the last call to
+/// [`ScriptedInput::next_frame`] count as pressed on the current frame.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    down: Vec<imgui::Key>,
+    pressed: Vec<imgui::Key>,
+    repeated: Vec<imgui::Key>,
+    analog: Vec<(imgui::Key, f32)>,
+    controllers: [Option<XINPUT_STATE>; MAX_CONTROLLERS as usize],
+    time: f64,
+}
+
+impl ScriptedInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the current frame and advances the clock.
+    pub fn next_frame(&mut self, elapsed: Duration) {
+        self.pressed.clear();
+        self.repeated.clear();
+        self.time += elapsed.as_secs_f64();
+    }
+
+    pub fn press(&mut self, key: imgui::Key) {
+        if !self.down.contains(&key) {
+            self.down.push(key);
+            self.pressed.push(key);
+        }
+    }
+
+    pub fn release(&mut self, key: imgui::Key) {
+        self.down.retain(|&k| k != key);
+        self.analog.retain(|&(k, _)| k != key);
+    }
+
+    /// Moves an analog key, pressing it past 0.1 like imgui's backends do.
+    pub fn set_analog(&mut self, key: imgui::Key, value: f32) {
+        self.release(key);
+        self.analog.push((key, value));
+        if value > 0.1 {
+            self.press(key);
+        }
+    }
+
+    /// Simulates an OS key repeat of a held key.
+    pub fn repeat(&mut self, key: imgui::Key) {
+        if self.down.contains(&key) {
+            self.repeated.push(key);
+        }
+    }
+
+    /// Holds the modifiers of `key`, then presses its key. Generic modifiers
+    /// are pressed with their left side.
+    pub fn press_key(&mut self, key: &Key) {
+        for modifier in key.modifiers.iter() {
+            self.press(Self::physical(modifier));
+        }
+        self.press(key.key);
+    }
+
+    /// Releases the key and the modifiers of `key`.
+    pub fn release_key(&mut self, key: &Key) {
+        self.release(key.key);
+        for modifier in key.modifiers.iter() {
+            self.release(Self::physical(modifier));
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.down.clear();
+        self.analog.clear();
+    }
+
+    /// Connects the primary controller with the given state, or disconnects
+    /// it.
+    pub fn set_controller_state(&mut self, state: Option<XINPUT_STATE>) {
+        self.set_controller_state_at(0, state);
+    }
+
+    /// Connects the controller in `slot` with the given state, or disconnects
+    /// it. Like XInput does, the packet number is bumped whenever the gamepad
+    /// changes. Slots past [`controller::MAX_CONTROLLERS`] are ignored, as
+    /// they can never be connected.
+    pub fn set_controller_state_at(&mut self, slot: u8, state: Option<XINPUT_STATE>) {
+        let Some(controller) = self.controllers.get_mut(slot as usize) else {
+            return;
+        };
+        let previous = *controller;
+        *controller = state.map(|mut state| {
+            state.dwPacketNumber = match previous {
+                Some(previous) if previous.Gamepad == state.Gamepad => previous.dwPacketNumber,
+                Some(previous) => previous.dwPacketNumber.wrapping_add(1),
+                None => 0,
+            };
+            state
+        });
+    }
+
+    fn physical(modifier: Modifier) -> imgui::Key {
+        match modifier {
+            Modifier::ModCtrl => imgui::Key::LeftCtrl,
+            Modifier::ModShift => imgui::Key::LeftShift,
+            Modifier::ModAlt => imgui::Key::LeftAlt,
+            Modifier::ModSuper => imgui::Key::LeftSuper,
+            modifier => modifier.into(),
+        }
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn is_key_down(&self, key: imgui::Key) -> bool {
+        let either = |left, right| self.down.contains(&left) || self.down.contains(&right);
+        match key {
+            imgui::Key::ModCtrl => either(imgui::Key::LeftCtrl, imgui::Key::RightCtrl),
+            imgui::Key::ModShift => either(imgui::Key::LeftShift, imgui::Key::RightShift),
+            imgui::Key::ModAlt => either(imgui::Key::LeftAlt, imgui::Key::RightAlt),
+            imgui::Key::ModSuper => either(imgui::Key::LeftSuper, imgui::Key::RightSuper),
+            key => self.down.contains(&key),
+        }
+    }
+
+    fn is_key_pressed(&self, key: imgui::Key) -> bool {
+        self.pressed.contains(&key) || self.repeated.contains(&key)
+    }
+
+    fn is_key_pressed_no_repeat(&self, key: imgui::Key) -> bool {
+        self.pressed.contains(&key)
     }
 
-    pub fn is_up(&self, ui: &Ui) -> bool {
-        !self.is_down(ui)
+    fn time(&self) -> f64 {
+        self.time
     }
 
-    pub fn is_pressed(&self, ui: &Ui) -> bool {
-        ui.is_key_pressed(self.key)
-            && self.modifiers.iter().all(|modifier| modifier.map(|k| k.is_down(ui)).unwrap_or(true))
-            && ModifierState::from(ui) == ModifierState::from(self.modifiers)
+    fn key_analog_value(&self, key: imgui::Key) -> f32 {
+        match self.analog.iter().find(|&&(k, _)| k == key) {
+            Some(&(_, value)) => value,
+            None => f32::from(u8::from(self.is_key_down(key))),
+        }
+    }
+
+    fn controller_state_at(&self, slot: u8) -> Option<XINPUT_STATE> {
+        self.controllers.get(slot as usize).copied().flatten()
     }
 }
 
@@ -398,4 +1387,288 @@ mod tests {
         let key: Key = "ctrl+f".parse().unwrap();
         println!("{key:?}");
     }
+
+    #[test]
+    fn test_parse_hotkey() {
+        let hotkey: Hotkey = "ctrl+k, ctrl+s".parse().unwrap();
+        assert_eq!(hotkey.strokes(), &["ctrl+k".parse().unwrap(), "ctrl+s".parse().unwrap()]);
+        assert_eq!(hotkey.to_string(), "ctrl+k, ctrl+s");
+
+        let hotkey: Hotkey = "f1".parse().unwrap();
+        assert_eq!(hotkey, Hotkey::from("f1".parse::<Key>().unwrap()));
+
+        assert!("ctrl+k,".parse::<Hotkey>().is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(_, names, _) in REPR_MAP {
+            let repr = names[0];
+            let key: Key = repr.parse().unwrap();
+            assert_eq!(key.to_string(), repr);
+
+            for (i, &(_, mod_names, _)) in MOD_REPR_MAP.iter().enumerate() {
+                let mod_repr = mod_names[0];
+                let s = format!("{mod_repr}+{repr}");
+                assert_eq!(s.parse::<Key>().unwrap().to_string(), s);
+
+                for (j, &(_, mod_names2, _)) in MOD_REPR_MAP.iter().enumerate() {
+                    let mod_repr2 = mod_names2[0];
+                    let key = format!("{mod_repr}+{mod_repr2}+{repr}").parse::<Key>().unwrap();
+                    let formatted = key.to_string();
+                    assert_eq!(formatted.parse::<Key>().unwrap(), key);
+                    if i < j {
+                        assert_eq!(formatted, format!("{mod_repr}+{mod_repr2}+{repr}"));
+                    }
+                }
+            }
+        }
+
+        for &(modifier, names, _) in MOD_REPR_MAP {
+            assert_eq!(names[0].parse::<Modifier>().unwrap(), modifier);
+            assert_eq!(modifier.to_string(), names[0]);
+        }
+
+        assert_eq!("CTRL+Shift+F1".parse::<Key>().unwrap().to_string(), "ctrl+shift+f1");
+        assert_eq!("shift+ctrl+f1".parse::<Key>().unwrap().to_string(), "ctrl+shift+f1");
+        assert_eq!(
+            "lsuper+rshift+lalt+ctrl+f".parse::<Key>().unwrap().to_string(),
+            "ctrl+rshift+lalt+lsuper+f"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_hotkey() {
+        for s in ["ctrl+k, ctrl+s", ",", "ctrl+,", "ctrl+,, ,", "right, ctrl+right, ."] {
+            assert_eq!(s.parse::<Hotkey>().unwrap().to_string(), s);
+        }
+
+        assert_eq!("ctrl+k,ctrl+s".parse::<Hotkey>().unwrap().to_string(), "ctrl+k, ctrl+s");
+        assert_eq!("ctrl+,,,".parse::<Hotkey>().unwrap().strokes().len(), 2);
+
+        for s in ["hold:500ms f5", "release ctrl+k, ctrl+s", "doubletap:300ms q", "repeat:50ms ,"] {
+            assert_eq!(s.parse::<Hotkey>().unwrap().to_string(), s);
+        }
+
+        assert_eq!("press f5".parse::<Hotkey>().unwrap().to_string(), "f5");
+        for s in ["lenient f5", "side-specific lctrl+f", "strict release ctrl+k, ctrl+s"] {
+            assert_eq!(s.parse::<Hotkey>().unwrap().to_string(), s);
+        }
+        assert_eq!(
+            "hold:1s LENIENT f5".parse::<Hotkey>().unwrap().to_string(),
+            "lenient hold:1000ms f5"
+        );
+        assert_eq!("hold:1.5s f5".parse::<Hotkey>().unwrap().to_string(), "hold:1500ms f5");
+        assert_eq!(
+            "hold f5".parse::<Hotkey>().unwrap().trigger(),
+            Trigger::Hold(Duration::from_millis(500))
+        );
+        assert!("hold:500 f5".parse::<Hotkey>().is_err());
+        assert!("hold:500ms".parse::<Hotkey>().is_err());
+        assert!("release:1s f5".parse::<Hotkey>().is_err());
+
+        let hotkey = "timeout:800ms ctrl+k, ctrl+s".parse::<Hotkey>().unwrap();
+        assert_eq!(hotkey.timeout(), Duration::from_millis(800));
+        assert_eq!(hotkey.to_string(), "timeout:800ms ctrl+k, ctrl+s");
+        assert_eq!(
+            "Timeout:2s release ctrl+k, ctrl+s".parse::<Hotkey>().unwrap().to_string(),
+            "release timeout:2000ms ctrl+k, ctrl+s"
+        );
+        assert_eq!("timeout:1s f5".parse::<Hotkey>().unwrap().to_string(), "f5");
+        let err = "timeout:800 f5".parse::<Hotkey>().unwrap_err();
+        assert_eq!(err.span(), 8..11);
+    }
+
+    #[test]
+    fn test_names() {
+        // Names and aliases must not be ambiguous, and a name shared between
+        // a key and a modifier must mean the same physical key.
+        let names = all_names(REPR_MAP).collect::<Vec<_>>();
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[i + 1..].contains(name), "duplicate key name {name}");
+            assert!(!name.contains(['+', '|', ' ']), "reserved char in {name}");
+        }
+
+        for &(modifier, mod_names, _) in MOD_REPR_MAP {
+            for name in mod_names {
+                if let Some(key) = find_by_name(REPR_MAP, name) {
+                    assert_eq!(key, imgui::Key::from(modifier));
+                }
+            }
+        }
+
+        for (alias, canonical) in [
+            ("esc", "escape"),
+            ("Return", "enter"),
+            ("pagedown", "pgdown"),
+            ("num*", "kpmultiply"),
+            ("win+e", "super+e"),
+            ("lwin+num0", "lsuper+kp0"),
+            ("control+del", "ctrl+delete"),
+        ] {
+            assert_eq!(alias.parse::<Key>().unwrap().to_string(), canonical);
+        }
+
+        let pretty = |s: &str| s.parse::<Hotkey>().unwrap().pretty().to_string();
+        assert_eq!(pretty("ctrl+f1"), "Ctrl+F1");
+        assert_eq!(pretty("num*"), "Num *");
+        assert_eq!(pretty("lctrl+shift+pgdown"), "Left Ctrl+Shift+Page Down");
+        assert_eq!(pretty("hold:1s ctrl+k, ctrl+,"), "Hold Ctrl+K, Ctrl+,");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = "ctrl+pgdwn".parse::<Key>().unwrap_err();
+        assert_eq!(err.token(), "pgdwn");
+        assert_eq!(err.span(), 5..10);
+        assert_eq!(err.suggestions(), ["pgdown"]);
+        assert_eq!(
+            err.to_string(),
+            "Could not find key \"pgdwn\" at 5..10, did you mean `pgdown`?"
+        );
+
+        let err = "ctlr+f".parse::<Key>().unwrap_err();
+        assert!(matches!(err, ParseError::UnknownModifier { .. }));
+        assert_eq!((err.span(), err.suggestions()), (0..4, &["ctrl"][..]));
+
+        assert_eq!("ctrl+".parse::<Key>(), Err(ParseError::Empty { span: 5..5 }));
+        assert_eq!("+f".parse::<Key>(), Err(ParseError::Empty { span: 0..0 }));
+        assert_eq!("".parse::<Hotkey>(), Err(ParseError::Empty { span: 0..0 }));
+        assert_eq!("ctrl+k,".parse::<Hotkey>(), Err(ParseError::Empty { span: 7..7 }));
+
+        // Spans are relative to the whole hotkey.
+        let err = "hold:1s ctrl+k, ctrl+pgdwn".parse::<Hotkey>().unwrap_err();
+        assert_eq!((err.token(), err.span()), ("pgdwn", 21..26));
+
+        let err = "hodl:1s f5".parse::<Hotkey>().unwrap_err();
+        assert!(matches!(err, ParseError::UnknownTrigger { .. }));
+        assert_eq!((err.span(), err.suggestions()), (0..4, &["hold"][..]));
+
+        let err = " hold:1 f5".parse::<Hotkey>().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDuration { .. }));
+        assert_eq!((err.token(), err.span()), ("1", 6..7));
+
+        let err = "release:1s f5".parse::<Hotkey>().unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedDuration { .. }));
+        assert_eq!(err.span(), 0..10);
+    }
+
+    /// Runs a trigger over a list of `(pressed, down)` frames spaced 100ms
+    /// apart and returns the indices of the frames where it fired.
+    fn run_trigger(trigger: Trigger, frames: &[(bool, bool)]) -> Vec<usize> {
+        let mut state = TriggerState::default();
+        frames
+            .iter()
+            .enumerate()
+            .filter(|&(i, &(pressed, down))| state.update(trigger, pressed, down, i as f64 * 0.1))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn test_triggers() {
+        const P: (bool, bool) = (true, true);
+        const D: (bool, bool) = (false, true);
+        const U: (bool, bool) = (false, false);
+
+        let ms = Duration::from_millis;
+
+        assert_eq!(run_trigger(Trigger::Press, &[U, P, D, D, U, P]), [1, 5]);
+        assert_eq!(run_trigger(Trigger::Release, &[U, P, D, D, U, P, U]), [4, 6]);
+        assert_eq!(run_trigger(Trigger::Hold(ms(250)), &[P, D, D, D, D, U, P, D, U]), [3]);
+        assert_eq!(run_trigger(Trigger::Hold(ms(0)), &[U, P, D, U]), [1]);
+        assert_eq!(run_trigger(Trigger::DoubleTap(ms(250)), &[P, U, P, U, P, U, U, U, P]), [2]);
+        assert_eq!(run_trigger(Trigger::DoubleTap(ms(250)), &[P, U, U, U, P, U, P]), [6]);
+        assert_eq!(run_trigger(Trigger::Repeat(ms(200)), &[P, D, D, D, D, U, D]), [0, 2, 4]);
+        // Key repeats while held don't restart holds nor count as new taps.
+        assert_eq!(run_trigger(Trigger::Hold(ms(250)), &[P, P, P, P, P, U]), [3]);
+        assert_eq!(run_trigger(Trigger::DoubleTap(ms(250)), &[P, P, P, U]), Vec::<usize>::new());
+    }
+
+    /// Types each stroke of `keys` on its own frame, `elapsed` apart, and
+    /// returns the frames where `hotkey` fired.
+    fn run_hotkey(hotkey: &str, keys: &[&str], elapsed: Duration) -> Vec<usize> {
+        let mut hotkey: Hotkey = hotkey.parse().unwrap();
+        let mut input = ScriptedInput::new();
+        let mut fired = Vec::new();
+
+        for (i, key) in keys.iter().enumerate() {
+            let key: Key = key.parse().unwrap();
+            input.press_key(&key);
+            if hotkey.is_pressed(&input) {
+                fired.push(i);
+            }
+            input.release_all();
+            input.next_frame(elapsed);
+        }
+
+        fired
+    }
+
+    #[test]
+    fn test_match_policies() {
+        use MatchPolicy::*;
+
+        let matches = |key: &str, held: &[imgui::Key], policy| {
+            let mut input = ScriptedInput::new();
+            held.iter().for_each(|&k| input.press(k));
+            key.parse::<Key>().unwrap().is_pressed_with(&input, policy)
+        };
+
+        use imgui::Key::{LeftCtrl, LeftShift, RightCtrl, F, F5};
+
+        assert!(matches("f5", &[F5], Strict));
+        assert!(!matches("f5", &[LeftShift, F5], Strict));
+        assert!(matches("f5", &[LeftShift, F5], Lenient));
+        assert!(!matches("f5", &[LeftShift, F5], SideSpecific));
+        assert!(!matches("ctrl+f5", &[F5], Lenient));
+
+        assert!(matches("lctrl+f", &[LeftCtrl, RightCtrl, F], Strict));
+        assert!(!matches("lctrl+f", &[LeftCtrl, RightCtrl, F], SideSpecific));
+        assert!(matches("lctrl+f", &[LeftCtrl, F], SideSpecific));
+        assert!(!matches("lctrl+f", &[RightCtrl, F], SideSpecific));
+        assert!(matches("ctrl+f", &[RightCtrl, F], SideSpecific));
+        assert!(matches("ctrl+f", &[LeftCtrl, RightCtrl, F], SideSpecific));
+
+        // The policy of the hotkey wins over the global one.
+        let mut hotkey: Hotkey = "lenient f5".parse().unwrap();
+        let mut input = ScriptedInput::new();
+        input.press(LeftShift);
+        input.press(F5);
+        assert!(hotkey.is_pressed(&input));
+        assert_eq!(hotkey.effective_policy(), Lenient);
+
+        assert_eq!("Side-Specific".parse::<MatchPolicy>(), Ok(SideSpecific));
+        assert_eq!("lenent".parse::<MatchPolicy>().unwrap_err().suggestions(), ["lenient"]);
+    }
+
+    #[test]
+    fn test_scripted_input() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(run_hotkey("ctrl+f", &["f", "ctrl+f", "shift+ctrl+f", "lctrl+f"], ms(10)), [
+            1, 3
+        ]);
+        assert_eq!(run_hotkey("lctrl+f", &["rctrl+f", "lctrl+f"], ms(10)), [1]);
+        assert_eq!(run_hotkey("ctrl+k, ctrl+s", &["ctrl+s", "ctrl+k", "ctrl+s"], ms(10)), [2]);
+        assert_eq!(
+            run_hotkey("ctrl+k, ctrl+s", &["ctrl+k", "ctrl+s"], ms(1500)),
+            Vec::<usize>::new()
+        );
+        assert_eq!(run_hotkey("ctrl+k, ctrl+s", &["ctrl+k", "ctrl+k", "ctrl+s"], ms(10)), [2]);
+
+        let mut input = ScriptedInput::new();
+        input.press(imgui::Key::RightShift);
+        input.press(imgui::Key::F5);
+        assert!("shift+f5".parse::<Key>().unwrap().is_pressed(&input));
+        assert_eq!(Key::capture(&input), "shift+f5".parse().ok());
+
+        input.next_frame(Duration::from_millis(10));
+        assert!(!"shift+f5".parse::<Key>().unwrap().is_pressed(&input));
+        assert!("shift+f5".parse::<Key>().unwrap().is_down(&input));
+
+        input.repeat(imgui::Key::F5);
+        assert!("shift+f5".parse::<Key>().unwrap().is_pressed(&input));
+        assert_eq!(Key::capture(&input), None);
+    }
 }
@@ -1,7 +1,17 @@
 // #![deny(missing_docs)]
 
+pub mod binding;
 pub mod controller;
+pub mod error;
+pub mod headless;
 pub mod key;
+pub mod log;
+pub mod registry;
+pub mod replay;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(test)]
+mod test_support;
 pub mod widgets;
 
 pub use crossbeam_channel;
@@ -0,0 +1,102 @@
+//! Structured log events sent by [`Widget::log`].
+//!
+//! Consumers that only display the messages can keep receiving strings
+//! through [`log_messages`], or by converting the events with
+//! [`String::from`].
+
+use std::fmt;
+use std::time::SystemTime;
+
+use crossbeam_channel::Sender;
+
+use crate::widgets::Widget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Severity::Debug => "DEBUG",
+            Severity::Info => "INFO",
+            Severity::Warning => "WARN",
+            Severity::Error => "ERROR",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEvent {
+    pub severity: Severity,
+    /// When the event happened, rather than when it was sent.
+    pub timestamp: SystemTime,
+    /// Identifies the widget that produced the event, e.g. a flag's label.
+    pub source: String,
+    pub message: String,
+    /// Key-value pairs for consumers that need more than the message, e.g.
+    /// the path of a loaded savefile.
+    pub payload: Vec<(&'static str, String)>,
+}
+
+impl LogEvent {
+    pub fn new(severity: Severity, source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            timestamp: SystemTime::now(),
+            source: source.into(),
+            message: message.into(),
+            payload: Vec::new(),
+        }
+    }
+
+    pub fn info(source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Info, source, message)
+    }
+
+    pub fn warning(source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, source, message)
+    }
+
+    pub fn error(source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, source, message)
+    }
+
+    pub fn with_payload(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.payload.push((key, value.to_string()));
+        self
+    }
+
+    /// Returns the first payload value with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.payload.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Displays the message only, like the plain string logs did.
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<LogEvent> for String {
+    fn from(event: LogEvent) -> Self {
+        event.message
+    }
+}
+
+/// Compatibility shim for consumers of plain string logs: collects the events
+/// of the widget and sends their messages to `tx`.
+pub fn log_messages(widget: &mut dyn Widget, tx: &Sender<String>) {
+    let (event_tx, event_rx) = crossbeam_channel::unbounded();
+    widget.log(event_tx);
+
+    for event in event_rx.try_iter() {
+        tx.send(event.into()).ok();
+    }
+}
@@ -0,0 +1,397 @@
+//! Table of all the hotkeys of a widget tree, with conflict detection.
+//!
+//! Slots are identified by name. Names are unique within a tree as long as
+//! its widgets are, since groups prefix the names of their children; when two
+//! identical widgets expose the same name, the later ones are told apart by a
+//! ` #2`, ` #3`... suffix, in the order they are visited.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use crossbeam_channel::Sender;
+
+use crate::binding::Binding;
+use crate::key::{match_policy, Hotkey, MatchPolicy};
+use crate::log::LogEvent;
+use crate::widgets::Widget;
+
+/// Where a hotkey is listened to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Always, through [`Widget::interact`].
+    Global,
+    /// Only while the widget's popup is open, e.g. the close key of a group.
+    Popup,
+}
+
+/// Bindings of a widget tree by slot name, as edited by the user. Unbound
+/// slots map to `None`.
+pub type HotkeyConfig = BTreeMap<String, Option<Binding>>;
+
+/// A binding slot exposed by [`Widget::hotkeys`].
+pub struct HotkeySlot<'a> {
+    pub name: &'a str,
+    pub scope: Scope,
+    pub binding: &'a mut Option<Binding>,
+}
+
+/// Tells apart the slots that share a name, across one or more widgets
+/// visited in the same order every time.
+#[derive(Debug, Default)]
+pub(crate) struct SlotNames(HashMap<String, usize>);
+
+impl SlotNames {
+    pub(crate) fn unique<'a>(&mut self, name: &'a str) -> Cow<'a, str> {
+        let count = self.0.entry(name.to_string()).or_default();
+        *count += 1;
+        match *count {
+            1 => Cow::Borrowed(name),
+            n => Cow::Owned(format!("{name} #{n}")),
+        }
+    }
+}
+
+/// Visits the slots of a widget and its children, with unique names.
+pub(crate) fn visit_unique(
+    widget: &mut dyn Widget,
+    names: &mut SlotNames,
+    visit: &mut dyn FnMut(HotkeySlot<'_>),
+) {
+    widget.hotkeys(&mut |slot| {
+        let name = names.unique(slot.name);
+        visit(HotkeySlot { name: &name, ..slot })
+    });
+}
+
+#[derive(Debug, Clone)]
+pub struct RegistryEntry {
+    pub name: String,
+    pub scope: Scope,
+    pub binding: Binding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both entries are bound to the very same inputs.
+    Exact,
+    /// Pressing one of the entries also fires the other, e.g. `ctrl+f` and
+    /// `f` under a lenient policy, `ctrl+k` and `ctrl+k, ctrl+s`, or `l1` and
+    /// `l1+a`.
+    Overlap,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Conflict<'a> {
+    pub kind: ConflictKind,
+    pub first: &'a RegistryEntry,
+    pub second: &'a RegistryEntry,
+}
+
+impl std::fmt::Display for Conflict<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ConflictKind::Exact => write!(
+                f,
+                "Hotkey conflict: \"{}\" and \"{}\" are both bound to {}",
+                self.first.name, self.second.name, self.first.binding
+            ),
+            ConflictKind::Overlap => write!(
+                f,
+                "Hotkey conflict: \"{}\" ({}) overlaps with \"{}\" ({})",
+                self.first.name, self.first.binding, self.second.name, self.second.binding
+            ),
+        }
+    }
+}
+
+/// Collects the bindings of widgets to detect conflicts and to display the
+/// full binding table.
+///
+/// Bindings are matched with their own [`MatchPolicy`] if they have one, and
+/// with the registry's otherwise, which defaults to the global one.
+#[derive(Debug)]
+pub struct HotkeyRegistry {
+    entries: Vec<RegistryEntry>,
+    policy: MatchPolicy,
+}
+
+impl Default for HotkeyRegistry {
+    fn default() -> Self {
+        Self::new(match_policy())
+    }
+}
+
+impl HotkeyRegistry {
+    pub fn new(policy: MatchPolicy) -> Self {
+        Self { entries: Vec::new(), policy }
+    }
+
+    pub fn register(&mut self, name: &str, scope: Scope, binding: &Binding) {
+        self.entries.push(RegistryEntry {
+            name: name.to_string(),
+            scope,
+            binding: binding.clone(),
+        });
+    }
+
+    /// Registers all the bound slots of a widget and its children.
+    pub fn register_widget(&mut self, widget: &mut dyn Widget) {
+        visit_unique(widget, &mut SlotNames::default(), &mut |slot| {
+            if let Some(binding) = slot.binding {
+                self.register(slot.name, slot.scope, binding);
+            }
+        });
+    }
+
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries
+    }
+
+    pub fn conflicts(&self) -> Vec<Conflict<'_>> {
+        let mut conflicts = Vec::new();
+
+        for (i, first) in self.entries.iter().enumerate() {
+            for second in &self.entries[i + 1..] {
+                if let Some(kind) = self.conflict_kind(first.scope, &first.binding, second) {
+                    conflicts.push(Conflict { kind, first, second });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// The registered entries a new binding would conflict with.
+    pub fn conflicts_with(
+        &self,
+        scope: Scope,
+        binding: &Binding,
+    ) -> Vec<(ConflictKind, &RegistryEntry)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((self.conflict_kind(scope, binding, entry)?, entry)))
+            .collect()
+    }
+
+    /// Sends a message for each conflict.
+    ///
+    /// Nothing checks the bindings of a tree on its own: hosts should register
+    /// their widgets and call this at startup, unless the tree is wrapped in a
+    /// [`HotkeyEditor`](crate::widgets::hotkey_editor::HotkeyEditor), which
+    /// reports the conflicts of its children when it is created.
+    pub fn log(&self, tx: Sender<LogEvent>) {
+        for conflict in self.conflicts() {
+            tx.send(LogEvent::warning("Hotkeys", conflict.to_string())).ok();
+        }
+    }
+
+    fn conflict_kind(
+        &self,
+        scope: Scope,
+        binding: &Binding,
+        entry: &RegistryEntry,
+    ) -> Option<ConflictKind> {
+        let other = &entry.binding;
+
+        // Popups are modal, so their keys can't be listened to together.
+        if scope == Scope::Popup && entry.scope == Scope::Popup {
+            None
+        } else if binding == other {
+            Some(ConflictKind::Exact)
+        } else if self.bindings_overlap(binding, other) {
+            Some(ConflictKind::Overlap)
+        } else {
+            None
+        }
+    }
+
+    /// Whether some input fires both bindings.
+    fn bindings_overlap(&self, binding: &Binding, other: &Binding) -> bool {
+        let policy = binding.policy().unwrap_or(self.policy);
+        let other_policy = other.policy().unwrap_or(self.policy);
+
+        let keys_overlap = binding.keys().iter().any(|hotkey| {
+            other.keys().iter().any(|other_hotkey| {
+                overlaps(hotkey, other_hotkey, policy)
+                    || overlaps(other_hotkey, hotkey, other_policy)
+            })
+        });
+
+        let buttons_overlap = binding.buttons().iter().any(|buttons| {
+            other.buttons().iter().any(|other_buttons| {
+                buttons.is_subset(other_buttons) || other_buttons.is_subset(buttons)
+            })
+        });
+
+        keys_overlap || buttons_overlap
+    }
+}
+
+/// Whether typing `other` fires `hotkey` along the way.
+fn overlaps(hotkey: &Hotkey, other: &Hotkey, policy: MatchPolicy) -> bool {
+    let (strokes, other_strokes) = (hotkey.strokes(), other.strokes());
+
+    strokes.len() <= other_strokes.len()
+        && strokes.iter().zip(other_strokes).all(|(key, other)| key.fires_on(other, policy))
+}
+
+/// Collects the bindings of a widget and its children, e.g. to persist them.
+pub fn hotkey_config(widget: &mut dyn Widget) -> HotkeyConfig {
+    let mut config = HotkeyConfig::new();
+    visit_unique(widget, &mut SlotNames::default(), &mut |slot| {
+        config.insert(slot.name.to_string(), slot.binding.clone());
+    });
+    config
+}
+
+/// Rebinds the slots of a widget and its children that appear in `config`.
+pub fn apply_hotkey_config(widget: &mut dyn Widget, config: &HotkeyConfig) {
+    visit_unique(widget, &mut SlotNames::default(), &mut |slot| {
+        if let Some(binding) = config.get(slot.name) {
+            *slot.binding = binding.clone();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(policy: MatchPolicy, hotkeys: &[&str]) -> HotkeyRegistry {
+        let mut registry = HotkeyRegistry::new(policy);
+        for hotkey in hotkeys {
+            registry.register(hotkey, Scope::Global, &hotkey.parse().unwrap());
+        }
+        registry
+    }
+
+    fn conflicts(policy: MatchPolicy, hotkeys: &[&str]) -> Vec<(ConflictKind, String, String)> {
+        registry(policy, hotkeys)
+            .conflicts()
+            .into_iter()
+            .map(|c| (c.kind, c.first.name.clone(), c.second.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_conflicts() {
+        use ConflictKind::*;
+        use MatchPolicy::*;
+
+        let c = |kind, a: &str, b: &str| (kind, a.to_string(), b.to_string());
+
+        assert_eq!(conflicts(Strict, &["ctrl+f", "f", "shift+f", "ctrl+g"]), []);
+        assert_eq!(conflicts(Lenient, &["ctrl+f", "f", "ctrl+g"]), [c(Overlap, "ctrl+f", "f")]);
+        assert_eq!(conflicts(Lenient, &["ctrl+f", "shift+f", "ctrl+shift+f"]), [
+            c(Overlap, "ctrl+f", "ctrl+shift+f"),
+            c(Overlap, "shift+f", "ctrl+shift+f")
+        ]);
+        assert_eq!(conflicts(Strict, &["CTRL+F", "ctrl+f"]), [c(Exact, "CTRL+F", "ctrl+f")]);
+        assert_eq!(conflicts(Strict, &["lctrl+f", "ctrl+f"]), [c(Overlap, "lctrl+f", "ctrl+f")]);
+        assert_eq!(conflicts(Strict, &["lctrl+f", "rctrl+f"]), []);
+        assert_eq!(conflicts(Strict, &["ctrl+k", "ctrl+k, ctrl+s", "ctrl+s"]), [c(
+            Overlap,
+            "ctrl+k",
+            "ctrl+k, ctrl+s"
+        )]);
+        assert_eq!(conflicts(Strict, &["hold:1s f5", "f5"]), [c(Overlap, "hold:1s f5", "f5")]);
+        assert_eq!(conflicts(Strict, &["lenient f5", "shift+f5"]), [c(
+            Overlap,
+            "lenient f5",
+            "shift+f5"
+        )]);
+        assert_eq!(conflicts(Lenient, &["strict f5", "shift+f5"]), []);
+        assert_eq!(conflicts(SideSpecific, &["lctrl+f", "lctrl+rctrl+f"]), []);
+        assert_eq!(conflicts(Strict, &["lctrl+f", "lctrl+rctrl+f"]), [c(
+            Overlap,
+            "lctrl+f",
+            "lctrl+rctrl+f"
+        )]);
+        assert_eq!(conflicts(Strict, &["f1 | l1+a", "f2 | pad:a+l1", "l1", "r1"]), [
+            c(Overlap, "f1 | l1+a", "f2 | pad:a+l1"),
+            c(Overlap, "f1 | l1+a", "l1"),
+            c(Overlap, "f2 | pad:a+l1", "l1")
+        ]);
+    }
+
+    #[test]
+    fn test_hotkey_config() {
+        use crate::test_support::TestFlag;
+        use crate::widgets::flag::FlagWidget;
+        use crate::widgets::group::Group;
+
+        let mut group = Group::new("Flags", "escape".parse().unwrap(), vec![
+            Box::new(FlagWidget::new("a", TestFlag::default(), "f1".parse().ok())),
+            Box::new(FlagWidget::new("b", TestFlag::default(), None)),
+        ]);
+
+        let mut config = hotkey_config(&mut group);
+        assert_eq!(config.len(), 3);
+        assert_eq!(config["Flags/Close"], "escape".parse().ok());
+        assert_eq!(config["Flags/a"], "f1".parse().ok());
+        assert_eq!(config["Flags/b"], None);
+
+        config.insert("Flags/a".to_string(), None);
+        config.insert("Flags/b".to_string(), "ctrl+k, ctrl+b".parse().ok());
+        config.remove("Flags/Close");
+        apply_hotkey_config(&mut group, &config);
+
+        let config = hotkey_config(&mut group);
+        assert_eq!(config["Flags/Close"], "escape".parse().ok());
+        assert_eq!(config["Flags/a"], None);
+        assert_eq!(config["Flags/b"], "ctrl+k, ctrl+b".parse().ok());
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        use crate::widgets::group::Group;
+        use crate::widgets::stats_editor::{Datum, Stats, StatsEditor};
+
+        struct TestStats;
+
+        impl Stats for TestStats {
+            fn data(&mut self) -> Option<impl Iterator<Item = Datum<'_>>> {
+                None::<std::iter::Empty<Datum<'_>>>
+            }
+
+            fn read(&mut self) {}
+
+            fn write(&mut self) {}
+
+            fn clear(&mut self) {}
+        }
+
+        let mut group = Group::new("Stats", "escape".parse().unwrap(), vec![
+            Box::new(StatsEditor::new(TestStats, "f1".parse().ok(), None)),
+            Box::new(StatsEditor::new(TestStats, "f2".parse().ok(), None)),
+        ]);
+
+        let mut config = hotkey_config(&mut group);
+        assert_eq!(config["Stats/Edit stats"], "f1".parse().ok());
+        assert_eq!(config["Stats/Edit stats #2"], "f2".parse().ok());
+        assert_eq!(config.len(), 5);
+
+        config.insert("Stats/Edit stats #2".to_string(), "f3".parse().ok());
+        apply_hotkey_config(&mut group, &config);
+        let config = hotkey_config(&mut group);
+        assert_eq!(config["Stats/Edit stats"], "f1".parse().ok());
+        assert_eq!(config["Stats/Edit stats #2"], "f3".parse().ok());
+
+        let mut registry = HotkeyRegistry::new(MatchPolicy::Strict);
+        registry.register_widget(&mut group);
+        let names: Vec<_> = registry.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Stats/Close", "Stats/Edit stats", "Stats/Edit stats #2"]);
+    }
+
+    #[test]
+    fn test_popup_scope() {
+        let mut registry = registry(MatchPolicy::Strict, &["escape"]);
+        let escape = "escape".parse().unwrap();
+        registry.register("close 1", Scope::Popup, &escape);
+        registry.register("close 2", Scope::Popup, &escape);
+
+        let conflicts = registry.conflicts();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|c| c.first.name == "escape"));
+    }
+}
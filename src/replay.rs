@@ -0,0 +1,568 @@
+//! Recording of the input of every frame, and replay through the same paths
+//! as live input, e.g. to reproduce a hotkey bug from a user's recording.
+//!
+//! # File format
+//!
+//! Little-endian, starting with the magic `PTIN` and a version byte, followed
+//! by the frames until the end of the file. Each frame is:
+//!
+//! - the time in seconds, as `f64`;
+//! - the number of keys with any state, as `u8`, then for each one its index in
+//!   [`recorded_keys`] and its state flags, as `u8`s;
+//! - the number of analog values that don't follow from the key states, as
+//!   `u8`, then for each one the key's index as `u8` and the value as `f32`;
+//! - the mask of the connected controller slots and the mask of the slots whose
+//!   state changed since the previous frame, as `u8`s, then for each changed
+//!   and connected slot its packet number as `u32`, buttons as `u16`, triggers
+//!   as `u8`s and stick axes as `i16`s, like `XINPUT_STATE`.
+//!
+//! Idle frames take 12 bytes.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use windows::Win32::UI::Input::XboxController::{
+    XINPUT_GAMEPAD, XINPUT_GAMEPAD_BUTTON_FLAGS, XINPUT_STATE,
+};
+
+use crate::controller::{self, MAX_CONTROLLERS};
+use crate::key::InputSource;
+
+const MAGIC: &[u8; 4] = b"PTIN";
+const VERSION: u8 = 1;
+
+const DOWN: u8 = 1 << 0;
+const PRESSED: u8 = 1 << 1;
+const REPEATED: u8 = 1 << 2;
+
+const MODIFIERS: [imgui::Key; 4] =
+    [imgui::Key::ModCtrl, imgui::Key::ModShift, imgui::Key::ModAlt, imgui::Key::ModSuper];
+
+/// The keys whose state is recorded: all the named imgui keys, then the
+/// modifiers. A key is stored as its index in this list.
+pub fn recorded_keys() -> impl Iterator<Item = imgui::Key> {
+    imgui::Key::VARIANTS.into_iter().chain(MODIFIERS)
+}
+
+fn key_at(index: u8) -> Option<imgui::Key> {
+    recorded_keys().nth(index as usize)
+}
+
+fn key_index(key: imgui::Key) -> u8 {
+    recorded_keys().position(|k| k == key).expect("only recorded keys are stored") as u8
+}
+
+/// The input of one frame, as an [`InputSource`]. The keys are only stored
+/// as their index in [`recorded_keys`] in files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedFrame {
+    time: f64,
+    keys: Vec<(imgui::Key, u8)>,
+    analog: Vec<(imgui::Key, f32)>,
+    controllers: [Option<XINPUT_STATE>; MAX_CONTROLLERS as usize],
+}
+
+impl RecordedFrame {
+    /// Reads the state of all the recorded keys and controllers.
+    pub fn capture(input: &dyn InputSource) -> Self {
+        let mut frame = RecordedFrame { time: input.time(), ..Default::default() };
+
+        for key in recorded_keys() {
+            let down = input.is_key_down(key);
+            let flags = (if down { DOWN } else { 0 })
+                | (if input.is_key_pressed_no_repeat(key) { PRESSED } else { 0 })
+                | (if input.is_key_pressed(key) { REPEATED } else { 0 });
+
+            if flags != 0 {
+                frame.keys.push((key, flags));
+            }
+
+            let analog = input.key_analog_value(key);
+            if analog != f32::from(u8::from(down)) {
+                frame.analog.push((key, analog));
+            }
+        }
+
+        for (slot, state) in frame.controllers.iter_mut().enumerate() {
+            *state = input.controller_state_at(slot as u8);
+        }
+
+        frame
+    }
+
+    fn flags(&self, key: imgui::Key) -> u8 {
+        self.keys.iter().find(|&&(k, _)| k == key).map(|&(_, flags)| flags).unwrap_or(0)
+    }
+
+    /// The recorded keys that are down, excluding the modifiers.
+    fn keys_down(&self) -> impl Iterator<Item = imgui::Key> + '_ {
+        self.keys
+            .iter()
+            .filter(|&&(_, flags)| flags & DOWN != 0)
+            .map(|&(key, _)| key)
+            .filter(|key| !MODIFIERS.contains(key))
+    }
+}
+
+impl InputSource for RecordedFrame {
+    fn is_key_down(&self, key: imgui::Key) -> bool {
+        self.flags(key) & DOWN != 0
+    }
+
+    fn is_key_pressed(&self, key: imgui::Key) -> bool {
+        self.flags(key) & REPEATED != 0
+    }
+
+    fn is_key_pressed_no_repeat(&self, key: imgui::Key) -> bool {
+        self.flags(key) & PRESSED != 0
+    }
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn key_analog_value(&self, key: imgui::Key) -> f32 {
+        match self.analog.iter().find(|&&(k, _)| k == key) {
+            Some(&(_, value)) => value,
+            None => f32::from(u8::from(self.is_key_down(key))),
+        }
+    }
+
+    fn controller_state_at(&self, slot: u8) -> Option<XINPUT_STATE> {
+        self.controllers.get(slot as usize).copied().flatten()
+    }
+}
+
+/// A sequence of recorded frames.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+
+        let mut previous = [None; MAX_CONTROLLERS as usize];
+
+        for frame in &self.frames {
+            w.write_all(&frame.time.to_le_bytes())?;
+
+            w.write_all(&[count(frame.keys.len())?])?;
+            for &(key, flags) in &frame.keys {
+                w.write_all(&[key_index(key), flags])?;
+            }
+
+            w.write_all(&[count(frame.analog.len())?])?;
+            for &(key, value) in &frame.analog {
+                w.write_all(&[key_index(key)])?;
+                w.write_all(&value.to_le_bytes())?;
+            }
+
+            let (mut connected, mut changed) = (0u8, 0u8);
+            for (slot, state) in frame.controllers.iter().enumerate() {
+                connected |= u8::from(state.is_some()) << slot;
+                changed |= u8::from(!same_state(state, &previous[slot])) << slot;
+            }
+            w.write_all(&[connected, changed])?;
+
+            for (slot, state) in frame.controllers.iter().enumerate() {
+                if let Some(state) = state.filter(|_| changed & (1 << slot) != 0) {
+                    write_state(&mut w, &state)?;
+                }
+            }
+
+            previous = frame.controllers;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from(mut r: impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("Not an input recording".to_string()));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data(format!("Unsupported recording version {}", header[4])));
+        }
+
+        let mut frames: Vec<RecordedFrame> = Vec::new();
+
+        loop {
+            let mut time = [0u8; 8];
+            match r.read_exact(&mut time) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                result => result?,
+            }
+
+            let frame_index = frames.len();
+            let invalid_key =
+                |index| invalid_data(format!("Unknown key index {index} in frame {frame_index}"));
+
+            let mut frame = RecordedFrame { time: f64::from_le_bytes(time), ..Default::default() };
+
+            for _ in 0..read_u8(&mut r)? {
+                let [index, flags] = read_array(&mut r)?;
+                frame.keys.push((key_at(index).ok_or_else(|| invalid_key(index))?, flags));
+            }
+
+            for _ in 0..read_u8(&mut r)? {
+                let index = read_u8(&mut r)?;
+                let key = key_at(index).ok_or_else(|| invalid_key(index))?;
+                frame.analog.push((key, f32::from_le_bytes(read_array(&mut r)?)));
+            }
+
+            let [connected, changed] = read_array(&mut r)?;
+            let previous = frames.last().map(|f| f.controllers).unwrap_or_default();
+
+            for (slot, state) in frame.controllers.iter_mut().enumerate() {
+                *state = match (connected & (1 << slot), changed & (1 << slot)) {
+                    (0, _) => None,
+                    (_, 0) => previous[slot],
+                    _ => Some(read_state(&mut r)?),
+                };
+            }
+
+            frames.push(frame);
+        }
+
+        Ok(Recording { frames })
+    }
+}
+
+fn count(len: usize) -> io::Result<u8> {
+    u8::try_from(len).map_err(|_| invalid_data(format!("Too many entries in a frame: {len}")))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    Ok(read_array::<1>(r)?[0])
+}
+
+fn same_state(a: &Option<XINPUT_STATE>, b: &Option<XINPUT_STATE>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.dwPacketNumber == b.dwPacketNumber && a.Gamepad == b.Gamepad,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn write_state(w: &mut impl Write, state: &XINPUT_STATE) -> io::Result<()> {
+    let g = &state.Gamepad;
+    w.write_all(&state.dwPacketNumber.to_le_bytes())?;
+    w.write_all(&g.wButtons.0.to_le_bytes())?;
+    w.write_all(&[g.bLeftTrigger, g.bRightTrigger])?;
+    for axis in [g.sThumbLX, g.sThumbLY, g.sThumbRX, g.sThumbRY] {
+        w.write_all(&axis.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_state(r: &mut impl Read) -> io::Result<XINPUT_STATE> {
+    let dw_packet_number = u32::from_le_bytes(read_array(r)?);
+    let buttons = u16::from_le_bytes(read_array(r)?);
+    let [left_trigger, right_trigger] = read_array(r)?;
+    let mut axis = || read_array(r).map(i16::from_le_bytes);
+
+    Ok(XINPUT_STATE {
+        dwPacketNumber: dw_packet_number,
+        Gamepad: XINPUT_GAMEPAD {
+            wButtons: XINPUT_GAMEPAD_BUTTON_FLAGS(buttons),
+            bLeftTrigger: left_trigger,
+            bRightTrigger: right_trigger,
+            sThumbLX: axis()?,
+            sThumbLY: axis()?,
+            sThumbRX: axis()?,
+            sThumbRY: axis()?,
+        },
+    })
+}
+
+/// Captures the input of every frame it is called on.
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    recording: Recording,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the current frame. Must be called once per frame, e.g. with
+    /// the `Ui` before the widgets interact.
+    pub fn record(&mut self, input: &dyn InputSource) {
+        self.recording.frames.push(RecordedFrame::capture(input));
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Plays a recording back frame by frame.
+///
+/// Each frame is an [`InputSource`] for the widgets' [`Widget::interact`],
+/// and can be fed to an imgui context so that the bindings read from the `Ui`
+/// in [`Widget::render`] see the same input.
+///
+/// [`Widget::interact`]: crate::widgets::Widget::interact
+/// [`Widget::render`]: crate::widgets::Widget::render
+#[derive(Debug)]
+pub struct InputReplayer {
+    recording: Recording,
+    next: usize,
+}
+
+impl InputReplayer {
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, next: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.frames.len()
+    }
+
+    /// Advances to the next frame, if there is one left.
+    pub fn next_frame(&mut self) -> Option<&RecordedFrame> {
+        let frame = self.recording.frames.get(self.next)?;
+        self.next += 1;
+        Some(frame)
+    }
+
+    /// Advances to the next frame and queues its input as imgui events, to be
+    /// called before starting the imgui frame.
+    ///
+    /// The gamepad keys are fed from the primary controller, like a backend
+    /// would, so that `Ui` reports the same controller state. Without one,
+    /// they are fed as recorded, e.g. from a backend reporting the gamepad
+    /// through imgui only.
+    pub fn feed(&mut self, io: &mut imgui::Io) -> Option<&RecordedFrame> {
+        let previous = self.next.checked_sub(1).and_then(|i| self.recording.frames.get(i));
+        let frame = self.recording.frames.get(self.next)?;
+        self.next += 1;
+
+        feed_frame(io, previous, frame);
+        Some(frame)
+    }
+}
+
+/// Queues the input of `frame` as imgui events, releasing the keys that were
+/// down on the `previous` frame.
+pub(crate) fn feed_frame(
+    io: &mut imgui::Io,
+    previous: Option<&RecordedFrame>,
+    frame: &RecordedFrame,
+) {
+    let is_gamepad_key = |key: &imgui::Key| controller::is_gamepad_key(*key);
+
+    if let Some(previous) = previous {
+        io.delta_time = ((frame.time - previous.time) as f32).max(f32::EPSILON);
+        for key in previous.keys_down().filter(|k| !is_gamepad_key(k)) {
+            if !frame.is_key_down(key) {
+                io.add_key_event(key, false);
+            }
+        }
+    }
+
+    for key in MODIFIERS {
+        io.add_key_event(key, frame.is_key_down(key));
+    }
+    for key in frame.keys_down().filter(|k| !is_gamepad_key(k)) {
+        io.add_key_event(key, true);
+    }
+
+    match frame.controllers[0] {
+        Some(state) => {
+            io.backend_flags.insert(imgui::BackendFlags::HAS_GAMEPAD);
+            controller::feed_gamepad_keys(io, &state.Gamepad);
+        },
+        None => {
+            // imgui clears the gamepad keys unless the backend has a gamepad.
+            let keys = frame.keys.iter().map(|&(key, _)| key);
+            let has_gamepad = keys
+                .chain(frame.analog.iter().map(|&(key, _)| key))
+                .any(controller::is_gamepad_key);
+            io.backend_flags.set(imgui::BackendFlags::HAS_GAMEPAD, has_gamepad);
+            for key in recorded_keys().filter(is_gamepad_key) {
+                io.add_key_analog_event(key, frame.is_key_down(key), frame.key_analog_value(key));
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use windows::Win32::UI::Input::XboxController::*;
+
+    use super::*;
+    use crate::binding::Binding;
+    use crate::headless::Harness;
+    use crate::key::ScriptedInput;
+
+    fn controller_state(buttons: XINPUT_GAMEPAD_BUTTON_FLAGS, rx: i16) -> Option<XINPUT_STATE> {
+        Some(XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD { wButtons: buttons, sThumbRX: rx, ..Default::default() },
+        })
+    }
+
+    /// Ctrl+O, then L1+A on the primary controller with the right stick
+    /// pushed, then Escape, with idle frames in between.
+    fn record() -> Recording {
+        let mut input = ScriptedInput::new();
+        let mut recorder = InputRecorder::new();
+        let mut frame = |input: &mut ScriptedInput| {
+            recorder.record(input);
+            input.next_frame(Duration::from_millis(16));
+        };
+
+        frame(&mut input);
+        input.press_key(&"ctrl+o".parse().unwrap());
+        frame(&mut input);
+        frame(&mut input);
+        input.release_all();
+        frame(&mut input);
+        input.set_controller_state(controller_state(XINPUT_GAMEPAD_LEFT_SHOULDER, 0));
+        frame(&mut input);
+        let buttons = XINPUT_GAMEPAD_LEFT_SHOULDER | XINPUT_GAMEPAD_A;
+        input.set_controller_state(controller_state(buttons, -20000));
+        frame(&mut input);
+        frame(&mut input);
+        input.set_controller_state_at(2, controller_state(XINPUT_GAMEPAD_B, 0));
+        input.set_analog(imgui::Key::GamepadL2, 0.5);
+        frame(&mut input);
+        input.set_controller_state(None);
+        input.release_all();
+        input.press(imgui::Key::Escape);
+        frame(&mut input);
+        input.release_all();
+        frame(&mut input);
+
+        recorder.finish()
+    }
+
+    #[test]
+    fn test_keys() {
+        assert!(recorded_keys().count() <= 256);
+        assert_eq!(recorded_keys().filter(|k| MODIFIERS.contains(k)).count(), 4);
+        for (index, key) in recorded_keys().enumerate() {
+            assert_eq!(key_at(index as u8), Some(key));
+            assert_eq!(key_index(key), index as u8);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let recording = record();
+        assert_eq!(recording.frames().len(), 10);
+
+        let mut bytes = Vec::new();
+        recording.write_to(&mut bytes).unwrap();
+        assert_eq!(Recording::read_from(&bytes[..]).unwrap(), recording);
+
+        // Idle frames take 12 bytes, and so do unchanged controllers.
+        let mut idle = Vec::new();
+        Recording { frames: vec![RecordedFrame::default(); 3] }.write_to(&mut idle).unwrap();
+        assert_eq!(idle.len(), 5 + 3 * 12);
+
+        let frame = &recording.frames()[1];
+        assert!(frame.is_key_down(imgui::Key::ModCtrl));
+        assert!(frame.is_key_down(imgui::Key::LeftCtrl));
+        assert!(frame.is_key_pressed_no_repeat(imgui::Key::O));
+        assert!(!recording.frames()[2].is_key_pressed_no_repeat(imgui::Key::O));
+        assert_eq!(recording.frames()[7].key_analog_value(imgui::Key::GamepadL2), 0.5);
+        assert!(recording.frames()[7].controller_state_at(2).is_some());
+        assert!(recording.frames()[8].controller_state_at(0).is_none());
+
+        assert!(Recording::read_from(&b"PTIX\x01"[..]).is_err());
+        assert!(Recording::read_from(&b"PTIN\x02"[..]).is_err());
+        assert!(Recording::read_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut bindings: Vec<Binding> = ["ctrl+o", "l1+a", "rstick_left", "escape", "pad2:b"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let mut replayer = InputReplayer::new(record());
+        let mut fired = Vec::new();
+        while let Some(frame) = replayer.next_frame() {
+            let pressed: Vec<_> = bindings.iter_mut().map(|b| b.is_pressed(frame)).collect();
+            fired.push(pressed.iter().map(|&p| u8::from(p)).collect::<Vec<_>>());
+        }
+        assert!(replayer.is_finished());
+
+        assert_eq!(fired, [
+            [0, 0, 0, 0, 0],
+            [1, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 1, 1, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 1],
+            [0, 0, 0, 1, 0],
+            [0, 0, 0, 0, 0],
+        ]);
+    }
+
+    #[test]
+    fn test_feed_gamepad_keys() {
+        // A gamepad reported through imgui's keys only.
+        let mut input = ScriptedInput::new();
+        input.press(imgui::Key::GamepadFaceDown);
+        input.set_analog(imgui::Key::GamepadL2, 0.5);
+        input.set_analog(imgui::Key::GamepadRStickLeft, 0.75);
+        let mut recorder = InputRecorder::new();
+        recorder.record(&input);
+
+        let mut harness = Harness::new();
+        let ctx = harness.context();
+        let mut replayer = InputReplayer::new(recorder.finish());
+        replayer.feed(ctx.io_mut()).unwrap();
+
+        let ui = ctx.new_frame();
+        assert!(InputSource::is_key_down(ui, imgui::Key::GamepadFaceDown));
+        assert!(!InputSource::is_key_down(ui, imgui::Key::GamepadFaceRight));
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::GamepadL2), 0.5);
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::GamepadRStickLeft), 0.75);
+        assert_eq!(ui.controller_state().map(|state| state.Gamepad.bLeftTrigger), Some(128));
+        ctx.render();
+    }
+}
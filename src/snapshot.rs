@@ -0,0 +1,548 @@
+//! Software rendering of imgui draw data, and comparison of the result with
+//! golden PNG images, so that layout regressions show up in `cargo test`.
+//!
+//! The rasterizer draws the triangles the way imgui's GPU backends do: nearest
+//! texture sampling, interpolated vertex colors and alpha blending, within the
+//! clip rectangles. Anti-aliasing comes from imgui's own fringe vertices.
+//!
+//! Run the tests with `UPDATE_GOLDENS=1` to write the golden images instead of
+//! comparing against them.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use imgui::{DrawCmd, DrawCmdParams, DrawData, DrawVert, TextureId};
+
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// An 8-bit RGBA image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self { width, height, pixels: vec![color; (width * height) as usize] }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read_png(io::BufReader::new(fs::File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut w = io::BufWriter::new(fs::File::create(path)?);
+        self.write_png(&mut w)?;
+        w.flush()
+    }
+
+    /// Encodes the image as a non-interlaced 8-bit RGBA PNG.
+    pub fn write_png(&self, mut w: impl Write) -> io::Result<()> {
+        let mut header = Vec::with_capacity(13);
+        header.extend(self.width.to_be_bytes());
+        header.extend(self.height.to_be_bytes());
+        header.extend([8, 6, 0, 0, 0]);
+
+        // Each row is filtered with the difference to the pixel on its left,
+        // which compresses flat UI well.
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        let mut row = Vec::with_capacity(self.width as usize * 4 + 1);
+        for y in 0..self.height {
+            row.clear();
+            row.push(1);
+            let mut left = [0u8; 4];
+            for x in 0..self.width {
+                let pixel = self.pixel(x, y);
+                row.extend((0..4).map(|c| pixel[c].wrapping_sub(left[c])));
+                left = pixel;
+            }
+            encoder.write_all(&row)?;
+        }
+
+        w.write_all(PNG_SIGNATURE)?;
+        write_chunk(&mut w, b"IHDR", &header)?;
+        write_chunk(&mut w, b"IDAT", &encoder.finish()?)?;
+        write_chunk(&mut w, b"IEND", &[])
+    }
+
+    /// Decodes a non-interlaced 8-bit RGBA PNG.
+    pub fn read_png(mut r: impl Read) -> io::Result<Self> {
+        let mut signature = [0u8; 8];
+        r.read_exact(&mut signature)?;
+        if &signature != PNG_SIGNATURE {
+            return Err(invalid_data("not a PNG file"));
+        }
+
+        let mut size = None;
+        let mut compressed = Vec::new();
+        loop {
+            let (kind, data) = read_chunk(&mut r)?;
+            match &kind {
+                b"IHDR" => {
+                    if data.len() != 13 || data[8..] != [8, 6, 0, 0, 0] {
+                        return Err(invalid_data("only 8-bit RGBA PNGs are supported"));
+                    }
+                    let width = u32::from_be_bytes(data[0..4].try_into().unwrap());
+                    let height = u32::from_be_bytes(data[4..8].try_into().unwrap());
+                    size = Some((width, height));
+                },
+                b"IDAT" => compressed.extend(data),
+                b"IEND" => break,
+                _ => {},
+            }
+        }
+
+        let (width, height) = size.ok_or_else(|| invalid_data("missing PNG header"))?;
+        let stride = width as usize * 4;
+        let mut data = Vec::new();
+        ZlibDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+        if data.len() != (stride + 1) * height as usize {
+            return Err(invalid_data("wrong PNG data size"));
+        }
+
+        let mut rows: Vec<u8> = Vec::with_capacity(stride * height as usize);
+        for (y, line) in data.chunks_exact(stride + 1).enumerate() {
+            let (filter, line) = (line[0], &line[1..]);
+            let start = y * stride;
+            for (i, &byte) in line.iter().enumerate() {
+                let left = if i >= 4 { rows[start + i - 4] } else { 0 };
+                let up = if y > 0 { rows[start + i - stride] } else { 0 };
+                let up_left = if y > 0 && i >= 4 { rows[start + i - stride - 4] } else { 0 };
+                let prediction = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                    4 => paeth(left, up, up_left),
+                    _ => return Err(invalid_data("unknown PNG filter")),
+                };
+                rows.push(byte.wrapping_add(prediction));
+            }
+        }
+
+        let pixels = rows.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect();
+        Ok(Self { width, height, pixels })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) =
+        ((p - i16::from(a)).abs(), (p - i16::from(b)).abs(), (p - i16::from(c)).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc.finalize().to_be_bytes())
+}
+
+fn read_chunk(r: &mut impl Read) -> io::Result<([u8; 4], Vec<u8>)> {
+    let mut header = [0u8; 8];
+    r.read_exact(&mut header)?;
+    let len = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let kind: [u8; 4] = header[4..8].try_into().unwrap();
+
+    let mut data = vec![0u8; len];
+    r.read_exact(&mut data)?;
+    let mut crc = [0u8; 4];
+    r.read_exact(&mut crc)?;
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&kind);
+    hasher.update(&data);
+    if hasher.finalize() != u32::from_be_bytes(crc) {
+        return Err(invalid_data("PNG chunk checksum mismatch"));
+    }
+
+    Ok((kind, data))
+}
+
+/// An RGBA texture the draw data can refer to, e.g. the font atlas.
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Texture {
+    fn sample(&self, [u, v]: [f32; 2]) -> [f32; 4] {
+        let x = ((u * self.width as f32).floor() as i64).clamp(0, self.width as i64 - 1);
+        let y = ((v * self.height as f32).floor() as i64).clamp(0, self.height as i64 - 1);
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let texel = &self.data[i..i + 4];
+        [0, 1, 2, 3].map(|c| f32::from(texel[c]) / 255.)
+    }
+}
+
+/// Renders imgui draw data on the CPU.
+pub struct Rasterizer {
+    textures: Vec<(TextureId, Texture)>,
+    clear_color: [u8; 4],
+}
+
+impl Rasterizer {
+    pub fn new() -> Self {
+        Self { textures: Vec::new(), clear_color: [0x42, 0x59, 0x61, 0xff] }
+    }
+
+    /// Registers a texture. Draw commands using an unknown texture sample
+    /// plain white.
+    pub fn with_texture(mut self, id: TextureId, texture: Texture) -> Self {
+        self.textures.push((id, texture));
+        self
+    }
+
+    pub fn with_clear_color(mut self, color: [u8; 4]) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    pub fn clear_color(&self) -> [u8; 4] {
+        self.clear_color
+    }
+
+    pub fn render(&self, draw_data: &DrawData) -> Image {
+        let [width, height] = draw_data.display_size;
+        let mut image = Image::new(width as u32, height as u32, self.clear_color);
+
+        for draw_list in draw_data.draw_lists() {
+            let vtx = draw_list.vtx_buffer();
+            let idx = draw_list.idx_buffer();
+
+            for command in draw_list.commands() {
+                let DrawCmd::Elements { count, cmd_params } = command else {
+                    continue;
+                };
+                let DrawCmdParams { clip_rect, texture_id, vtx_offset, idx_offset } = cmd_params;
+
+                let [x, y] = draw_data.display_pos;
+                let clip = [clip_rect[0] - x, clip_rect[1] - y, clip_rect[2] - x, clip_rect[3] - y];
+                let texture =
+                    self.textures.iter().find(|(id, _)| *id == texture_id).map(|(_, t)| t);
+
+                for triangle in idx[idx_offset..idx_offset + count].chunks_exact(3) {
+                    let [a, b, c] = [0, 1, 2].map(|i| &vtx[vtx_offset + triangle[i] as usize]);
+                    draw_triangle(&mut image, [a, b, c], clip, texture);
+                }
+            }
+        }
+
+        image
+    }
+}
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Whether pixel centers exactly on the edge from `a` to `b` belong to the
+/// triangle, so that the pixels on an edge shared by two triangles are only
+/// blended once.
+fn owns_edge(a: [f32; 2], b: [f32; 2]) -> bool {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    dy > 0. || (dy == 0. && dx < 0.)
+}
+
+fn draw_triangle(image: &mut Image, v: [&DrawVert; 3], clip: [f32; 4], texture: Option<&Texture>) {
+    let [mut a, mut b, c] = v;
+    let mut area = edge(a.pos, b.pos, c.pos);
+    if area == 0. {
+        return;
+    }
+    if area < 0. {
+        std::mem::swap(&mut a, &mut b);
+        area = -area;
+    }
+
+    // Pixels whose centers may be covered, within the clip rect and image.
+    let xs = [a.pos[0], b.pos[0], c.pos[0]];
+    let ys = [a.pos[1], b.pos[1], c.pos[1]];
+    let bounds = |vs: [f32; 3], lo: f32, hi: f32, size: u32| {
+        let min = vs.into_iter().fold(f32::MAX, f32::min).max(lo).max(0.);
+        let max = vs.into_iter().fold(f32::MIN, f32::max).min(hi);
+        (min.floor() as u32, (max.ceil().max(0.) as u32).min(size))
+    };
+    let (min_x, max_x) = bounds(xs, clip[0], clip[2], image.width);
+    let (min_y, max_y) = bounds(ys, clip[1], clip[3], image.height);
+
+    let owns = [owns_edge(b.pos, c.pos), owns_edge(c.pos, a.pos), owns_edge(a.pos, b.pos)];
+    let colors = [a.col, b.col, c.col].map(|col| col.map(|c| f32::from(c) / 255.));
+    let uvs = [a.uv, b.uv, c.uv];
+    let shade = |l: [f32; 3]| {
+        let mix = |v: [f32; 3]| l[0] * v[0] + l[1] * v[1] + l[2] * v[2];
+        let color = [0, 1, 2, 3].map(|ch| mix([colors[0][ch], colors[1][ch], colors[2][ch]]));
+        match texture {
+            Some(texture) => {
+                let texel = texture.sample([
+                    mix([uvs[0][0], uvs[1][0], uvs[2][0]]),
+                    mix([uvs[0][1], uvs[1][1], uvs[2][1]]),
+                ]);
+                [0, 1, 2, 3].map(|ch| color[ch] * texel[ch])
+            },
+            None => color,
+        }
+    };
+
+    // Shapes like rects have a single color and sample a single texel, so
+    // there is nothing to interpolate.
+    let flat = (a.col == b.col && b.col == c.col && a.uv == b.uv && b.uv == c.uv)
+        .then(|| shade([1., 0., 0.]));
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = [x as f32 + 0.5, y as f32 + 0.5];
+            let w = [edge(b.pos, c.pos, p), edge(c.pos, a.pos, p), edge(a.pos, b.pos, p)];
+            if (0..3).any(|i| w[i] < 0. || (w[i] == 0. && !owns[i])) {
+                continue;
+            }
+
+            let color = flat.unwrap_or_else(|| shade(w.map(|w| w / area)));
+            blend(&mut image.pixels[(y * image.width + x) as usize], color);
+        }
+    }
+}
+
+fn blend(dst: &mut [u8; 4], src: [f32; 4]) {
+    let alpha = src[3].clamp(0., 1.);
+    let to_u8 = |v: f32| (v.clamp(0., 1.) * 255. + 0.5) as u8;
+    if alpha == 0. {
+        return;
+    } else if alpha == 1. {
+        *dst = src.map(to_u8);
+        return;
+    }
+
+    let d = dst.map(|c| f32::from(c) / 255.);
+
+    *dst = [
+        to_u8(src[0] * alpha + d[0] * (1. - alpha)),
+        to_u8(src[1] * alpha + d[1] * (1. - alpha)),
+        to_u8(src[2] * alpha + d[2] * (1. - alpha)),
+        to_u8(alpha + d[3] * (1. - alpha)),
+    ];
+}
+
+/// How much an image may differ from its golden image.
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    /// Largest difference of a channel for pixels to count as equal.
+    pub channel: u8,
+    /// Fraction of the pixels that may differ by more.
+    pub pixels: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self { channel: 4, pixels: 0.001 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    Size { expected: (u32, u32), actual: (u32, u32) },
+    Pixels { differing: usize, total: usize },
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Size { expected, actual } => {
+                write!(
+                    f,
+                    "expected a {}x{} image, got {}x{}",
+                    expected.0, expected.1, actual.0, actual.1
+                )
+            },
+            Mismatch::Pixels { differing, total } => {
+                write!(f, "{differing} of {total} pixels differ")
+            },
+        }
+    }
+}
+
+/// Compares an image with the expected one.
+pub fn compare(expected: &Image, actual: &Image, tolerance: Tolerance) -> Result<(), Mismatch> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(Mismatch::Size {
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+
+    let total = expected.pixels.len();
+    let differing = diff_mask(expected, actual, tolerance.channel).filter(|&d| d).count();
+    if differing as f64 > tolerance.pixels * total as f64 {
+        Err(Mismatch::Pixels { differing, total })
+    } else {
+        Ok(())
+    }
+}
+
+fn diff_mask<'a>(
+    expected: &'a Image,
+    actual: &'a Image,
+    channel: u8,
+) -> impl Iterator<Item = bool> + 'a {
+    expected
+        .pixels
+        .iter()
+        .zip(&actual.pixels)
+        .map(move |(e, a)| (0..4).any(|c| e[c].abs_diff(a[c]) > channel))
+}
+
+/// Highlights the differing pixels in red over a faded copy of the expected
+/// image. Both images must have the same size.
+pub fn diff_image(expected: &Image, actual: &Image, channel: u8) -> Image {
+    let pixels = diff_mask(expected, actual, channel)
+        .zip(&expected.pixels)
+        .map(|(differs, &[r, g, b, _])| {
+            if differs {
+                [0xff, 0, 0, 0xff]
+            } else {
+                let gray = ((u16::from(r) + u16::from(g) + u16::from(b)) / 6) as u8;
+                [gray, gray, gray, 0xff]
+            }
+        })
+        .collect();
+
+    Image { width: expected.width, height: expected.height, pixels }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+/// Compares the image with the golden image at `path`.
+///
+/// On a mismatch, the image and a diff are written next to the golden image,
+/// as `<name>.actual.png` and `<name>.diff.png`, before panicking. A missing
+/// golden image is written, and fails the test so that it gets reviewed.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &Image, tolerance: Tolerance) {
+    let path = path.as_ref();
+
+    let update = std::env::var_os("UPDATE_GOLDENS").is_some();
+    if update || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        actual.save(path).unwrap();
+        assert!(update, "Golden image {} was missing and has been written", path.display());
+        return;
+    }
+
+    let expected = Image::load(path)
+        .unwrap_or_else(|e| panic!("Couldn't load golden image {}: {e}", path.display()));
+
+    if let Err(mismatch) = compare(&expected, actual, tolerance) {
+        let actual_path = sibling(path, "actual");
+        actual.save(&actual_path).unwrap();
+        if let Mismatch::Pixels { .. } = mismatch {
+            diff_image(&expected, actual, tolerance.channel).save(&sibling(path, "diff")).unwrap();
+        }
+        panic!("{} doesn't match: {mismatch}, see {}", path.display(), actual_path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vert(pos: [f32; 2], col: [u8; 4]) -> DrawVert {
+        DrawVert { pos, uv: [0., 0.], col }
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut image = Image::new(5, 3, [1, 2, 3, 255]);
+        image.pixels[7] = [255, 0, 128, 64];
+        image.pixels[14] = [0, 255, 0, 0];
+
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], PNG_SIGNATURE);
+        assert_eq!(Image::read_png(&png[..]).unwrap(), image);
+
+        png[20] ^= 1;
+        assert!(Image::read_png(&png[..]).is_err());
+    }
+
+    #[test]
+    fn test_triangles() {
+        let mut image = Image::new(4, 4, [0, 0, 0, 255]);
+        let white = [255, 255, 255, 255];
+        let clip = [0., 0., 4., 4.];
+
+        // Two triangles of a quad share the diagonal: no pixel is drawn twice,
+        // which would show with a translucent color.
+        let half = [255, 255, 255, 128];
+        let [a, b, c, d] = [[0., 0.], [4., 0.], [4., 4.], [0., 4.]].map(|p| vert(p, half));
+        draw_triangle(&mut image, [&a, &b, &c], clip, None);
+        draw_triangle(&mut image, [&a, &c, &d], clip, None);
+        assert!(image.pixels.iter().all(|&p| p == [128, 128, 128, 255]));
+
+        // Clipped and with either winding.
+        let mut image = Image::new(4, 4, [0, 0, 0, 255]);
+        let [a, b, c] = [[0., 0.], [0., 4.], [4., 4.]].map(|p| vert(p, white));
+        draw_triangle(&mut image, [&a, &b, &c], [0., 2., 4., 4.], None);
+        assert_eq!(image.pixel(0, 1), [0, 0, 0, 255]);
+        assert_eq!(image.pixel(0, 3), white);
+        assert_eq!(image.pixel(1, 3), white);
+        assert_eq!(image.pixel(3, 2), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_compare() {
+        let expected = Image::new(10, 10, [0, 0, 0, 255]);
+        let mut actual = expected.clone();
+        actual.pixels[0] = [3, 0, 0, 255];
+        assert_eq!(compare(&expected, &actual, Tolerance { channel: 4, pixels: 0. }), Ok(()));
+
+        actual.pixels[1] = [30, 0, 0, 255];
+        assert_eq!(
+            compare(&expected, &actual, Tolerance { channel: 4, pixels: 0. }),
+            Err(Mismatch::Pixels { differing: 1, total: 100 })
+        );
+        assert_eq!(compare(&expected, &actual, Tolerance { channel: 4, pixels: 0.01 }), Ok(()));
+        assert_eq!(diff_image(&expected, &actual, 4).pixel(1, 0), [255, 0, 0, 255]);
+
+        assert!(matches!(
+            compare(&expected, &Image::new(10, 5, [0; 4]), Tolerance::default()),
+            Err(Mismatch::Size { .. })
+        ));
+    }
+}
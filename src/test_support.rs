@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::widgets::flag::Flag;
+
+/// A flag whose clones share the same state, so that a test can keep one to
+/// check after handing the other to a widget.
+#[derive(Clone, Default)]
+pub(crate) struct TestFlag(Arc<AtomicBool>);
+
+impl TestFlag {
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Flag for TestFlag {
+    fn set(&mut self, value: bool) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<bool> {
+        Some(self.is_set())
+    }
+}
@@ -0,0 +1,387 @@
+//! Widget trees built from a config file instead of by hand.
+//!
+//! The game registers its [`Flag`], [`ReadWrite`], [`PositionStorage`] and
+//! [`Stats`] providers by name in a [`WidgetFactory`], and the menu layout is
+//! read as a list of [`WidgetConfig`] entries with any serde format, e.g.:
+//!
+//! ```toml
+//! [[widgets]]
+//! widget = "flag"
+//! label = "No death"
+//! flag = "no_death"
+//! hotkey = "f1"
+//!
+//! [[widgets]]
+//! widget = "group"
+//! label = "Cheats"
+//! children = [
+//!   { widget = "store_value", value = "quitout", hotkey = "ctrl+q" },
+//! ]
+//! ```
+//!
+//! Unknown widget kinds and fields are rejected by serde, with the location
+//! the format reports. Provider names and bindings are validated by
+//! [`WidgetFactory::build`], which reports every invalid entry by its path.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::binding::Binding;
+use crate::error::{suggest, ConfigError, ConfigErrorKind};
+use crate::widgets::flag::{Flag, FlagWidget};
+use crate::widgets::group::Group;
+use crate::widgets::label::LabelWidget;
+use crate::widgets::position::{Position, PositionStorage};
+use crate::widgets::stats_editor::{Stats, StatsEditor};
+use crate::widgets::store_value::{ReadWrite, StoreValue};
+use crate::widgets::Widget;
+
+/// An entry of a widget config. Bindings are given in the same syntax as
+/// [`Binding`]'s.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "widget", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WidgetConfig {
+    Flag {
+        label: String,
+        flag: String,
+        hotkey: Option<String>,
+    },
+    StoreValue {
+        value: String,
+        hotkey: Option<String>,
+    },
+    Position {
+        position: String,
+        load: Option<String>,
+        save: Option<String>,
+    },
+    Stats {
+        stats: String,
+        open: Option<String>,
+        close: Option<String>,
+    },
+    Label {
+        label: String,
+    },
+    /// A button opening a popup with the children. The close binding defaults
+    /// to escape.
+    Group {
+        label: String,
+        close: Option<String>,
+        children: Vec<WidgetConfig>,
+    },
+}
+
+impl WidgetConfig {
+    fn label(&self) -> Option<&str> {
+        match self {
+            WidgetConfig::Flag { label, .. }
+            | WidgetConfig::Label { label }
+            | WidgetConfig::Group { label, .. } => Some(label),
+            _ => None,
+        }
+    }
+}
+
+type Constructor<A> = Box<dyn Fn(A) -> Box<dyn Widget> + Send + Sync>;
+type Bindings = (Option<Binding>, Option<Binding>);
+
+/// Named providers of the game's state, which config entries refer to.
+///
+/// Providers are registered as constructors, so that an entry can be used
+/// several times in a config.
+#[derive(Default)]
+pub struct WidgetFactory {
+    flags: HashMap<String, Constructor<(String, Option<Binding>)>>,
+    values: HashMap<String, Constructor<Option<Binding>>>,
+    positions: HashMap<String, Constructor<Bindings>>,
+    stats: HashMap<String, Constructor<Bindings>>,
+}
+
+impl WidgetFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_flag<F: Flag + 'static>(
+        &mut self,
+        name: &str,
+        flag: impl Fn() -> F + Send + Sync + 'static,
+    ) {
+        self.flags.insert(
+            name.to_string(),
+            Box::new(move |(label, hotkey)| Box::new(FlagWidget::new(&label, flag(), hotkey))),
+        );
+    }
+
+    pub fn register_read_write<W: ReadWrite>(
+        &mut self,
+        name: &str,
+        value: impl Fn() -> W + Send + Sync + 'static,
+    ) {
+        self.values.insert(
+            name.to_string(),
+            Box::new(move |hotkey| Box::new(StoreValue::new(value(), hotkey))),
+        );
+    }
+
+    pub fn register_position<P: PositionStorage>(
+        &mut self,
+        name: &str,
+        storage: impl Fn() -> P + Send + Sync + 'static,
+    ) {
+        // Named after the provider, as the config doesn't label positions.
+        let provider = name.to_string();
+        self.positions.insert(
+            name.to_string(),
+            Box::new(move |(load, save)| {
+                Box::new(Position::new(storage(), load, save).with_name(&provider))
+            }),
+        );
+    }
+
+    pub fn register_stats<S: Stats>(
+        &mut self,
+        name: &str,
+        stats: impl Fn() -> S + Send + Sync + 'static,
+    ) {
+        self.stats.insert(
+            name.to_string(),
+            Box::new(move |(open, close)| Box::new(StatsEditor::new(stats(), open, close))),
+        );
+    }
+
+    /// Builds the widgets of all the entries, or returns an error for each
+    /// invalid entry.
+    pub fn build(&self, config: &[WidgetConfig]) -> Result<Vec<Box<dyn Widget>>, Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let widgets = self.build_all(config, "", &mut errors);
+
+        if errors.is_empty() {
+            Ok(widgets)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn build_all(
+        &self,
+        config: &[WidgetConfig],
+        parent: &str,
+        errors: &mut Vec<ConfigError>,
+    ) -> Vec<Box<dyn Widget>> {
+        config
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let path = format!("{parent}[{index}]");
+                let mut ctx = Entry { path, entry, errors };
+                self.build_entry(&mut ctx)
+            })
+            .collect()
+    }
+
+    fn build_entry(&self, ctx: &mut Entry) -> Option<Box<dyn Widget>> {
+        match ctx.entry {
+            WidgetConfig::Flag { label, flag, hotkey } => {
+                let hotkey = ctx.binding("hotkey", hotkey);
+                let constructor = ctx.provider("flag", &self.flags, flag);
+                Some(constructor?((label.clone(), hotkey?)))
+            },
+            WidgetConfig::StoreValue { value, hotkey } => {
+                let hotkey = ctx.binding("hotkey", hotkey);
+                let constructor = ctx.provider("value", &self.values, value);
+                Some(constructor?(hotkey?))
+            },
+            WidgetConfig::Position { position, load, save } => {
+                let (load, save) = (ctx.binding("load", load), ctx.binding("save", save));
+                let constructor = ctx.provider("position", &self.positions, position);
+                Some(constructor?((load?, save?)))
+            },
+            WidgetConfig::Stats { stats, open, close } => {
+                let (open, close) = (ctx.binding("open", open), ctx.binding("close", close));
+                let constructor = ctx.provider("stats", &self.stats, stats);
+                Some(constructor?((open?, close?)))
+            },
+            WidgetConfig::Label { label } => Some(Box::new(LabelWidget::new(label))),
+            WidgetConfig::Group { label, close, children } => {
+                let close = ctx.binding("close", close);
+                let path = format!("{}.children", ctx.path);
+                let children = self.build_all(children, &path, ctx.errors);
+                let close = close?.unwrap_or_else(|| "escape".parse().unwrap());
+                Some(Box::new(Group::new(label, close, children)))
+            },
+        }
+    }
+}
+
+/// The entry being built, which errors are reported against.
+struct Entry<'a> {
+    path: String,
+    entry: &'a WidgetConfig,
+    errors: &'a mut Vec<ConfigError>,
+}
+
+impl Entry<'_> {
+    fn error(&mut self, kind: ConfigErrorKind) {
+        self.errors.push(ConfigError {
+            path: self.path.clone(),
+            label: self.entry.label().map(str::to_string),
+            kind,
+        });
+    }
+
+    /// Parses an optional binding field. Returns `None` if it is invalid.
+    fn binding(
+        &mut self,
+        field: &'static str,
+        binding: &Option<String>,
+    ) -> Option<Option<Binding>> {
+        match binding.as_deref().map(str::parse::<Binding>).transpose() {
+            Ok(binding) => Some(binding),
+            Err(error) => {
+                self.error(ConfigErrorKind::InvalidBinding { field, error });
+                None
+            },
+        }
+    }
+
+    fn provider<'m, T>(
+        &mut self,
+        provider: &'static str,
+        map: &'m HashMap<String, T>,
+        name: &str,
+    ) -> Option<&'m T> {
+        let found = map.get(name);
+        if found.is_none() {
+            let mut suggestions = suggest(name, map.keys().map(String::as_str));
+            suggestions.sort_unstable();
+            let suggestions = suggestions.into_iter().map(str::to_string).collect();
+            self.error(ConfigErrorKind::UnknownProvider {
+                provider,
+                name: name.to_string(),
+                suggestions,
+            });
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::registry::hotkey_config;
+    use crate::test_support::TestFlag;
+
+    struct TestValue(&'static str);
+
+    impl ReadWrite for TestValue {
+        fn read(&mut self) -> bool {
+            true
+        }
+
+        fn write(&mut self) {}
+
+        fn label(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn factory() -> WidgetFactory {
+        let mut factory = WidgetFactory::new();
+        factory.register_flag("no_death", TestFlag::default);
+        factory.register_flag("no_damage", TestFlag::default);
+        factory.register_flag("one_shot", TestFlag::default);
+        factory.register_read_write("quitout", || TestValue("Quitout"));
+        factory
+    }
+
+    fn config(json: &str) -> Vec<WidgetConfig> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_build() {
+        let config = config(
+            r#"[
+                { "widget": "flag", "label": "No death", "flag": "no_death", "hotkey": "f1" },
+                { "widget": "label", "label": "Values" },
+                {
+                    "widget": "group",
+                    "label": "Cheats",
+                    "children": [
+                        { "widget": "flag", "label": "One shot", "flag": "one_shot" },
+                        { "widget": "store_value", "value": "quitout", "hotkey": "ctrl+q" }
+                    ]
+                }
+            ]"#,
+        );
+
+        let mut widgets = factory().build(&config).unwrap();
+        assert_eq!(widgets.len(), 3);
+
+        let mut config = crate::registry::HotkeyConfig::new();
+        for widget in &mut widgets {
+            config.append(&mut hotkey_config(widget.as_mut()));
+        }
+        assert_eq!(config["No death"], "f1".parse().ok());
+        assert_eq!(config["Cheats/One shot"], None);
+        assert_eq!(config["Cheats/Quitout"], "ctrl+q".parse().ok());
+        assert_eq!(config["Cheats/Close"], "escape".parse().ok());
+    }
+
+    #[test]
+    fn test_errors() {
+        let config = config(
+            r#"[
+                { "widget": "flag", "label": "No death", "flag": "no_deth", "hotkey": "f1" },
+                {
+                    "widget": "group",
+                    "label": "Cheats",
+                    "close": "esc",
+                    "children": [
+                        { "widget": "label", "label": "ok" },
+                        { "widget": "store_value", "value": "quitout", "hotkey": "ctlr+q" },
+                        { "widget": "stats", "stats": "character" }
+                    ]
+                }
+            ]"#,
+        );
+
+        let errors = factory().build(&config).err().unwrap();
+        let paths: Vec<_> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["[0]", "[1].children[1]", "[1].children[2]"]);
+
+        assert_eq!(errors[0].label.as_deref(), Some("No death"));
+        assert_eq!(errors[0].kind, ConfigErrorKind::UnknownProvider {
+            provider: "flag",
+            name: "no_deth".to_string(),
+            suggestions: vec!["no_death".to_string()],
+        });
+        assert_eq!(
+            errors[0].to_string(),
+            "Widget [0] (\"No death\"): could not find flag \"no_deth\", did you mean `no_death`?"
+        );
+
+        let ConfigErrorKind::InvalidBinding { field, error } = &errors[1].kind else {
+            panic!("{:?}", errors[1]);
+        };
+        assert_eq!(*field, "hotkey");
+        assert!(matches!(error, ParseError::UnknownModifier { .. }));
+
+        assert_eq!(
+            errors[2].to_string(),
+            "Widget [1].children[2]: could not find stats \"character\""
+        );
+    }
+
+    #[test]
+    fn test_serde_errors() {
+        let parse = |json| serde_json::from_str::<Vec<WidgetConfig>>(json);
+        assert!(parse(r#"[{ "widget": "flagg", "label": "a", "flag": "b" }]"#).is_err());
+        assert!(parse(r#"[{ "widget": "flag", "label": "a" }]"#).is_err());
+        assert!(parse(r#"[{ "widget": "label", "label": "a", "hotkey": "f1" }]"#).is_err());
+    }
+}
@@ -1,5 +1,8 @@
-use crate::key::Key;
-use crate::widgets::Widget;
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{label_with_hotkey, Widget};
 
 pub trait Flag: Send + Sync {
     fn set(&mut self, value: bool);
@@ -14,21 +17,20 @@ pub trait Flag: Send + Sync {
 }
 
 pub struct FlagWidget<F: Flag> {
+    name: String,
     label: String,
     label_true: String,
     label_false: String,
     flag: F,
-    hotkey: Option<Key>,
-    logs: Vec<String>,
+    hotkey: Option<Binding>,
+    logs: Vec<LogEvent>,
 }
 
 impl<F: Flag> FlagWidget<F> {
-    pub fn new(label: &str, flag: F, hotkey: Option<Key>) -> Self {
+    pub fn new(label: &str, flag: F, hotkey: Option<Binding>) -> Self {
         Self {
-            label: hotkey
-                .as_ref()
-                .map(|hotkey| format!("{label} ({hotkey})"))
-                .unwrap_or_else(|| label.to_string()),
+            name: label.to_string(),
+            label: label_with_hotkey(label, hotkey.as_ref()),
             flag,
             hotkey,
             label_true: format!("{label} activated"),
@@ -38,7 +40,8 @@ impl<F: Flag> FlagWidget<F> {
     }
 
     fn log_state(&mut self, state: bool) {
-        self.logs.push(if state { self.label_true.clone() } else { self.label_false.clone() });
+        let message = if state { &self.label_true } else { &self.label_false };
+        self.logs.push(LogEvent::info(&self.name, message).with_payload("state", state));
     }
 }
 
@@ -56,21 +59,77 @@ impl<F: Flag> Widget for FlagWidget<F> {
         }
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        if self.hotkey.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.hotkey.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.action();
         }
     }
 
+    fn is_enabled(&self) -> bool {
+        self.flag.get().is_some()
+    }
+
     fn action(&mut self) {
         if let Some(state) = self.flag.toggle() {
             self.log_state(state);
         }
     }
 
-    fn log(&mut self, tx: crossbeam_channel::Sender<String>) {
+    fn log(&mut self, tx: crossbeam_channel::Sender<LogEvent>) {
         self.logs.drain(..).for_each(|log| {
             tx.send(log).ok();
         });
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot { name: &self.name, scope: Scope::Global, binding: &mut self.hotkey });
+        self.label = label_with_hotkey(&self.name, self.hotkey.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::key::ScriptedInput;
+    use crate::log::{log_messages, Severity};
+    use crate::test_support::TestFlag;
+
+    #[test]
+    fn test_interact() {
+        let flag = TestFlag::default();
+        let mut widget = FlagWidget::new("test", flag.clone(), "ctrl+f".parse().ok());
+        let mut input = ScriptedInput::new();
+
+        input.press(imgui::Key::F);
+        widget.interact(&input);
+        assert!(!flag.is_set());
+
+        input.release_all();
+        input.next_frame(Duration::from_millis(10));
+        input.press_key(&"ctrl+f".parse().unwrap());
+        widget.interact(&input);
+        assert!(flag.is_set());
+
+        // Still held on the next frame: no toggle back.
+        input.next_frame(Duration::from_millis(10));
+        widget.interact(&input);
+        assert!(flag.is_set());
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        widget.log(tx);
+        let events = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].severity, Severity::Info);
+        assert_eq!(events[0].source, "test");
+        assert_eq!(events[0].message, "test activated");
+        assert_eq!(events[0].get("state"), Some("true"));
+
+        // Plain string consumers only get the messages.
+        widget.action();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        log_messages(&mut widget, &tx);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), ["test deactivated"]);
+    }
 }
@@ -2,25 +2,37 @@ use crossbeam_channel::Sender;
 use imgui::sys::{igGetCursorPosX, igGetCursorPosY, igGetWindowPos, igSetNextWindowPos, ImVec2};
 use imgui::Condition;
 
-use super::{Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
-use crate::key::Key;
+use super::navigator::Focus;
+use super::{label_with_hotkey, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
 
 pub struct Group {
     label: String,
     label_close: String,
     tag: String,
-    key_close: Key,
+    key_close: Option<Binding>,
     children: Vec<Box<dyn Widget>>,
+    focus: Focus,
+    entered: bool,
+    request_open: bool,
+    request_close: bool,
 }
 
 impl Group {
-    pub fn new(label: &str, key_close: Key, commands: Vec<Box<dyn Widget>>) -> Self {
+    pub fn new(label: &str, key_close: Binding, commands: Vec<Box<dyn Widget>>) -> Self {
         Self {
             label: label.to_string(),
             tag: format!("##group-{label}"),
-            label_close: format!("Close ({key_close})"),
-            key_close,
+            label_close: label_with_hotkey("Close", Some(&key_close)),
+            key_close: Some(key_close),
             children: commands,
+            focus: Focus::default(),
+            entered: false,
+            request_open: false,
+            request_close: false,
         }
     }
 }
@@ -36,7 +48,9 @@ impl Widget for Group {
             (igGetCursorPosX() + wnd_pos.x, igGetCursorPosY() + wnd_pos.y)
         };
 
-        if ui.button_with_size(&self.label, [button_width, BUTTON_HEIGHT]) {
+        if ui.button_with_size(&self.label, [button_width, BUTTON_HEIGHT])
+            || std::mem::take(&mut self.request_open)
+        {
             ui.open_popup(&self.tag);
         }
 
@@ -56,15 +70,19 @@ impl Widget for Group {
             .scroll_bar(false)
             .begin_popup()
         {
-            for widget in &mut self.children {
-                widget.render(ui);
-            }
+            self.focus.render(ui, &mut self.children);
 
             if ui.button_with_size(&self.label_close, [button_width, BUTTON_HEIGHT])
-                || (self.key_close.is_pressed(ui) && !ui.is_any_item_active())
+                || (self.key_close.as_mut().map(|k| k.is_pressed(ui)).unwrap_or(false)
+                    && !ui.is_any_item_active())
+                || std::mem::take(&mut self.request_close)
             {
                 ui.close_current_popup();
+                self.focus.reset();
+                self.entered = false;
             }
+        } else {
+            self.request_close = false;
         }
     }
 
@@ -74,15 +92,58 @@ impl Widget for Group {
         }
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
+    fn interact(&mut self, input: &dyn InputSource) {
         for widget in &mut self.children {
-            widget.interact(ui);
+            widget.interact(input);
         }
     }
 
-    fn log(&mut self, tx: Sender<String>) {
+    fn action(&mut self) {
+        self.focus.action(&mut self.children);
+    }
+
+    fn cursor_down(&mut self) {
+        self.focus.cursor_down(&mut self.children);
+    }
+
+    fn cursor_up(&mut self) {
+        self.focus.cursor_up(&mut self.children);
+    }
+
+    fn want_enter(&mut self) -> bool {
+        self.request_open = true;
+        self.focus.reset();
+        self.entered = true;
+        true
+    }
+
+    fn want_exit(&mut self) -> bool {
+        let exit = self.focus.want_exit(&mut self.children);
+        self.request_close |= exit;
+        self.entered &= !exit;
+        exit
+    }
+
+    fn is_entered(&mut self) -> bool {
+        self.entered
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
         for widget in &mut self.children {
             widget.log(tx.clone());
         }
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        let name = format!("{}/Close", self.label);
+        visit(HotkeySlot { name: &name, scope: Scope::Popup, binding: &mut self.key_close });
+        self.label_close = label_with_hotkey("Close", self.key_close.as_ref());
+
+        for widget in &mut self.children {
+            widget.hotkeys(&mut |slot| {
+                let name = format!("{}/{}", self.label, slot.name);
+                visit(HotkeySlot { name: &name, ..slot })
+            });
+        }
+    }
 }
@@ -0,0 +1,366 @@
+use crossbeam_channel::Sender;
+use imgui::sys::{igGetCursorPosX, igGetCursorPosY, igGetWindowPos, igSetNextWindowPos, ImVec2};
+use imgui::Condition;
+
+use crate::binding::Binding;
+use crate::controller::{gamepad_from_keys, stick_deadzone, ControllerCombination};
+use crate::key::{InputSource, Key};
+use crate::log::LogEvent;
+use crate::registry::{visit_unique, HotkeyConfig, HotkeyRegistry, HotkeySlot, Scope, SlotNames};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+
+const HKE_TAG: &str = "##hotkey-editor";
+const SOURCE: &str = "Hotkey editor";
+
+struct Row {
+    name: String,
+    label: String,
+}
+
+enum RowAction {
+    Capture(usize),
+    Clear(usize),
+}
+
+/// Wraps a widget tree and lets the user rebind its hotkeys from a popup.
+///
+/// The children are rendered and interacted with as if they were not wrapped.
+/// Every time a binding changes, the full [`HotkeyConfig`] of the tree is sent
+/// through `tx` so the host can persist it. The conflicts between the initial
+/// bindings are logged as warnings.
+pub struct HotkeyEditor {
+    label_close: String,
+    key_close: Option<Binding>,
+    children: Vec<Box<dyn Widget>>,
+    rows: Vec<Row>,
+    capturing: Option<usize>,
+    /// The controller inputs pressed since the capture started, or `None`
+    /// until the controller has been released, e.g. after the button that
+    /// started the capture.
+    captured_buttons: Option<ControllerCombination>,
+    /// Whether a capture ended on this frame, so that the key which ended it
+    /// doesn't also fire the children.
+    capture_ended: bool,
+    message: Option<String>,
+    tx: Sender<HotkeyConfig>,
+    logs: Vec<LogEvent>,
+}
+
+impl HotkeyEditor {
+    pub fn new(
+        key_close: Option<Binding>,
+        children: Vec<Box<dyn Widget>>,
+        tx: Sender<HotkeyConfig>,
+    ) -> Self {
+        let mut editor = Self {
+            label_close: label_with_hotkey("Close", key_close.as_ref()),
+            key_close,
+            children,
+            rows: Vec::new(),
+            capturing: None,
+            captured_buttons: None,
+            capture_ended: false,
+            message: None,
+            tx,
+            logs: Vec::new(),
+        };
+
+        let mut registry = HotkeyRegistry::default();
+        editor.visit_children(&mut |_, slot| {
+            if let Some(binding) = slot.binding {
+                registry.register(slot.name, slot.scope, binding);
+            }
+        });
+        for conflict in registry.conflicts() {
+            editor.logs.push(LogEvent::warning("Hotkeys", conflict.to_string()));
+        }
+
+        editor
+    }
+
+    fn visit_children(&mut self, visit: &mut dyn FnMut(usize, HotkeySlot<'_>)) {
+        let mut names = SlotNames::default();
+        let mut index = 0;
+        for widget in &mut self.children {
+            visit_unique(widget.as_mut(), &mut names, &mut |slot| {
+                visit(index, slot);
+                index += 1;
+            });
+        }
+    }
+
+    fn refresh_rows(&mut self) {
+        let mut rows = Vec::new();
+        self.visit_children(&mut |_, slot| {
+            let label = match slot.binding {
+                Some(binding) => binding.pretty().to_string(),
+                None => "<unbound>".to_string(),
+            };
+            rows.push(Row { name: slot.name.to_string(), label });
+        });
+        self.rows = rows;
+    }
+
+    /// Binds the slot at `index` to `binding`, unless it conflicts with others.
+    fn rebind(&mut self, index: usize, binding: Option<Binding>) {
+        let mut registry = HotkeyRegistry::default();
+        let mut scope = Scope::Global;
+        self.visit_children(&mut |i, slot| match slot.binding {
+            _ if i == index => scope = slot.scope,
+            Some(binding) => registry.register(slot.name, slot.scope, binding),
+            None => {},
+        });
+
+        if let Some(binding) = &binding {
+            let conflicts = registry.conflicts_with(scope, binding);
+            if let Some((_, entry)) = conflicts.first() {
+                self.message = Some(format!(
+                    "{} is already used by \"{}\" ({})",
+                    binding.pretty(),
+                    entry.name,
+                    entry.binding.pretty()
+                ));
+                return;
+            }
+        }
+
+        let mut name = String::new();
+        self.visit_children(&mut |i, slot| {
+            if i == index {
+                name = slot.name.to_string();
+                *slot.binding = binding.clone();
+            }
+        });
+
+        match &binding {
+            Some(binding) => self.logs.push(
+                LogEvent::info(SOURCE, format!("Bound \"{name}\" to {}", binding.pretty()))
+                    .with_payload("slot", &name)
+                    .with_payload("binding", binding),
+            ),
+            None => self.logs.push(
+                LogEvent::info(SOURCE, format!("Unbound \"{name}\"")).with_payload("slot", &name),
+            ),
+        }
+
+        self.message = None;
+        self.refresh_rows();
+
+        let mut config = HotkeyConfig::new();
+        self.visit_children(&mut |_, slot| {
+            config.insert(slot.name.to_string(), slot.binding.clone());
+        });
+        self.tx.send(config).ok();
+    }
+
+    fn binding_at(&mut self, index: usize) -> Option<Binding> {
+        let mut binding = None;
+        self.visit_children(&mut |i, slot| {
+            if i == index {
+                binding = slot.binding.clone();
+            }
+        });
+        binding
+    }
+
+    fn start_capture(&mut self, index: usize) {
+        self.capturing = Some(index);
+        self.captured_buttons = None;
+        self.message = None;
+    }
+
+    /// Replaces the keyboard alternatives of the slot being captured with the
+    /// key pressed on this frame. The controller alternatives, trigger mode
+    /// and match policy of the previous binding are kept.
+    fn capture_key(&mut self, ui: &imgui::Ui, index: usize) {
+        let Some(key) = Key::capture(ui) else {
+            return;
+        };
+
+        let binding = match self.binding_at(index) {
+            Some(binding) => binding.with_keys(vec![key.into()]),
+            None => Binding::from(key),
+        };
+
+        self.capturing = None;
+        self.rebind(index, Some(binding));
+    }
+
+    /// Replaces the controller alternatives of the slot being captured with
+    /// the inputs held together, once they are all released. The keyboard
+    /// alternatives, trigger mode and match policy of the previous binding
+    /// are kept.
+    fn capture_buttons(&mut self, input: &dyn InputSource, index: usize) {
+        let gamepad = match input.controller_state() {
+            Some(state) => state.Gamepad,
+            None => gamepad_from_keys(input),
+        };
+        let held = ControllerCombination::held(&gamepad, stick_deadzone());
+
+        let buttons = match self.captured_buttons {
+            None if held.is_empty() => {
+                self.captured_buttons = Some(held);
+                return;
+            },
+            Some(buttons) if held.is_empty() && !buttons.is_empty() => buttons,
+            Some(buttons) => {
+                self.captured_buttons = Some(buttons.union(&held));
+                return;
+            },
+            None => return,
+        };
+
+        let binding = match self.binding_at(index) {
+            Some(binding) => binding.with_buttons(vec![buttons]),
+            None => Binding::from(buttons),
+        };
+
+        self.capturing = None;
+        self.rebind(index, Some(binding));
+    }
+}
+
+impl Widget for HotkeyEditor {
+    fn render(&mut self, ui: &imgui::Ui) {
+        for widget in &mut self.children {
+            widget.render(ui);
+        }
+
+        let scale = scaling_factor(ui);
+        let button_width = BUTTON_WIDTH * scale;
+
+        let (x, y) = unsafe {
+            let mut wnd_pos = ImVec2::default();
+            igGetWindowPos(&mut wnd_pos);
+            (igGetCursorPosX() + wnd_pos.x, igGetCursorPosY() + wnd_pos.y)
+        };
+
+        if ui.button_with_size("Hotkeys", [button_width, BUTTON_HEIGHT]) {
+            ui.open_popup(HKE_TAG);
+            self.refresh_rows();
+            self.message = None;
+        }
+
+        unsafe {
+            igSetNextWindowPos(
+                ImVec2::new(x + 200. * scale, y),
+                Condition::Always as i8 as _,
+                ImVec2::new(0., 0.),
+            )
+        };
+
+        if let Some(_token) = ui
+            .modal_popup_config(HKE_TAG)
+            .resizable(false)
+            .movable(false)
+            .title_bar(false)
+            .scroll_bar(false)
+            .begin_popup()
+        {
+            let mut close_pressed = self.key_close.as_mut().is_some_and(|k| k.is_pressed(ui));
+
+            // Checked before drawing the rows, so that the key which activated
+            // a row's button is not captured on the same frame. The close key
+            // and escape cancel the capture rather than being captured.
+            if let Some(index) = self.capturing {
+                if close_pressed || ui.is_key_pressed_no_repeat(imgui::Key::Escape) {
+                    self.capturing = None;
+                    close_pressed = false;
+                } else {
+                    self.capture_key(ui, index);
+                }
+                self.capture_ended = self.capturing.is_none();
+            }
+
+            let mut action = None;
+
+            ui.columns(3, "##hotkey-editor-columns", false);
+            for (index, row) in self.rows.iter().enumerate() {
+                let _id = ui.push_id_usize(index);
+
+                ui.text(&row.name);
+                ui.next_column();
+
+                if self.capturing == Some(index) {
+                    ui.text_disabled("Press input...");
+                } else if ui.button_with_size(&row.label, [button_width * 0.5, BUTTON_HEIGHT]) {
+                    action = Some(RowAction::Capture(index));
+                }
+                ui.next_column();
+
+                if ui.button("Clear") {
+                    action = Some(RowAction::Clear(index));
+                }
+                ui.next_column();
+            }
+            ui.columns(1, "##hotkey-editor-columns", false);
+
+            match action {
+                Some(RowAction::Capture(index)) => self.start_capture(index),
+                Some(RowAction::Clear(index)) => {
+                    self.capturing = None;
+                    self.rebind(index, None);
+                },
+                None => {},
+            }
+
+            if let Some(message) = &self.message {
+                ui.text_wrapped(message);
+            }
+
+            if self.capturing.is_some() {
+                if ui.button_with_size("Cancel", [button_width, BUTTON_HEIGHT]) {
+                    self.capturing = None;
+                }
+            } else if ui.button_with_size(&self.label_close, [button_width, BUTTON_HEIGHT])
+                || (close_pressed && !ui.is_any_item_active())
+            {
+                ui.close_current_popup();
+            }
+        }
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        for widget in &mut self.children {
+            widget.render_closed(ui);
+        }
+    }
+
+    fn interact(&mut self, input: &dyn InputSource) {
+        // Don't fire the hotkeys that are being typed in as new bindings.
+        if let Some(index) = self.capturing {
+            self.capture_buttons(input, index);
+            return;
+        }
+        if std::mem::take(&mut self.capture_ended) {
+            return;
+        }
+
+        for widget in &mut self.children {
+            widget.interact(input);
+        }
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
+        for widget in &mut self.children {
+            widget.log(tx.clone());
+        }
+
+        for log in self.logs.drain(..) {
+            tx.send(log).ok();
+        }
+    }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        for widget in &mut self.children {
+            widget.hotkeys(visit);
+        }
+
+        visit(HotkeySlot {
+            name: "Hotkeys/Close",
+            scope: Scope::Popup,
+            binding: &mut self.key_close,
+        });
+        self.label_close = label_with_hotkey("Close", self.key_close.as_ref());
+    }
+}
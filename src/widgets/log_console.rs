@@ -0,0 +1,544 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Receiver, Sender};
+use imgui::sys::{igGetCursorPosX, igGetCursorPosY, igGetWindowPos, igSetNextWindowPos, ImVec2};
+use imgui::{Condition, ImColor32, StyleColor};
+
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::{LogEvent, Severity};
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+
+const LC_TAG: &str = "##log-console";
+const LCL_TAG: &str = "##log-console-list";
+const SOURCE: &str = "Log console";
+
+/// How long overlay entries take to fade out once their time is up.
+const FADE_OUT: Duration = Duration::from_secs(1);
+
+/// A history entry: an event and the number of times it was logged in a row.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// The last occurrence of the event.
+    pub event: LogEvent,
+    pub count: usize,
+    received: Instant,
+}
+
+impl LogEntry {
+    fn is_repeat(&self, event: &LogEvent) -> bool {
+        self.event.severity == event.severity
+            && self.event.source == event.source
+            && self.event.message == event.message
+    }
+}
+
+/// The events received during the session, with consecutive repeats
+/// collapsed. The oldest entries are dropped beyond the capacity.
+#[derive(Debug)]
+pub struct LogHistory {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl LogHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, event: LogEvent) {
+        let received = Instant::now();
+        if let Some(last) = self.entries.back_mut().filter(|last| last.is_repeat(&event)) {
+            last.event = event;
+            last.count += 1;
+            last.received = received;
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry { event, count: 1, received });
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    /// The distinct sources of the entries, sorted.
+    pub fn sources(&self) -> Vec<&str> {
+        let mut sources: Vec<_> = self.entries.iter().map(|e| e.event.source.as_str()).collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes every entry as a line of text, regardless of any filter.
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        for entry in &self.entries {
+            let event = &entry.event;
+            write!(
+                w,
+                "{} {:<5} [{}] {}",
+                format_time(event.timestamp),
+                event.severity,
+                event.source,
+                event.message
+            )?;
+            for (key, value) in &event.payload {
+                write!(w, " {key}={value:?}")?;
+            }
+            if entry.count > 1 {
+                write!(w, " (x{})", entry.count)?;
+            }
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+}
+
+/// Filters the entries shown by the console.
+#[derive(Debug, Default, Clone)]
+pub struct LogFilter {
+    /// Case insensitive text to look for in the messages and sources.
+    pub text: String,
+    pub source: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let event = &entry.event;
+
+        if self.source.as_ref().is_some_and(|source| *source != event.source) {
+            return false;
+        }
+
+        if self.text.is_empty() {
+            return true;
+        }
+
+        let text = self.text.to_lowercase();
+        event.message.to_lowercase().contains(&text) || event.source.to_lowercase().contains(&text)
+    }
+}
+
+/// Formats the UTC time of day, e.g. `13:37:00.123`.
+fn format_time(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = elapsed.as_secs() % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+/// Opacity of an overlay entry: opaque until `fade` has elapsed, then
+/// fading out over [`FADE_OUT`].
+fn overlay_alpha(age: Duration, fade: Duration) -> f32 {
+    match age.checked_sub(fade) {
+        None => 1.,
+        Some(over) => (1. - over.as_secs_f32() / FADE_OUT.as_secs_f32()).max(0.),
+    }
+}
+
+fn severity_color(ui: &imgui::Ui, severity: Severity) -> [f32; 4] {
+    match severity {
+        Severity::Debug => ui.style_color(StyleColor::TextDisabled),
+        Severity::Info => ui.style_color(StyleColor::Text),
+        Severity::Warning => [1., 0.8, 0.3, 1.],
+        Severity::Error => [1., 0.4, 0.4, 1.],
+    }
+}
+
+fn entry_text(entry: &LogEntry) -> String {
+    match entry.count {
+        1 => entry.event.message.clone(),
+        count => format!("{} (x{count})", entry.event.message),
+    }
+}
+
+/// Consumes the events sent to the [`Widget::log`] channel.
+///
+/// The full history opens in a popup, and the latest entries are shown in an
+/// overlay while the menu is closed, until they fade out.
+pub struct LogConsole {
+    rx: Receiver<LogEvent>,
+    history: LogHistory,
+    filter: LogFilter,
+    export_path: PathBuf,
+    overlay: Option<Duration>,
+    overlay_lines: usize,
+    label: String,
+    label_close: String,
+    key_open: Option<Binding>,
+    key_close: Option<Binding>,
+    logs: Vec<LogEvent>,
+    entered: bool,
+    request_open: bool,
+    request_close: bool,
+}
+
+impl LogConsole {
+    /// Creates a console receiving the events from `rx`, which exports the
+    /// history to `export_path`.
+    pub fn new(
+        rx: Receiver<LogEvent>,
+        key_open: Option<Binding>,
+        key_close: Option<Binding>,
+        export_path: PathBuf,
+    ) -> Self {
+        Self {
+            rx,
+            history: LogHistory::new(1000),
+            filter: LogFilter::default(),
+            export_path,
+            overlay: Some(Duration::from_secs(5)),
+            overlay_lines: 5,
+            label: label_with_hotkey("Log", key_open.as_ref()),
+            label_close: label_with_hotkey("Close", key_close.as_ref()),
+            key_open,
+            key_close,
+            logs: Vec::new(),
+            entered: false,
+            request_open: false,
+            request_close: false,
+        }
+    }
+
+    /// Sets how many entries the history keeps.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.history = LogHistory::new(capacity);
+        self
+    }
+
+    /// Sets how long entries stay in the overlay before fading out, and how
+    /// many are shown at most. `None` disables the overlay.
+    pub fn with_overlay(mut self, fade: Option<Duration>, lines: usize) -> Self {
+        self.overlay = fade;
+        self.overlay_lines = lines;
+        self
+    }
+
+    pub fn history(&self) -> &LogHistory {
+        &self.history
+    }
+
+    fn receive(&mut self) {
+        for event in self.rx.try_iter() {
+            self.history.push(event);
+        }
+    }
+
+    fn export(&mut self) {
+        let path = self.export_path.display();
+        match self.history.export(&self.export_path) {
+            Ok(()) => self.logs.push(
+                LogEvent::info(SOURCE, format!("Exported log to {path}"))
+                    .with_payload("path", &path),
+            ),
+            Err(e) => self.logs.push(
+                LogEvent::error(SOURCE, format!("Couldn't export log: {e}"))
+                    .with_payload("path", &path),
+            ),
+        }
+    }
+
+    fn render_filters(&mut self, ui: &imgui::Ui, width: f32) {
+        {
+            let _tok = ui.push_item_width(width * 0.5 - 4.);
+            ui.input_text("##log-console-filter", &mut self.filter.text).hint("filter").build();
+        }
+
+        ui.same_line();
+
+        let _tok = ui.push_item_width(width * 0.5);
+        let preview = self.filter.source.as_deref().unwrap_or("All sources");
+        if let Some(_token) = ui.begin_combo("##log-console-source", preview) {
+            if ui.selectable("All sources") {
+                self.filter.source = None;
+            }
+            let mut selected = None;
+            for source in self.history.sources() {
+                if ui.selectable(source) {
+                    selected = Some(source.to_string());
+                }
+            }
+            if selected.is_some() {
+                self.filter.source = selected;
+            }
+        }
+    }
+
+    fn render_history(&mut self, ui: &imgui::Ui) {
+        let at_bottom = ui.scroll_y() >= ui.scroll_max_y();
+
+        for entry in self.history.entries().filter(|entry| self.filter.matches(entry)) {
+            let event = &entry.event;
+            ui.text_disabled(format_time(event.timestamp));
+            ui.same_line();
+            ui.text_colored(severity_color(ui, event.severity), entry_text(entry));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(&event.source);
+            }
+        }
+
+        if at_bottom {
+            ui.set_scroll_here_y_with_ratio(1.);
+        }
+    }
+
+    fn render_overlay(&mut self, ui: &imgui::Ui) {
+        let Some(fade) = self.overlay else {
+            return;
+        };
+
+        let scale = scaling_factor(ui);
+        let line_height = ui.text_line_height_with_spacing();
+        let [_, height] = ui.io().display_size;
+        let now = Instant::now();
+
+        let visible: Vec<_> = self
+            .history
+            .entries()
+            .rev()
+            .map(|entry| (entry, overlay_alpha(now.duration_since(entry.received), fade)))
+            .take_while(|(_, alpha)| *alpha > 0.)
+            .take(self.overlay_lines)
+            .collect();
+
+        let draw_list = ui.get_foreground_draw_list();
+        for (i, (entry, alpha)) in visible.into_iter().enumerate() {
+            let pos = [16. * scale, height - 16. * scale - (i + 1) as f32 * line_height];
+            let [r, g, b, a] = severity_color(ui, entry.event.severity);
+            let text = entry_text(entry);
+
+            draw_list.add_text(
+                [pos[0] + 1., pos[1] + 1.],
+                ImColor32::from_rgba_f32s(0., 0., 0., a * alpha),
+                &text,
+            );
+            draw_list.add_text(pos, ImColor32::from_rgba_f32s(r, g, b, a * alpha), &text);
+        }
+    }
+}
+
+impl Widget for LogConsole {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.receive();
+
+        let scale = scaling_factor(ui);
+        let button_width = BUTTON_WIDTH * scale;
+
+        let (x, y) = unsafe {
+            let mut wnd_pos = ImVec2::default();
+            igGetWindowPos(&mut wnd_pos);
+            (igGetCursorPosX() + wnd_pos.x, igGetCursorPosY() + wnd_pos.y)
+        };
+
+        if ui.button_with_size(&self.label, [button_width, BUTTON_HEIGHT])
+            || std::mem::take(&mut self.request_open)
+        {
+            ui.open_popup(LC_TAG);
+        }
+
+        unsafe {
+            igSetNextWindowPos(
+                ImVec2::new(x + 200. * scale, y),
+                Condition::Always as i8 as _,
+                ImVec2::new(0., 0.),
+            )
+        };
+
+        if let Some(_token) = ui
+            .modal_popup_config(LC_TAG)
+            .resizable(false)
+            .movable(false)
+            .title_bar(false)
+            .scroll_bar(false)
+            .begin_popup()
+        {
+            let width = button_width * 2.;
+
+            self.render_filters(ui, width);
+
+            ui.child_window(LCL_TAG)
+                .size([width, 300. * scale])
+                .horizontal_scrollbar(true)
+                .build(|| self.render_history(ui));
+
+            if ui.button_with_size("Export", [width * 0.5 - 4., BUTTON_HEIGHT]) {
+                self.export();
+            }
+
+            ui.same_line();
+
+            if ui.button_with_size("Clear", [width * 0.5, BUTTON_HEIGHT]) {
+                self.history.clear();
+            }
+
+            if ui.button_with_size(&self.label_close, [width, BUTTON_HEIGHT])
+                || (!ui.is_any_item_active()
+                    && self.key_close.as_mut().map(|k| k.is_pressed(ui)).unwrap_or(false))
+                || std::mem::take(&mut self.request_close)
+            {
+                ui.close_current_popup();
+                self.entered = false;
+            }
+        } else {
+            self.request_close = false;
+        }
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.receive();
+        self.render_overlay(ui);
+    }
+
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.key_open.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
+            self.request_open = true;
+        }
+    }
+
+    fn action(&mut self) {
+        self.request_open = true;
+    }
+
+    fn want_enter(&mut self) -> bool {
+        self.request_open = true;
+        self.entered = true;
+        true
+    }
+
+    fn want_exit(&mut self) -> bool {
+        self.request_close = true;
+        self.entered = false;
+        true
+    }
+
+    fn is_entered(&mut self) -> bool {
+        self.entered
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
+        for log in self.logs.drain(..) {
+            tx.send(log).ok();
+        }
+    }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot { name: "Log/Open", scope: Scope::Global, binding: &mut self.key_open });
+        self.label = label_with_hotkey("Log", self.key_open.as_ref());
+
+        visit(HotkeySlot { name: "Log/Close", scope: Scope::Popup, binding: &mut self.key_close });
+        self.label_close = label_with_hotkey("Close", self.key_close.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(severity: Severity, source: &str, message: &str) -> LogEvent {
+        let mut event = LogEvent::new(severity, source, message);
+        event.timestamp = UNIX_EPOCH + Duration::from_millis(49_530_250);
+        event
+    }
+
+    fn messages(history: &LogHistory, filter: &LogFilter) -> Vec<String> {
+        history.entries().filter(|e| filter.matches(e)).map(entry_text).collect()
+    }
+
+    #[test]
+    fn test_collapse() {
+        let mut history = LogHistory::new(3);
+        history.push(event(Severity::Info, "a", "one"));
+        history.push(event(Severity::Info, "a", "one"));
+        history.push(event(Severity::Info, "b", "one"));
+        history.push(event(Severity::Error, "b", "one"));
+        history.push(event(Severity::Error, "b", "one"));
+        history.push(event(Severity::Error, "b", "one"));
+        assert_eq!(messages(&history, &LogFilter::default()), ["one (x2)", "one", "one (x3)"]);
+
+        // Repeats are only collapsed with the last entry.
+        history.push(event(Severity::Info, "a", "one"));
+        assert_eq!(messages(&history, &LogFilter::default()), ["one", "one (x3)", "one"]);
+        assert_eq!(history.sources(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut history = LogHistory::new(10);
+        history.push(event(Severity::Info, "test 1", "test 1 activated"));
+        history.push(event(Severity::Error, "Savefile manager", "Error loading savefile: nope"));
+        history.push(event(Severity::Info, "Savefile manager", "Loaded /save1.sl2"));
+
+        let filter = LogFilter { text: "SAVE".to_string(), source: None };
+        assert_eq!(messages(&history, &filter), [
+            "Error loading savefile: nope",
+            "Loaded /save1.sl2"
+        ]);
+
+        let filter = LogFilter { text: "test".to_string(), source: None };
+        assert_eq!(messages(&history, &filter), ["test 1 activated"]);
+
+        let filter = LogFilter { text: "loaded".to_string(), source: Some("test 1".to_string()) };
+        assert!(messages(&history, &filter).is_empty());
+
+        let filter =
+            LogFilter { text: String::new(), source: Some("Savefile manager".to_string()) };
+        assert_eq!(messages(&history, &filter).len(), 2);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut history = LogHistory::new(10);
+        history.push(event(Severity::Info, "test 1", "test 1 activated"));
+        history.push(event(Severity::Info, "test 1", "test 1 activated"));
+        history.push(
+            event(Severity::Error, "Savefile manager", "Error loading savefile: nope")
+                .with_payload("path", "/save1.sl2"),
+        );
+
+        let mut out = Vec::new();
+        history.write_to(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "13:45:30.250 INFO  [test 1] test 1 activated (x2)\n13:45:30.250 ERROR [Savefile \
+             manager] Error loading savefile: nope path=\"/save1.sl2\"\n"
+        );
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        history.export(file.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_overlay_alpha() {
+        let fade = Duration::from_secs(5);
+        assert_eq!(overlay_alpha(Duration::ZERO, fade), 1.);
+        assert_eq!(overlay_alpha(fade, fade), 1.);
+        assert_eq!(overlay_alpha(fade + FADE_OUT / 2, fade), 0.5);
+        assert_eq!(overlay_alpha(fade + FADE_OUT, fade), 0.);
+        assert_eq!(overlay_alpha(fade * 2, fade), 0.);
+    }
+}
@@ -1,8 +1,17 @@
 use crossbeam_channel::Sender;
 
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::HotkeySlot;
+
+pub mod factory;
 pub mod flag;
 pub mod group;
+pub mod hotkey_editor;
 pub mod label;
+pub mod log_console;
+pub mod navigator;
 pub mod nudge_position;
 pub mod position;
 pub mod radial_menu;
@@ -24,26 +33,61 @@ pub fn scaling_factor(ui: &imgui::Ui) -> f32 {
     }
 }
 
+/// Formats a label followed by its binding in parentheses, if there is one.
+pub(crate) fn label_with_hotkey(label: &str, binding: Option<&Binding>) -> String {
+    match binding {
+        Some(binding) => format!("{label} ({})", binding.pretty()),
+        None => label.to_string(),
+    }
+}
+
 pub trait Widget: Send + Sync {
     fn render(&mut self, _ui: &imgui::Ui);
 
     fn render_closed(&mut self, _ui: &imgui::Ui) {}
 
-    fn interact(&mut self, _ui: &imgui::Ui) {}
+    fn interact(&mut self, _input: &dyn InputSource) {}
 
+    /// Whether the widget can be activated, e.g. `false` while the state it
+    /// toggles can't be read.
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// Activates the widget, or the item focused inside it once entered.
     fn action(&mut self) {}
 
+    /// Moves the focus inside the widget once entered.
     fn cursor_down(&mut self) {}
 
+    /// Moves the focus inside the widget once entered.
     fn cursor_up(&mut self) {}
 
+    /// Called when the widget is activated from a [`Navigator`], instead of
+    /// [`Widget::action`]. Widgets with a popup open it and return `true`, so
+    /// that the following navigation commands go to them.
+    ///
+    /// [`Navigator`]: navigator::Navigator
     fn want_enter(&mut self) -> bool {
         false
     }
 
+    /// Called on the entered widget when the user goes back. Returns `true`
+    /// if the widget closed, or `false` if it only went back inside itself.
     fn want_exit(&mut self) -> bool {
         false
     }
 
-    fn log(&mut self, _tx: Sender<String>) {}
+    /// Whether the widget is still entered after [`Widget::want_enter`]
+    /// returned `true`. Widgets with a popup return `false` once it was
+    /// closed some other way, e.g. with the mouse or its close key.
+    fn is_entered(&mut self) -> bool {
+        true
+    }
+
+    /// Sends the events logged since the last call.
+    fn log(&mut self, _tx: Sender<LogEvent>) {}
+
+    /// Visits the binding slots of this widget and of its children.
+    fn hotkeys(&mut self, _visit: &mut dyn FnMut(HotkeySlot<'_>)) {}
 }
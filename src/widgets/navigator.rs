@@ -0,0 +1,489 @@
+use crossbeam_channel::Sender;
+use imgui::StyleColor;
+
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::Widget;
+
+/// Which of a list of children has the focus, and whether it was entered.
+///
+/// Shared by the widgets that list children, e.g. [`Group`], so that
+/// navigation commands are forwarded down to the deepest entered widget.
+///
+/// [`Group`]: crate::widgets::group::Group
+#[derive(Debug, Default)]
+pub(crate) struct Focus {
+    cursor: usize,
+    entered: bool,
+    visible: bool,
+}
+
+impl Focus {
+    pub(crate) fn cursor_up(&mut self, children: &mut [Box<dyn Widget>]) {
+        match children.get_mut(self.cursor) {
+            Some(child) if self.entered => child.cursor_up(),
+            _ if !self.visible => self.visible = true,
+            _ => self.cursor = self.cursor.checked_sub(1).unwrap_or(children.len().max(1) - 1),
+        }
+    }
+
+    pub(crate) fn cursor_down(&mut self, children: &mut [Box<dyn Widget>]) {
+        match children.get_mut(self.cursor) {
+            Some(child) if self.entered => child.cursor_down(),
+            _ if !self.visible => self.visible = true,
+            _ => self.cursor = (self.cursor + 1) % children.len().max(1),
+        }
+    }
+
+    /// Forwards the action to the entered child, or enters the focused child
+    /// if it wants to be, or activates it.
+    pub(crate) fn action(&mut self, children: &mut [Box<dyn Widget>]) {
+        self.visible = true;
+        let Some(child) = children.get_mut(self.cursor) else {
+            return;
+        };
+
+        if self.entered {
+            child.action();
+        } else if child.want_enter() {
+            self.entered = true;
+        } else {
+            child.action();
+        }
+    }
+
+    /// Exits the entered child, if there is one. Returns `true` if there is
+    /// none, meaning that the owner of the focus should be exited.
+    pub(crate) fn want_exit(&mut self, children: &mut [Box<dyn Widget>]) -> bool {
+        self.visible = true;
+        match children.get_mut(self.cursor) {
+            Some(child) if self.entered => {
+                if child.want_exit() {
+                    self.entered = false;
+                }
+                false
+            },
+            _ => true,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Renders the children, with a frame around the focused one once
+    /// navigation started. The focused child is exited if it closed itself.
+    pub(crate) fn render(&mut self, ui: &imgui::Ui, children: &mut [Box<dyn Widget>]) {
+        for (i, widget) in children.iter_mut().enumerate() {
+            let group = ui.begin_group();
+            widget.render(ui);
+            group.end();
+
+            if i == self.cursor {
+                self.entered &= widget.is_entered();
+                if self.visible {
+                    highlight(ui, self.entered);
+                }
+            }
+        }
+    }
+}
+
+/// Draws the focus frame around the last item. Entered items get a thinner
+/// frame, since the focus moved inside them.
+pub(crate) fn highlight(ui: &imgui::Ui, entered: bool) {
+    let [x0, y0] = ui.item_rect_min();
+    let [x1, y1] = ui.item_rect_max();
+    let color = ui.style_color(StyleColor::NavHighlight);
+
+    ui.get_window_draw_list()
+        .add_rect([x0 - 2., y0 - 2.], [x1 + 2., y1 + 2.], color)
+        .thickness(if entered { 1. } else { 2. })
+        .build();
+}
+
+/// Wraps a widget tree and drives its navigation methods from a controller or
+/// the keyboard, so that the tool can be used without a mouse.
+///
+/// By default, the D-pad, the left stick or the arrows move the cursor, A or
+/// enter activate or enter the focused widget, and B or escape exit it.
+pub struct Navigator {
+    key_up: Option<Binding>,
+    key_down: Option<Binding>,
+    key_action: Option<Binding>,
+    key_exit: Option<Binding>,
+    children: Vec<Box<dyn Widget>>,
+    focus: Focus,
+    /// Whether the menu was rendered open on this frame.
+    open: bool,
+    item_active: bool,
+}
+
+impl Navigator {
+    pub fn new(children: Vec<Box<dyn Widget>>) -> Self {
+        let binding = |s: &str| s.parse().ok();
+
+        Self {
+            key_up: binding("repeat:150ms up | pad:up | lstick_up"),
+            key_down: binding("repeat:150ms down | pad:down | lstick_down"),
+            key_action: binding("enter | pad:a"),
+            key_exit: binding("escape | pad:b"),
+            children,
+            focus: Focus::default(),
+            open: false,
+            item_active: false,
+        }
+    }
+
+    pub fn with_bindings(
+        mut self,
+        key_up: Option<Binding>,
+        key_down: Option<Binding>,
+        key_action: Option<Binding>,
+        key_exit: Option<Binding>,
+    ) -> Self {
+        self.key_up = key_up;
+        self.key_down = key_down;
+        self.key_action = key_action;
+        self.key_exit = key_exit;
+        self
+    }
+}
+
+impl Widget for Navigator {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.focus.render(ui, &mut self.children);
+        self.open = true;
+        self.item_active = ui.is_any_item_active();
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.open = false;
+        for widget in &mut self.children {
+            widget.render_closed(ui);
+        }
+    }
+
+    fn interact(&mut self, input: &dyn InputSource) {
+        let pressed = |binding: &mut Option<Binding>| {
+            binding.as_mut().map(|b| b.is_pressed(input)).unwrap_or(false)
+        };
+
+        // All the bindings are polled on every frame to keep their state.
+        let up = pressed(&mut self.key_up);
+        let down = pressed(&mut self.key_down);
+        let action = pressed(&mut self.key_action);
+        let exit = pressed(&mut self.key_exit);
+
+        // Don't steal the keys used in game while the menu is closed, or the
+        // ones typed in a text field.
+        if self.open && !self.item_active {
+            if up {
+                self.focus.cursor_up(&mut self.children);
+            }
+            if down {
+                self.focus.cursor_down(&mut self.children);
+            }
+            if action {
+                self.focus.action(&mut self.children);
+            }
+            if exit {
+                self.focus.want_exit(&mut self.children);
+            }
+        }
+
+        for widget in &mut self.children {
+            widget.interact(input);
+        }
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
+        for widget in &mut self.children {
+            widget.log(tx.clone());
+        }
+    }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        for widget in &mut self.children {
+            widget.hotkeys(visit);
+        }
+
+        for (name, binding) in [
+            ("Navigation/Up", &mut self.key_up),
+            ("Navigation/Down", &mut self.key_down),
+            ("Navigation/Action", &mut self.key_action),
+            ("Navigation/Exit", &mut self.key_exit),
+        ] {
+            // Like the close keys of popups, which exit is meant to overlap.
+            visit(HotkeySlot { name, scope: Scope::Popup, binding });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::headless::Harness;
+    use crate::key::ScriptedInput;
+    use crate::test_support::TestFlag;
+    use crate::widgets::flag::FlagWidget;
+    use crate::widgets::group::Group;
+    use crate::widgets::savefile_manager::SavefileManager;
+    use crate::widgets::stats_editor::{Datum, Stats, StatsEditor};
+
+    #[derive(Default)]
+    struct TestStats {
+        hp: i32,
+        mp: i32,
+        open: bool,
+        unreadable: bool,
+        written: Arc<AtomicI32>,
+    }
+
+    impl Stats for TestStats {
+        fn data(&mut self) -> Option<impl Iterator<Item = Datum<'_>>> {
+            self.open.then(|| {
+                [
+                    Datum::int("HP", &mut self.hp, 1, 99),
+                    Datum::separator(),
+                    Datum::int("MP", &mut self.mp, 0, 99),
+                ]
+                .into_iter()
+            })
+        }
+
+        fn read(&mut self) {
+            if self.unreadable {
+                return;
+            }
+            self.open = true;
+            self.hp = 10;
+            self.mp = 5;
+        }
+
+        fn write(&mut self) {
+            self.written.store(self.hp * 100 + self.mp, Ordering::Relaxed);
+        }
+
+        fn clear(&mut self) {
+            self.open = false;
+        }
+    }
+
+    /// A navigator rendered open, for the tests which only interact with it.
+    fn open(children: Vec<Box<dyn Widget>>) -> Navigator {
+        let mut navigator = Navigator::new(children);
+        navigator.open = true;
+        navigator
+    }
+
+    fn tap(navigator: &mut Navigator, input: &mut ScriptedInput, key: imgui::Key) {
+        input.press(key);
+        navigator.interact(input);
+        input.release_all();
+        input.next_frame(Duration::from_millis(16));
+        navigator.interact(input);
+        input.next_frame(Duration::from_millis(16));
+    }
+
+    #[test]
+    fn test_navigation() {
+        use imgui::Key::*;
+
+        let flags: Vec<_> = (0..3).map(|_| TestFlag::default()).collect();
+        let flag = |i: usize| -> Box<dyn Widget> {
+            Box::new(FlagWidget::new(&format!("{i}"), flags[i].clone(), None))
+        };
+        let state = |i: usize| flags[i].is_set();
+
+        let mut navigator = open(vec![
+            flag(0),
+            flag(1),
+            Box::new(Group::new("Group", "f12".parse().unwrap(), vec![flag(2)])),
+        ]);
+        let mut input = ScriptedInput::new();
+
+        // The first press only shows the focus.
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!([state(0), state(1), state(2)], [false, true, false]);
+
+        // Entering the group forwards the commands to its children.
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!([state(0), state(1), state(2)], [false, true, true]);
+
+        // Exiting the group gives the focus back, which wraps around.
+        tap(&mut navigator, &mut input, Escape);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!([state(0), state(1), state(2)], [true, true, true]);
+
+        tap(&mut navigator, &mut input, UpArrow);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!([state(0), state(1), state(2)], [true, true, false]);
+    }
+
+    #[test]
+    fn test_navigation_stats() {
+        use imgui::Key::*;
+
+        let stats = TestStats::default();
+        let written = Arc::clone(&stats.written);
+        let mut navigator = open(vec![Box::new(StatsEditor::new(stats, None, None))]);
+        let mut input = ScriptedInput::new();
+
+        // Up and down step the value being edited, and skip separators
+        // otherwise.
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, UpArrow);
+        tap(&mut navigator, &mut input, UpArrow);
+        tap(&mut navigator, &mut input, Escape);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Escape);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!(written.load(Ordering::Relaxed), 1204);
+
+        // Exiting discards the edits, and entering again reads the stats.
+        tap(&mut navigator, &mut input, Escape);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, UpArrow);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!(written.load(Ordering::Relaxed), 1005);
+
+        // Stats that can't be read aren't entered, so nothing is written.
+        let stats = TestStats { unreadable: true, ..Default::default() };
+        let written = Arc::clone(&stats.written);
+        written.store(-1, Ordering::Relaxed);
+        let mut navigator = open(vec![Box::new(StatsEditor::new(stats, None, None))]);
+        tap(&mut navigator, &mut input, DownArrow);
+        tap(&mut navigator, &mut input, Enter);
+        tap(&mut navigator, &mut input, Enter);
+        assert_eq!(written.load(Ordering::Relaxed), -1);
+    }
+
+    #[test]
+    fn test_navigation_tree() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let savefile = tmp_dir.path().join("ER0000.sl2");
+        fs::write(&savefile, "ER0000").unwrap();
+        fs::write(tmp_dir.path().join("save1.sl2"), "save1").unwrap();
+        fs::create_dir_all(tmp_dir.path().join("Any%")).unwrap();
+        fs::write(tmp_dir.path().join("Any%").join("ER0001.sl2"), "ER0001").unwrap();
+
+        let mut navigator =
+            Navigator::new(vec![Box::new(SavefileManager::new(None, None, savefile.clone()))]);
+        let mut harness = Harness::new();
+        let mut tap = |harness: &mut Harness, key: &str| {
+            harness.tap(key);
+            harness.run(&mut navigator, 3);
+        };
+
+        tap(&mut harness, "down");
+        tap(&mut harness, "enter");
+        assert!(harness.item("Any%").is_none());
+
+        // The selection goes through the visible nodes, directories first,
+        // and the action opens or closes directories.
+        tap(&mut harness, "down");
+        tap(&mut harness, "enter");
+        assert!(harness.item("Any%").is_some());
+        tap(&mut harness, "down");
+        tap(&mut harness, "enter");
+        assert!(harness.item("ER0001.sl2").is_some());
+        tap(&mut harness, "down");
+        tap(&mut harness, "enter");
+        assert_eq!(fs::read_to_string(&savefile).unwrap(), "ER0001");
+
+        // Closed directories are skipped.
+        tap(&mut harness, "up");
+        tap(&mut harness, "enter");
+        assert!(harness.item("ER0001.sl2").is_none());
+        tap(&mut harness, "down");
+        tap(&mut harness, "down");
+        tap(&mut harness, "enter");
+        assert_eq!(fs::read_to_string(&savefile).unwrap(), "save1");
+
+        tap(&mut harness, "escape");
+        assert!(harness.item("Any%").is_none());
+    }
+
+    #[test]
+    fn test_navigation_closed() {
+        let flags: Vec<_> = (0..2).map(|_| TestFlag::default()).collect();
+        let flag = |i: usize| -> Box<dyn Widget> {
+            Box::new(FlagWidget::new(&format!("{i}"), flags[i].clone(), None))
+        };
+        let state = |i: usize| flags[i].is_set();
+
+        let mut navigator = Navigator::new(vec![
+            flag(0),
+            Box::new(Group::new("Group", "f12".parse().unwrap(), vec![flag(1)])),
+        ]);
+        let mut harness = Harness::new();
+        let mut run = |harness: &mut Harness, frames| harness.run(&mut navigator, frames);
+        let tap = |harness: &mut Harness, run: &mut dyn FnMut(&mut Harness, usize), key| {
+            harness.tap(key);
+            run(harness, 3);
+        };
+
+        tap(&mut harness, &mut run, "down");
+        tap(&mut harness, &mut run, "down");
+        tap(&mut harness, &mut run, "enter");
+        assert!(harness.item("1").is_some());
+
+        // Closing the group with the mouse exits it.
+        assert!(harness.click("Close (F12)"));
+        run(&mut harness, 4);
+        assert!(harness.item("1").is_none());
+        tap(&mut harness, &mut run, "up");
+        tap(&mut harness, &mut run, "enter");
+        assert_eq!([state(0), state(1)], [true, false]);
+
+        // So does closing it with its close key.
+        tap(&mut harness, &mut run, "down");
+        tap(&mut harness, &mut run, "enter");
+        assert!(harness.item("1").is_some());
+        tap(&mut harness, &mut run, "f12");
+        assert!(harness.item("1").is_none());
+        tap(&mut harness, &mut run, "up");
+        tap(&mut harness, &mut run, "enter");
+        assert_eq!([state(0), state(1)], [false, false]);
+    }
+
+    #[test]
+    fn test_navigation_menu_closed() {
+        let flag = TestFlag::default();
+        let mut navigator =
+            Navigator::new(vec![Box::new(FlagWidget::new("0", flag.clone(), None))]);
+        let mut harness = Harness::new();
+
+        harness.run(&mut navigator, 1);
+        harness.tap("down");
+        harness.run(&mut navigator, 2);
+
+        // The keys go to the game while the menu is closed.
+        harness.tap("enter");
+        harness.run_closed(&mut navigator, 2);
+        assert!(!flag.is_set());
+
+        harness.tap("enter");
+        harness.run(&mut navigator, 2);
+        assert!(flag.is_set());
+    }
+}
@@ -1,6 +1,8 @@
-use crate::key::Key;
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::registry::{HotkeySlot, Scope};
 use crate::widgets::position::PositionStorage;
-use crate::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
 
 pub trait NudgePositionStorage: PositionStorage {
     fn nudge_up(&mut self);
@@ -9,23 +11,20 @@ pub trait NudgePositionStorage: PositionStorage {
 
 pub struct NudgePosition<N: NudgePositionStorage> {
     nudge_position: N,
-    key_nudge_up: Option<Key>,
-    key_nudge_down: Option<Key>,
+    key_nudge_up: Option<Binding>,
+    key_nudge_down: Option<Binding>,
     label_nudge_up: String,
     label_nudge_down: String,
 }
 
 impl<N: NudgePositionStorage> NudgePosition<N> {
-    pub fn new(nudge_position: N, key_nudge_up: Option<Key>, key_nudge_down: Option<Key>) -> Self {
-        let label_nudge_up = match key_nudge_up {
-            Some(key_nudge_up) => format!("Nudge up ({key_nudge_up})"),
-            None => "Nudge up".to_string(),
-        };
-
-        let label_nudge_down = match key_nudge_down {
-            Some(key_nudge_down) => format!("Nudge down ({key_nudge_down})"),
-            None => "Nudge down".to_string(),
-        };
+    pub fn new(
+        nudge_position: N,
+        key_nudge_up: Option<Binding>,
+        key_nudge_down: Option<Binding>,
+    ) -> Self {
+        let label_nudge_up = label_with_hotkey("Nudge up", key_nudge_up.as_ref());
+        let label_nudge_down = label_with_hotkey("Nudge down", key_nudge_down.as_ref());
 
         Self { nudge_position, key_nudge_up, key_nudge_down, label_nudge_up, label_nudge_down }
     }
@@ -48,13 +47,29 @@ impl<N: NudgePositionStorage> Widget for NudgePosition<N> {
         }
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        if self.key_nudge_up.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.key_nudge_up.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.nudge_position.nudge_up();
         }
 
-        if self.key_nudge_down.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+        if self.key_nudge_down.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.nudge_position.nudge_down();
         }
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot {
+            name: "Nudge up",
+            scope: Scope::Global,
+            binding: &mut self.key_nudge_up,
+        });
+        visit(HotkeySlot {
+            name: "Nudge down",
+            scope: Scope::Global,
+            binding: &mut self.key_nudge_down,
+        });
+
+        self.label_nudge_up = label_with_hotkey("Nudge up", self.key_nudge_up.as_ref());
+        self.label_nudge_down = label_with_hotkey("Nudge down", self.key_nudge_down.as_ref());
+    }
 }
@@ -1,5 +1,8 @@
-use crate::key::Key;
-use crate::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
 
 pub trait PositionStorage: Send + Sync + 'static {
     fn save(&mut self);
@@ -11,22 +14,22 @@ pub trait PositionStorage: Send + Sync + 'static {
 
 pub struct Position<P: PositionStorage> {
     storage: P,
-    key_read: Option<Key>,
-    key_write: Option<Key>,
+    name: String,
+    key_read: Option<Binding>,
+    key_write: Option<Binding>,
     label_load: String,
     label_save: String,
-    logs: Vec<String>,
+    logs: Vec<LogEvent>,
 }
 
 impl<P: PositionStorage> Position<P> {
-    pub fn new(storage: P, key_load: Option<Key>, key_save: Option<Key>) -> Self {
-        let label_load =
-            key_load.map(|k| format!("Load ({k})")).unwrap_or_else(|| "Load".to_string());
-        let label_save =
-            key_save.map(|k| format!("Save ({k})")).unwrap_or_else(|| "Save".to_string());
+    pub fn new(storage: P, key_load: Option<Binding>, key_save: Option<Binding>) -> Self {
+        let label_load = label_with_hotkey("Load", key_load.as_ref());
+        let label_save = label_with_hotkey("Save", key_save.as_ref());
 
         Self {
             storage,
+            name: "Position".to_string(),
             key_write: key_load,
             key_read: key_save,
             label_load,
@@ -35,14 +38,23 @@ impl<P: PositionStorage> Position<P> {
         }
     }
 
+    /// Sets the name that the hotkey slots and the log events are under, to
+    /// tell apart several positions. Defaults to "Position".
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
     pub fn save_position(&mut self) {
         self.storage.save();
-        self.logs.push(format!("Saved position  {}", self.storage.display_stored()));
+        let message = format!("Saved position  {}", self.storage.display_stored());
+        self.logs.push(LogEvent::info(&self.name, message));
     }
 
     pub fn load_position(&mut self) {
         self.storage.load();
-        self.logs.push(format!("Loaded position {}", self.storage.display_stored()));
+        let message = format!("Loaded position {}", self.storage.display_stored());
+        self.logs.push(LogEvent::info(&self.name, message));
     }
 }
 
@@ -67,12 +79,12 @@ impl<S: PositionStorage> Widget for Position<S> {
         ui.text(self.storage.display_stored());
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        if self.key_write.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.key_write.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.load_position();
         }
 
-        if self.key_read.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+        if self.key_read.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.save_position();
         }
     }
@@ -81,9 +93,19 @@ impl<S: PositionStorage> Widget for Position<S> {
         self.load_position();
     }
 
-    fn log(&mut self, tx: crossbeam_channel::Sender<String>) {
+    fn log(&mut self, tx: crossbeam_channel::Sender<LogEvent>) {
         self.logs.drain(..).for_each(|log| {
             tx.send(log).ok();
         });
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        let name = format!("{}/Load", self.name);
+        visit(HotkeySlot { name: &name, scope: Scope::Global, binding: &mut self.key_write });
+        let name = format!("{}/Save", self.name);
+        visit(HotkeySlot { name: &name, scope: Scope::Global, binding: &mut self.key_read });
+
+        self.label_load = label_with_hotkey("Load", self.key_write.as_ref());
+        self.label_save = label_with_hotkey("Save", self.key_read.as_ref());
+    }
 }
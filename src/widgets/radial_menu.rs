@@ -1,10 +1,198 @@
 use std::f32::consts::PI;
 
+use crossbeam_channel::Sender;
 use imgui::sys::{
-    igCalcTextSize, igGetForegroundDrawList, ImDrawList_AddText_Vec2, ImDrawList_PathArcTo,
-    ImDrawList_PathFillConvex, ImVec2,
+    igCalcTextSize, igGetForegroundDrawList, igIsMousePosValid, ImDrawList_AddImage,
+    ImDrawList_AddText_Vec2, ImDrawList_PathArcTo, ImDrawList_PathFillConvex, ImVec2,
 };
-use imgui::{ImColor32, StyleColor};
+use imgui::{ImColor32, StyleColor, TextureId};
+
+use crate::binding::Binding;
+use crate::controller::{gamepad_from_keys, stick_deadzone};
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{scaling_factor, Widget};
+
+/// Layout and colors of a radial menu. Lengths are in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadialStyle {
+    /// Angle of the middle of the first slice, in radians clockwise from the
+    /// right. Defaults to the top.
+    pub start_angle: f32,
+    /// Space between two slices.
+    pub gap: f32,
+    pub radius_min: f32,
+    pub radius_max: f32,
+    /// Space between a ring and the ring of a submenu around it.
+    pub ring_gap: f32,
+    /// Length of the selection vector under which nothing is selected.
+    pub deadzone: f32,
+    pub icon_size: f32,
+    /// The colors, or `None` to use the imgui style's.
+    pub color_slice: Option<[f32; 4]>,
+    pub color_slice_active: Option<[f32; 4]>,
+    pub color_slice_disabled: Option<[f32; 4]>,
+    pub color_text: Option<[f32; 4]>,
+    pub color_text_disabled: Option<[f32; 4]>,
+    pub color_text_shadow: Option<[f32; 4]>,
+}
+
+impl Default for RadialStyle {
+    fn default() -> Self {
+        Self {
+            start_angle: -PI * 0.5,
+            gap: 3.0,
+            radius_min: 80.,
+            radius_max: 160.,
+            ring_gap: 6.,
+            deadzone: 0.,
+            icon_size: 32.,
+            color_slice: None,
+            color_slice_active: None,
+            color_slice_disabled: None,
+            color_text: None,
+            color_text_disabled: None,
+            color_text_shadow: None,
+        }
+    }
+}
+
+impl RadialStyle {
+    /// The style with all lengths multiplied by `scale`, e.g. the
+    /// [`scaling_factor`].
+    pub fn scaled(&self, scale: f32) -> Self {
+        Self {
+            gap: self.gap * scale,
+            radius_min: self.radius_min * scale,
+            radius_max: self.radius_max * scale,
+            ring_gap: self.ring_gap * scale,
+            icon_size: self.icon_size * scale,
+            ..*self
+        }
+    }
+
+    /// The inner and outer radius of the ring at `depth`, 0 being the
+    /// innermost.
+    pub fn radii(&self, depth: usize) -> (f32, f32) {
+        let width = self.radius_max - self.radius_min;
+        let radius_min = self.radius_min + depth as f32 * (width + self.ring_gap);
+        (radius_min, radius_min + width)
+    }
+
+    fn color(ui: &imgui::Ui, color: Option<[f32; 4]>, fallback: StyleColor) -> u32 {
+        ImColor32::from(color.unwrap_or_else(|| ui.style_color(fallback))).to_bits()
+    }
+}
+
+/// A slice as drawn by [`radial_menu_with_style`].
+#[derive(Debug, Clone, Copy)]
+pub struct RadialSlice<'a> {
+    pub label: &'a str,
+    pub icon: Option<TextureId>,
+    pub enabled: bool,
+}
+
+impl<'a> From<&'a str> for RadialSlice<'a> {
+    fn from(label: &'a str) -> Self {
+        Self { label, icon: None, enabled: true }
+    }
+}
+
+/// Where a slice is drawn, relative to the center of the menu. Angles are in
+/// radians clockwise from the right, and `angle_min < angle_max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SliceGeometry {
+    pub angle_min: f32,
+    pub angle_max: f32,
+    pub radius_min: f32,
+    pub radius_max: f32,
+}
+
+impl SliceGeometry {
+    pub fn angle_mid(&self) -> f32 {
+        (self.angle_min + self.angle_max) * 0.5
+    }
+
+    /// The point in the middle of the slice, e.g. to center its label on.
+    pub fn mid(&self) -> ImVec2 {
+        let radius = (self.radius_min + self.radius_max) * 0.5;
+        let angle = self.angle_mid();
+        ImVec2 { x: radius * angle.cos(), y: radius * angle.sin() }
+    }
+}
+
+/// The angular layout of a ring of slices, and hit testing against it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ring {
+    /// Where the first slice starts.
+    start: f32,
+    slice_angle: f32,
+    count: usize,
+}
+
+impl Ring {
+    /// A ring around the full circle, the first slice being centered on
+    /// `start_angle`.
+    pub fn full(count: usize, start_angle: f32) -> Self {
+        let slice_angle = PI * 2.0 / (count.max(1) as f32);
+        Self { start: start_angle - slice_angle * 0.5, slice_angle, count }
+    }
+
+    /// The ring of a submenu of `count` slices, as an arc centered on the
+    /// slice at `index`. Its slices are as wide as this ring's unless they
+    /// would not fit.
+    pub fn submenu(&self, index: usize, count: usize) -> Self {
+        let (angle_min, angle_max) = self.angles(index);
+        let slice_angle = self.slice_angle.min(PI * 2.0 / (count.max(1) as f32));
+        let angle_mid = (angle_min + angle_max) * 0.5;
+        Self { start: angle_mid - slice_angle * count as f32 * 0.5, slice_angle, count }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The start and end angles of the slice at `index`.
+    pub fn angles(&self, index: usize) -> (f32, f32) {
+        let angle_min = self.start + self.slice_angle * index as f32;
+        (angle_min, angle_min + self.slice_angle)
+    }
+
+    pub fn slice(&self, index: usize, (radius_min, radius_max): (f32, f32)) -> SliceGeometry {
+        let (angle_min, angle_max) = self.angles(index);
+        SliceGeometry { angle_min, angle_max, radius_min, radius_max }
+    }
+
+    /// The slice that the `pos` vector points at, if it is longer than
+    /// `deadzone`.
+    ///
+    /// Every direction hits exactly one slice of a full ring, including
+    /// across the 0/2π seam; a slice owns its start angle.
+    pub fn hit_test(&self, pos: ImVec2, deadzone: f32) -> Option<usize> {
+        if pos.x.hypot(pos.y) <= deadzone.max(0.0) {
+            return None;
+        }
+
+        let angle = f32::atan2(pos.y, pos.x);
+        let offset = (angle - self.start).rem_euclid(PI * 2.0);
+        let index = (offset / self.slice_angle) as usize;
+
+        if index < self.count {
+            Some(index)
+        } else if self.is_full() && self.count > 0 {
+            // Rounding can put the angles just before the start at 2π.
+            Some(self.count - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the ring goes around the full circle, unlike submenu arcs.
+    fn is_full(&self) -> bool {
+        self.slice_angle * self.count as f32 >= PI * 2.0 - 1e-4
+    }
+}
 
 /// # Safety
 ///
@@ -12,44 +200,62 @@ use imgui::{ImColor32, StyleColor};
 /// lifetime of holding a &Ui reference.
 unsafe fn draw_slice(
     ui: &imgui::Ui,
-    txt: &str,
-    angle_base: f32,
-    (angle_min, angle_max): (f32, f32),
-    (radius_min, radius_max): (f32, f32),
+    slice: &RadialSlice,
+    geometry: &SliceGeometry,
     is_active: bool,
+    style: &RadialStyle,
 ) {
-    const GAP: f32 = 3.0;
-
     let [x, y] = ui.io().display_size;
     let center = ImVec2 { x: x * 0.5, y: y * 0.5 };
 
-    let radius_mid = (radius_max + radius_min) * 0.5;
-    let gap1 = GAP / radius_max;
-    let gap2 = GAP / radius_min;
+    let SliceGeometry { angle_min, angle_max, radius_min, radius_max } = *geometry;
+    let gap1 = style.gap / radius_max;
+    let gap2 = style.gap / radius_min;
 
     let draw_lists = igGetForegroundDrawList();
 
-    let button_color = ImColor32::from(ui.style_color(if is_active {
-        StyleColor::ButtonActive
-    } else {
-        StyleColor::Button
-    }));
+    let button_color = match (slice.enabled, is_active) {
+        (false, _) => RadialStyle::color(ui, style.color_slice_disabled, StyleColor::FrameBg),
+        (true, true) => RadialStyle::color(ui, style.color_slice_active, StyleColor::ButtonActive),
+        (true, false) => RadialStyle::color(ui, style.color_slice, StyleColor::Button),
+    };
 
     ImDrawList_PathArcTo(draw_lists, center, radius_max, angle_min + gap1, angle_max - gap1, 0);
     ImDrawList_PathArcTo(draw_lists, center, radius_min, angle_max - gap2, angle_min + gap2, 0);
-    ImDrawList_PathFillConvex(draw_lists, button_color.to_bits());
+    ImDrawList_PathFillConvex(draw_lists, button_color);
 
-    let color = ImColor32::from(ui.style_color(StyleColor::Text)).to_bits();
-    let color2 = ImColor32::from(ui.style_color(StyleColor::WindowBg)).to_bits();
-    let text_start = txt.as_ptr();
-    let text_end = text_start.add(txt.len());
+    let color = if slice.enabled {
+        RadialStyle::color(ui, style.color_text, StyleColor::Text)
+    } else {
+        RadialStyle::color(ui, style.color_text_disabled, StyleColor::TextDisabled)
+    };
+    let color2 = RadialStyle::color(ui, style.color_text_shadow, StyleColor::WindowBg);
+    let text_start = slice.label.as_ptr();
+    let text_end = text_start.add(slice.label.len());
     let mut text_size = ImVec2 { x: 0.0, y: 0.0 };
     igCalcTextSize(&mut text_size, text_start as _, text_end as _, false, 0.0);
 
-    let text_pos = ImVec2 {
-        x: center.x + radius_mid * angle_base.cos() - text_size.x * 0.5,
-        y: center.y + radius_mid * angle_base.sin() - text_size.y * 0.5,
-    };
+    let mid = geometry.mid();
+    let mid = ImVec2 { x: center.x + mid.x, y: center.y + mid.y };
+
+    // The icon goes above the text, both centered on the slice.
+    let icon_size = if slice.icon.is_some() { style.icon_size } else { 0.0 };
+    let top = mid.y - (icon_size + text_size.y) * 0.5;
+
+    if let Some(icon) = slice.icon {
+        let tint = if slice.enabled { 0xffffffff } else { 0x80ffffff };
+        ImDrawList_AddImage(
+            draw_lists,
+            icon.id() as _,
+            ImVec2 { x: mid.x - icon_size * 0.5, y: top },
+            ImVec2 { x: mid.x + icon_size * 0.5, y: top + icon_size },
+            ImVec2 { x: 0.0, y: 0.0 },
+            ImVec2 { x: 1.0, y: 1.0 },
+            tint,
+        );
+    }
+
+    let text_pos = ImVec2 { x: mid.x - text_size.x * 0.5, y: top + icon_size };
 
     ImDrawList_AddText_Vec2(
         draw_lists,
@@ -61,6 +267,20 @@ unsafe fn draw_slice(
     ImDrawList_AddText_Vec2(draw_lists, text_pos, color, text_start as _, text_end as _);
 }
 
+fn draw_ring(
+    ui: &imgui::Ui,
+    slices: &[RadialSlice],
+    ring: &Ring,
+    depth: usize,
+    active: Option<usize>,
+    style: &RadialStyle,
+) {
+    for (index, slice) in slices.iter().enumerate() {
+        let geometry = ring.slice(index, style.radii(depth));
+        unsafe { draw_slice(ui, slice, &geometry, active == Some(index), style) };
+    }
+}
+
 pub fn radial_menu(
     ui: &imgui::Ui,
     elements: &[&str],
@@ -68,36 +288,535 @@ pub fn radial_menu(
     radius_min: f32,
     radius_max: f32,
 ) -> Option<usize> {
-    let mut selected = None;
-    let count = elements.len();
-    for (index, txt) in elements.iter().enumerate() {
-        let slice_angle = PI * 2.0 / (count as f32);
-        let angle_base = slice_angle * (index as f32) - PI * 0.5;
-        let angle_base = if angle_base < 0.0 { angle_base + 2.0 * PI } else { angle_base };
-        let angle_min = angle_base - slice_angle * 0.5;
-        let angle_max = angle_base + slice_angle * 0.5;
-
-        let angle_of_pos = f32::atan2(pos.y, pos.x);
-        let angle_of_pos = if angle_of_pos < 0. { angle_of_pos + 2.0 * PI } else { angle_of_pos };
-        let is_active = selected.is_none()
-            && !(pos.x == 0.0 && pos.y == 0.0)
-            && angle_min < angle_of_pos
-            && angle_max > angle_of_pos;
-
-        unsafe {
-            draw_slice(
-                ui,
-                txt,
-                angle_base,
-                (angle_min, angle_max),
-                (radius_min, radius_max),
-                is_active,
-            )
+    let slices: Vec<RadialSlice> = elements.iter().map(|&label| label.into()).collect();
+    radial_menu_with_style(ui, &slices, pos, &RadialStyle {
+        radius_min,
+        radius_max,
+        ..Default::default()
+    })
+}
+
+/// Draws a single ring of slices and returns the one `pos` points at, like
+/// [`radial_menu`], with the given style.
+pub fn radial_menu_with_style(
+    ui: &imgui::Ui,
+    slices: &[RadialSlice],
+    pos: ImVec2,
+    style: &RadialStyle,
+) -> Option<usize> {
+    let ring = Ring::full(slices.len(), style.start_angle);
+    let selected = ring.hit_test(pos, style.deadzone);
+    draw_ring(ui, slices, &ring, 0, selected, style);
+    selected
+}
+
+enum ItemKind {
+    Action(Box<dyn Widget>),
+    Submenu(Vec<RadialItem>),
+}
+
+/// A slice of a [`RadialMenu`]: a widget to activate, or a submenu.
+pub struct RadialItem {
+    label: String,
+    icon: Option<TextureId>,
+    kind: ItemKind,
+}
+
+impl RadialItem {
+    pub fn new(label: &str, widget: Box<dyn Widget>) -> Self {
+        Self { label: label.to_string(), icon: None, kind: ItemKind::Action(widget) }
+    }
+
+    /// A slice which opens a ring with `items` around itself when pointed at.
+    pub fn submenu(label: &str, items: Vec<RadialItem>) -> Self {
+        Self { label: label.to_string(), icon: None, kind: ItemKind::Submenu(items) }
+    }
+
+    pub fn with_icon(mut self, icon: TextureId) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Submenus are enabled if any of their items is.
+    fn is_enabled(&self) -> bool {
+        match &self.kind {
+            ItemKind::Action(widget) => widget.is_enabled(),
+            ItemKind::Submenu(items) => items.iter().any(RadialItem::is_enabled),
+        }
+    }
+
+    /// The items of the submenu that this item opens, if it is one and it is
+    /// enabled.
+    fn opens(&self) -> Option<&[RadialItem]> {
+        match &self.kind {
+            ItemKind::Submenu(items) if self.is_enabled() => Some(items),
+            _ => None,
+        }
+    }
+
+    fn slice(&self) -> RadialSlice<'_> {
+        RadialSlice { label: &self.label, icon: self.icon, enabled: self.is_enabled() }
+    }
+}
+
+fn for_each_widget(items: &mut [RadialItem], f: &mut dyn FnMut(&mut dyn Widget)) {
+    for item in items {
+        match &mut item.kind {
+            ItemKind::Action(widget) => f(widget.as_mut()),
+            ItemKind::Submenu(items) => for_each_widget(items, f),
+        }
+    }
+}
+
+/// The items and the layout of the ring opened by following `path` from the
+/// innermost ring.
+fn ring_at<'a>(
+    items: &'a [RadialItem],
+    path: &[usize],
+    start_angle: f32,
+) -> (&'a [RadialItem], Ring) {
+    let mut ring = Ring::full(items.len(), start_angle);
+    let mut items = items;
+
+    for &index in path {
+        let children = items[index].opens().unwrap_or_default();
+        ring = ring.submenu(index, children.len());
+        items = children;
+    }
+
+    (items, ring)
+}
+
+/// A quick menu which opens around the center of the screen while its binding
+/// is held.
+///
+/// The slice is chosen with the right stick, or by pointing the mouse away
+/// from the center of the screen. Releasing the binding runs the
+/// [`Widget::action`] of the chosen item, if any and if it is enabled.
+///
+/// Pointing at a submenu opens its ring around the menu, as an arc centered
+/// on it. The arc keeps the focus until the selection leaves it or goes back
+/// to the center, so that large menus stay readable.
+pub struct RadialMenu {
+    key_open: Option<Binding>,
+    items: Vec<RadialItem>,
+    style: RadialStyle,
+    open: bool,
+    /// The mouse position relative to the center of the screen.
+    mouse: ImVec2,
+    stick: Option<ImVec2>,
+    /// The open submenus, by index in their parent ring.
+    path: Vec<usize>,
+    selected: Option<usize>,
+}
+
+impl RadialMenu {
+    pub fn new(key_open: Option<Binding>, items: Vec<RadialItem>) -> Self {
+        Self {
+            key_open,
+            items,
+            style: RadialStyle::default(),
+            open: false,
+            mouse: ImVec2::zero(),
+            stick: None,
+            path: Vec::new(),
+            selected: None,
+        }
+    }
+
+    /// Sets the style, with lengths before scaling.
+    pub fn with_style(mut self, style: RadialStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// The right stick's direction in screen space, outside of the deadzone.
+    fn stick(input: &dyn InputSource) -> Option<ImVec2> {
+        let gamepad = match input.controller_state() {
+            Some(state) => state.Gamepad,
+            None => gamepad_from_keys(input),
         };
+        let (x, y) = (f32::from(gamepad.sThumbRX), -f32::from(gamepad.sThumbRY));
+        let deadzone = f32::from(stick_deadzone());
+
+        (x.hypot(y) > deadzone).then_some(ImVec2 { x, y })
+    }
 
-        if is_active {
-            selected = Some(index);
+    fn render_menu(&mut self, ui: &imgui::Ui) {
+        if !self.open {
+            return;
+        }
+
+        // SAFETY: only reads the current context.
+        self.mouse = if unsafe { igIsMousePosValid(std::ptr::null()) } {
+            let [x, y] = ui.io().mouse_pos;
+            let [width, height] = ui.io().display_size;
+            ImVec2 { x: x - width * 0.5, y: y - height * 0.5 }
+        } else {
+            ImVec2::zero()
+        };
+
+        let style = self.style.scaled(scaling_factor(ui));
+        for depth in 0..=self.path.len() {
+            let (items, ring) = ring_at(&self.items, &self.path[..depth], style.start_angle);
+            let active = self.path.get(depth).copied().or(self.selected);
+            let slices: Vec<RadialSlice> = items.iter().map(RadialItem::slice).collect();
+            draw_ring(ui, &slices, &ring, depth, active, &style);
         }
     }
-    selected
+
+    /// The selection vector: the stick wins over the mouse while it is
+    /// pushed, and the mouse selects nothing near the center.
+    fn pos(&self) -> ImVec2 {
+        match self.stick {
+            Some(stick) => stick,
+            None if self.mouse.x.hypot(self.mouse.y) > MOUSE_DEADZONE => self.mouse,
+            None => ImVec2::zero(),
+        }
+    }
+
+    /// Closes the submenus that the selection left, then opens the ones it
+    /// points at.
+    fn update_selection(&mut self) {
+        let pos = self.pos();
+        let start_angle = self.style.start_angle;
+
+        if pos.x == 0.0 && pos.y == 0.0 {
+            self.path.clear();
+        }
+
+        while !self.path.is_empty()
+            && ring_at(&self.items, &self.path, start_angle).1.hit_test(pos, 0.).is_none()
+        {
+            self.path.pop();
+        }
+
+        loop {
+            let (items, ring) = ring_at(&self.items, &self.path, start_angle);
+            match ring.hit_test(pos, 0.) {
+                Some(index) if items[index].opens().is_some() => self.path.push(index),
+                selected => {
+                    self.selected = selected;
+                    break;
+                },
+            }
+        }
+    }
+
+    /// Runs the action of the selected item, and closes the menu.
+    fn confirm(&mut self) {
+        let selected = self.selected.take();
+        let mut items = &mut self.items[..];
+        for index in self.path.drain(..) {
+            match &mut items[index].kind {
+                ItemKind::Submenu(children) => items = children,
+                ItemKind::Action(_) => return,
+            }
+        }
+
+        if let Some(item) = selected.and_then(|index| items.get_mut(index)) {
+            match &mut item.kind {
+                ItemKind::Action(widget) if widget.is_enabled() => widget.action(),
+                _ => {},
+            }
+        }
+    }
+}
+
+const MOUSE_DEADZONE: f32 = 20.;
+
+impl Widget for RadialMenu {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.render_menu(ui);
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.render_menu(ui);
+    }
+
+    fn interact(&mut self, input: &dyn InputSource) {
+        let pressed = self.key_open.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false);
+        let held = self.key_open.as_ref().map(Binding::is_held).unwrap_or(false);
+
+        if pressed && !self.open {
+            self.open = true;
+            self.mouse = ImVec2::zero();
+        }
+
+        if self.open {
+            self.stick = Self::stick(input);
+            self.update_selection();
+
+            if !held {
+                self.open = false;
+                self.confirm();
+            }
+        }
+
+        for_each_widget(&mut self.items, &mut |widget| widget.interact(input));
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
+        for_each_widget(&mut self.items, &mut |widget| widget.log(tx.clone()));
+    }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        for_each_widget(&mut self.items, &mut |widget| widget.hotkeys(visit));
+
+        visit(HotkeySlot {
+            name: "Quick menu/Open",
+            scope: Scope::Global,
+            binding: &mut self.key_open,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use windows::Win32::UI::Input::XboxController::{XINPUT_GAMEPAD, XINPUT_STATE};
+
+    use super::*;
+    use crate::key::ScriptedInput;
+
+    struct Counter(Arc<AtomicUsize>);
+
+    impl Widget for Counter {
+        fn render(&mut self, _: &imgui::Ui) {}
+
+        fn action(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn right_stick(x: i16, y: i16) -> Option<XINPUT_STATE> {
+        Some(XINPUT_STATE {
+            dwPacketNumber: 0,
+            Gamepad: XINPUT_GAMEPAD { sThumbRX: x, sThumbRY: y, ..Default::default() },
+        })
+    }
+
+    fn pos(angle: f32) -> ImVec2 {
+        ImVec2 { x: angle.cos(), y: angle.sin() }
+    }
+
+    #[test]
+    fn test_hit_test() {
+        let ring = Ring::full(4, -PI * 0.5);
+        assert_eq!(ring.hit_test(ImVec2::zero(), 0.), None);
+        assert_eq!(ring.hit_test(pos(-PI * 0.5), 0.), Some(0));
+        assert_eq!(ring.hit_test(pos(0.), 0.), Some(1));
+        assert_eq!(ring.hit_test(pos(PI * 0.5), 0.), Some(2));
+        assert_eq!(ring.hit_test(pos(PI), 0.), Some(3));
+
+        // The slice at the right straddles the 0/2π seam.
+        assert_eq!(ring.hit_test(pos(-0.01), 0.), Some(1));
+        assert_eq!(ring.hit_test(pos(2. * PI - 0.01), 0.), Some(1));
+        assert_eq!(ring.hit_test(pos(0.01), 0.), Some(1));
+
+        // So does the first slice when starting from the right.
+        let ring = Ring::full(3, 0.);
+        assert_eq!(ring.hit_test(pos(-0.01), 0.), Some(0));
+        assert_eq!(ring.hit_test(pos(0.01), 0.), Some(0));
+        assert_eq!(ring.hit_test(pos(-PI / 3. + 0.01), 0.), Some(0));
+        assert_eq!(ring.hit_test(pos(-PI / 3. - 0.01), 0.), Some(2));
+
+        // A submenu keeps the width of the slices, centered on its parent.
+        let ring = Ring::full(4, -PI * 0.5);
+        let submenu = ring.submenu(1, 3);
+        assert_eq!(submenu.hit_test(pos(-PI * 0.5), 0.), Some(0));
+        assert_eq!(submenu.hit_test(pos(0.), 0.), Some(1));
+        assert_eq!(submenu.hit_test(pos(PI * 0.5), 0.), Some(2));
+        assert_eq!(submenu.hit_test(pos(PI), 0.), None);
+
+        // Unless they don't fit around the circle.
+        let submenu = ring.submenu(1, 8);
+        assert_eq!(submenu.hit_test(pos(0.1), 0.), Some(4));
+        assert_eq!(submenu.hit_test(pos(PI + 0.1), 0.), Some(0));
+        assert_eq!(submenu.hit_test(pos(PI - 0.1), 0.), Some(7));
+    }
+
+    #[test]
+    fn test_hit_test_full_circle() {
+        for count in 1..=16 {
+            for start in [-PI * 0.5, 0., 1., PI, 2. * PI] {
+                let ring = Ring::full(count, start);
+                for step in 0..720 {
+                    let angle = step as f32 * PI / 360.;
+                    let index = ring.hit_test(pos(angle), 0.).unwrap();
+                    let (angle_min, angle_max) = ring.angles(index);
+                    let offset = (angle - angle_min).rem_euclid(2. * PI);
+                    // Angles on the boundaries can round to either side.
+                    assert!(
+                        offset <= angle_max - angle_min + 1e-4 || offset >= 2. * PI - 1e-4,
+                        "{count} slices from {start}, at {angle}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deadzone() {
+        let ring = Ring::full(4, -PI * 0.5);
+        assert_eq!(ring.hit_test(ImVec2 { x: 0., y: -10. }, 20.), None);
+        assert_eq!(ring.hit_test(ImVec2 { x: 0., y: -20. }, 20.), None);
+        assert_eq!(ring.hit_test(ImVec2 { x: 0., y: -21. }, 20.), Some(0));
+        assert_eq!(ring.hit_test(ImVec2 { x: 20., y: 10. }, 20.), Some(1));
+        assert_eq!(ring.hit_test(ImVec2 { x: 1e-6, y: 0. }, 0.), Some(1));
+        assert_eq!(Ring::full(0, 0.).hit_test(pos(0.), 0.), None);
+    }
+
+    #[test]
+    fn test_geometry() {
+        let ring = Ring::full(4, -PI * 0.5);
+        let style = RadialStyle::default();
+        let slice = ring.slice(1, style.radii(0));
+        assert!((slice.angle_min + PI * 0.25).abs() < 1e-6);
+        assert!((slice.angle_max - PI * 0.25).abs() < 1e-6);
+        assert_eq!((slice.radius_min, slice.radius_max), (80., 160.));
+        let mid = slice.mid();
+        assert!((mid.x - 120.).abs() < 1e-4 && mid.y.abs() < 1e-4);
+
+        assert_eq!(style.radii(1), (166., 246.));
+        assert_eq!(style.scaled(0.5).radii(1), (83., 123.));
+    }
+
+    #[test]
+    fn test_interact() {
+        let counters: Vec<_> = (0..4).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let count = |i: usize| counters[i].load(Ordering::Relaxed);
+        let items = ["Up", "Right", "Down", "Left"]
+            .into_iter()
+            .zip(&counters)
+            .map(|(label, c)| RadialItem::new(label, Box::new(Counter(c.clone()))))
+            .collect();
+
+        let mut menu = RadialMenu::new("f5 | pad:rb".parse().ok(), items);
+        let mut input = ScriptedInput::new();
+        let mut frame = |input: &mut ScriptedInput| {
+            menu.interact(input);
+            input.next_frame(Duration::from_millis(16));
+        };
+
+        // Pushing the stick alone doesn't open the menu.
+        input.set_controller_state(right_stick(0, 30000));
+        frame(&mut input);
+        assert_eq!((0..4).map(count).collect::<Vec<_>>(), [0, 0, 0, 0]);
+
+        input.press(imgui::Key::F5);
+        frame(&mut input);
+        input.set_controller_state(right_stick(30000, 0));
+        frame(&mut input);
+        input.release_all();
+        frame(&mut input);
+        assert_eq!((0..4).map(count).collect::<Vec<_>>(), [0, 1, 0, 0]);
+
+        // Releasing with the stick in the deadzone cancels.
+        input.press(imgui::Key::F5);
+        frame(&mut input);
+        input.set_controller_state(right_stick(1000, 0));
+        frame(&mut input);
+        input.release_all();
+        frame(&mut input);
+        assert_eq!((0..4).map(count).collect::<Vec<_>>(), [0, 1, 0, 0]);
+
+        input.press(imgui::Key::F5);
+        input.set_controller_state(right_stick(-30000, 0));
+        frame(&mut input);
+        input.release_all();
+        frame(&mut input);
+        assert_eq!((0..4).map(count).collect::<Vec<_>>(), [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_stick_keys() {
+        let counters: Vec<_> = (0..4).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let count = |i: usize| counters[i].load(Ordering::Relaxed);
+        let items = counters
+            .iter()
+            .enumerate()
+            .map(|(i, c)| RadialItem::new(&format!("{i}"), Box::new(Counter(c.clone()))))
+            .collect();
+
+        // A backend reporting the right stick through imgui's gamepad keys.
+        let mut menu = RadialMenu::new("f5".parse().ok(), items);
+        let mut input = ScriptedInput::new();
+        input.press(imgui::Key::F5);
+        menu.interact(&input);
+        input.next_frame(Duration::from_millis(16));
+        input.set_analog(imgui::Key::GamepadRStickLeft, 1.);
+        menu.interact(&input);
+        input.next_frame(Duration::from_millis(16));
+        input.release_all();
+        input.set_analog(imgui::Key::GamepadRStickLeft, 1.);
+        menu.interact(&input);
+        assert_eq!((0..4).map(count).collect::<Vec<_>>(), [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_submenus() {
+        struct Disabled;
+
+        impl Widget for Disabled {
+            fn render(&mut self, _: &imgui::Ui) {}
+
+            fn is_enabled(&self) -> bool {
+                false
+            }
+
+            fn action(&mut self) {
+                panic!("disabled items can't be activated");
+            }
+        }
+
+        let counters: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let count = |i: usize| counters[i].load(Ordering::Relaxed);
+        let item =
+            |i: usize| RadialItem::new(&format!("{i}"), Box::new(Counter(counters[i].clone())));
+
+        let mut menu = RadialMenu::new("f5".parse().ok(), vec![
+            item(0),
+            RadialItem::submenu("More", vec![
+                item(1),
+                item(2),
+                RadialItem::new("x", Box::new(Disabled)),
+            ]),
+            RadialItem::submenu("Nothing", vec![RadialItem::new("y", Box::new(Disabled))]),
+            RadialItem::new("z", Box::new(Disabled)),
+        ]);
+        let mut input = ScriptedInput::new();
+        // Sweeps the stick through the given positions while holding the
+        // binding, then releases it.
+        let mut select = |sweep: &[(i16, i16)]| {
+            input.press(imgui::Key::F5);
+            for &(x, y) in sweep {
+                input.set_controller_state(right_stick(x, y));
+                menu.interact(&input);
+                input.next_frame(Duration::from_millis(16));
+            }
+            let path = (menu.path.clone(), menu.selected);
+            input.release_all();
+            menu.interact(&input);
+            input.next_frame(Duration::from_millis(16));
+            path
+        };
+
+        // Pointing at the submenu selects the middle of its arc.
+        assert_eq!(select(&[(30000, 0)]), (vec![1], Some(1)));
+        assert_eq!((count(0), count(1), count(2)), (0, 0, 1));
+        // The arc keeps the focus over the neighbouring slices.
+        assert_eq!(select(&[(30000, 0), (10000, 30000)]), (vec![1], Some(0)));
+        assert_eq!((count(0), count(1), count(2)), (0, 1, 1));
+        assert_eq!(select(&[(30000, 0), (-30000, 1000)]), (vec![], Some(3)));
+        assert_eq!(select(&[(30000, 0), (0, 0), (10000, 30000)]), (vec![], Some(0)));
+        assert_eq!((count(0), count(1), count(2)), (1, 1, 1));
+        // Disabled items and submenus don't do anything.
+        assert_eq!(select(&[(1000, -30000)]), (vec![], Some(2)));
+        assert_eq!(select(&[(30000, 0), (0, -30000), (-1000, -30000)]), (vec![1], Some(2)));
+        assert_eq!((count(0), count(1), count(2)), (1, 1, 1));
+    }
 }
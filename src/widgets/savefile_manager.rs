@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -11,16 +12,24 @@ use imgui::sys::{
 };
 use imgui::{Condition, TreeNodeFlags, Ui};
 
-use crate::key::Key;
-use crate::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
 
 const SFM_TAG: &str = "##savefile-manager";
 const SFML_TAG: &str = "##savefile-manager-list";
+const SOURCE: &str = "Savefile manager";
 
 pub struct SavefileManager(Box<dyn Widget>);
 
 impl SavefileManager {
-    pub fn new(key_load: Option<Key>, key_close: Option<Key>, savefile_path: PathBuf) -> Self {
+    pub fn new(
+        key_load: Option<Binding>,
+        key_close: Option<Binding>,
+        savefile_path: PathBuf,
+    ) -> Self {
         match SavefileManagerInner::new(key_load, key_close, savefile_path) {
             Ok(savefile_manager) => SavefileManager(Box::new(savefile_manager)),
             Err(e) => SavefileManager(Box::new(ErroredSavefileManager(e))),
@@ -37,8 +46,8 @@ impl Widget for SavefileManager {
         self.0.render_closed(ui)
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        self.0.interact(ui)
+    fn interact(&mut self, input: &dyn InputSource) {
+        self.0.interact(input)
     }
 
     fn action(&mut self) {
@@ -61,9 +70,17 @@ impl Widget for SavefileManager {
         self.0.want_exit()
     }
 
-    fn log(&mut self, tx: Sender<String>) {
+    fn is_entered(&mut self) -> bool {
+        self.0.is_entered()
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
         self.0.log(tx)
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        self.0.hotkeys(visit)
+    }
 }
 
 #[derive(Debug)]
@@ -79,32 +96,41 @@ impl Widget for ErroredSavefileManager {
 struct SavefileManagerInner {
     label_load: String,
     label_close: String,
-    key_load: Option<Key>,
-    key_close: Option<Key>,
+    key_load: Option<Binding>,
+    key_close: Option<Binding>,
     file_tree: FileTree,
     savefile_path: PathBuf,
     current_file: Option<PathBuf>,
     breadcrumbs: String,
     savefile_name: String,
     input_edited: bool,
-    logs: Vec<String>,
+    nav: TreeNav,
+    logs: Vec<LogEvent>,
+}
+
+/// Navigation state of the file tree while the manager is entered from a
+/// controller. The focus is the selected file.
+#[derive(Debug, Default)]
+struct TreeNav {
+    entered: bool,
+    request_open: bool,
+    request_close: bool,
+    /// The nodes rendered on the last frame, in order.
+    visible: Vec<PathBuf>,
+    /// The directories open on the last frame.
+    open: HashSet<PathBuf>,
+    toggle: Option<PathBuf>,
+    scroll: bool,
 }
 
 impl SavefileManagerInner {
     fn new(
-        key_load: Option<Key>,
-        key_close: Option<Key>,
+        key_load: Option<Binding>,
+        key_close: Option<Binding>,
         savefile_path: PathBuf,
     ) -> Result<Self, String> {
-        let label_load = match key_load {
-            Some(key_load) => format!("Load savefile ({key_load})"),
-            None => "Load savefile".to_string(),
-        };
-
-        let label_close = match key_close {
-            Some(key_close) => format!("Close ({key_close})"),
-            None => "Close".to_string(),
-        };
+        let label_load = label_with_hotkey("Load savefile", key_load.as_ref());
+        let label_close = label_with_hotkey("Close", key_close.as_ref());
 
         let Some(savefile_path_parent) = savefile_path.parent() else {
             return Err(format!(
@@ -126,39 +152,84 @@ impl SavefileManagerInner {
             savefile_name: String::new(),
             breadcrumbs: "/".to_string(),
             input_edited: false,
+            nav: TreeNav::default(),
             logs: Vec::new(),
         })
     }
 
+    fn update_breadcrumbs(&mut self) {
+        let root_path = self.file_tree.path();
+        let child_path = self
+            .current_file
+            .as_ref()
+            .and_then(|f| if f.is_dir() { Some(f.as_path()) } else { f.parent() })
+            .and_then(|path| path.strip_prefix(root_path).ok());
+
+        self.breadcrumbs.clear();
+
+        if let Some(path) = child_path {
+            write!(self.breadcrumbs, "/{}", path.to_string_lossy()).ok();
+        } else {
+            write!(self.breadcrumbs, "/").ok();
+        }
+    }
+
+    /// Selects the node `steps` rows away from the selected one.
+    fn move_selection(&mut self, steps: isize) {
+        let visible = &self.nav.visible;
+        if visible.is_empty() {
+            return;
+        }
+
+        let index =
+            match self.current_file.as_ref().and_then(|f| visible.iter().position(|p| p == f)) {
+                Some(index) => (index as isize + steps).rem_euclid(visible.len() as isize) as usize,
+                None => 0,
+            };
+
+        self.current_file = Some(visible[index].clone());
+        self.nav.scroll = true;
+        self.update_breadcrumbs();
+    }
+
     fn load_savefile(&mut self) {
         let Some(src_path) = self.current_file.as_ref() else {
-            self.logs.push("No current path! Can't load savefile.".to_string());
+            self.logs.push(LogEvent::warning(SOURCE, "No current path! Can't load savefile."));
             return;
         };
 
         if !src_path.is_file() {
-            self.logs.push("Can't load a directory -- please choose a file.".to_string());
+            self.logs
+                .push(LogEvent::warning(SOURCE, "Can't load a directory -- please choose a file."));
             return;
         }
 
         match load_savefile(src_path, &self.savefile_path) {
-            Ok(()) => self.logs.push(format!(
-                "Loaded {}/{}",
-                if self.breadcrumbs == "/" { "" } else { &self.breadcrumbs },
-                src_path.file_name().unwrap().to_str().unwrap()
-            )),
-            Err(e) => self.logs.push(format!("Error loading savefile: {}", e)),
+            Ok(()) => {
+                let message = format!(
+                    "Loaded {}/{}",
+                    if self.breadcrumbs == "/" { "" } else { &self.breadcrumbs },
+                    src_path.file_name().unwrap().to_str().unwrap()
+                );
+                self.logs
+                    .push(LogEvent::info(SOURCE, message).with_payload("path", src_path.display()))
+            },
+            Err(e) => self.logs.push(
+                LogEvent::error(SOURCE, format!("Error loading savefile: {}", e))
+                    .with_payload("path", src_path.display()),
+            ),
         };
     }
 
     fn import_savefile(&mut self) {
         if self.savefile_name.is_empty() {
-            self.logs.push(String::from("Cannot save to empty filename"));
+            self.logs.push(LogEvent::warning(SOURCE, "Cannot save to empty filename"));
             return;
         }
 
         if self.savefile_name.contains('/') || self.savefile_name.contains('\\') {
-            self.logs.push(String::from("Savefile name cannot contain path separator"));
+            self.logs
+                .push(LogEvent::warning(SOURCE, "Savefile name cannot contain path separator"));
             return;
         }
 
@@ -179,15 +250,21 @@ impl SavefileManagerInner {
             Ok(()) => {
                 self.savefile_name.clear();
                 if let Err(e) = self.file_tree.refresh() {
-                    self.logs.push(format!("Couldn't refresh file tree: {e}"));
+                    self.logs
+                        .push(LogEvent::error(SOURCE, format!("Couldn't refresh file tree: {e}")));
                 }
-                self.logs.push(format!(
+                let message = format!(
                     "Imported {}/{}",
                     if self.breadcrumbs == "/" { "" } else { &self.breadcrumbs },
                     dst_path.file_name().unwrap().to_str().unwrap()
-                ))
+                );
+                self.logs
+                    .push(LogEvent::info(SOURCE, message).with_payload("path", dst_path.display()))
             },
-            Err(e) => self.logs.push(format!("Error importing savefile: {}", e)),
+            Err(e) => self.logs.push(
+                LogEvent::error(SOURCE, format!("Error importing savefile: {}", e))
+                    .with_payload("path", dst_path.display()),
+            ),
         };
     }
 }
@@ -203,10 +280,12 @@ impl Widget for SavefileManagerInner {
             (igGetCursorPosX() + wnd_pos.x, igGetCursorPosY() + wnd_pos.y)
         };
 
-        if ui.button_with_size(&self.label_load, [button_width, BUTTON_HEIGHT]) {
+        if ui.button_with_size(&self.label_load, [button_width, BUTTON_HEIGHT])
+            || std::mem::take(&mut self.nav.request_open)
+        {
             ui.open_popup(SFM_TAG);
             if let Err(e) = self.file_tree.refresh() {
-                self.logs.push(format!("Couldn't refresh file tree: {e}"));
+                self.logs.push(LogEvent::error(SOURCE, format!("Couldn't refresh file tree: {e}")));
             }
         }
 
@@ -234,22 +313,12 @@ impl Widget for SavefileManagerInner {
                 });
 
             ui.child_window(SFML_TAG).size([button_width, 200. * scale]).build(|| {
-                if self.file_tree.render(ui, &mut self.current_file, true) {
-                    let root_path = self.file_tree.path();
-                    let child_path = self
-                        .current_file
-                        .as_ref()
-                        .and_then(|f| if f.is_dir() { Some(f.as_path()) } else { f.parent() })
-                        .and_then(|path| path.strip_prefix(root_path).ok());
-
-                    self.breadcrumbs.clear();
-
-                    if let Some(path) = child_path {
-                        write!(self.breadcrumbs, "/{}", path.to_string_lossy()).ok();
-                    } else {
-                        write!(self.breadcrumbs, "/").ok();
-                    }
+                self.nav.visible.clear();
+                if self.file_tree.render(ui, &mut self.current_file, true, &mut self.nav) {
+                    self.update_breadcrumbs();
                 }
+                self.nav.toggle = None;
+                self.nav.scroll = false;
             });
 
             if ui.button_with_size(&self.label_load, [button_width, BUTTON_HEIGHT]) {
@@ -288,37 +357,91 @@ impl Widget for SavefileManagerInner {
                     .arg(path.as_os_str())
                     .spawn()
                 {
-                    self.logs.push(format!("Couldn't show folder: {}", e));
+                    self.logs.push(LogEvent::error(SOURCE, format!("Couldn't show folder: {}", e)));
                 };
             }
 
             if ui.button_with_size(&self.label_close, [button_width, BUTTON_HEIGHT])
                 || (!ui.is_any_item_active()
-                    && self.key_close.map(|k| k.is_pressed(ui)).unwrap_or(false))
+                    && self.key_close.as_mut().map(|k| k.is_pressed(ui)).unwrap_or(false))
+                || std::mem::take(&mut self.nav.request_close)
             {
                 ui.close_current_popup();
+                self.nav.entered = false;
                 if let Err(e) = self.file_tree.refresh() {
-                    self.logs.push(format!("Couldn't refresh file tree: {e}"));
+                    self.logs
+                        .push(LogEvent::error(SOURCE, format!("Couldn't refresh file tree: {e}")));
                 }
             }
+        } else {
+            self.nav.request_close = false;
         }
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        if self.key_load.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.key_load.as_mut().map(|k| k.is_pressed(input)).unwrap_or(false) {
             self.load_savefile();
         }
     }
 
+    /// Loads the selected savefile, or opens or closes the selected directory
+    /// once entered.
     fn action(&mut self) {
-        self.load_savefile();
+        match &self.current_file {
+            Some(path) if self.nav.entered && path.is_dir() => self.nav.toggle = Some(path.clone()),
+            _ => self.load_savefile(),
+        }
+    }
+
+    fn cursor_down(&mut self) {
+        if self.nav.entered {
+            self.move_selection(1);
+        }
+    }
+
+    fn cursor_up(&mut self) {
+        if self.nav.entered {
+            self.move_selection(-1);
+        }
+    }
+
+    fn want_enter(&mut self) -> bool {
+        self.nav.entered = true;
+        self.nav.request_open = true;
+        true
     }
 
-    fn log(&mut self, tx: Sender<String>) {
+    fn want_exit(&mut self) -> bool {
+        self.nav.entered = false;
+        self.nav.request_close = true;
+        true
+    }
+
+    fn is_entered(&mut self) -> bool {
+        self.nav.entered
+    }
+
+    fn log(&mut self, tx: Sender<LogEvent>) {
         for log in self.logs.drain(..) {
             tx.send(log).ok();
         }
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot {
+            name: "Load savefile",
+            scope: Scope::Global,
+            binding: &mut self.key_load,
+        });
+        visit(HotkeySlot {
+            name: "Close savefile manager",
+            scope: Scope::Popup,
+            binding: &mut self.key_close,
+        });
+
+        self.label_load = label_with_hotkey("Load savefile", self.key_load.as_ref());
+        self.label_close = label_with_hotkey("Close", self.key_close.as_ref());
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -367,10 +490,18 @@ impl FileTree {
         }
     }
 
-    fn render(&self, ui: &Ui, current_file: &mut Option<PathBuf>, is_top: bool) -> bool {
+    fn render(
+        &self,
+        ui: &Ui,
+        current_file: &mut Option<PathBuf>,
+        is_top: bool,
+        nav: &mut TreeNav,
+    ) -> bool {
+        let is_current = current_file.as_ref().map(|f| f == self.path()).unwrap_or(false);
+        nav.visible.push(self.path().to_path_buf());
+
         match self {
             FileTree::File { path } => {
-                let is_current = current_file.as_ref().map(|f| f == path).unwrap_or(false);
                 let file_name = self.file_name();
 
                 unsafe { igUnindent(igGetTreeNodeToLabelSpacing()) };
@@ -387,6 +518,10 @@ impl FileTree {
 
                 unsafe { igIndent(igGetTreeNodeToLabelSpacing()) };
 
+                if is_current && nav.scroll {
+                    ui.set_scroll_here_y_with_ratio(0.5);
+                }
+
                 if ui.is_item_clicked() {
                     *current_file = Some(path.clone());
                     true
@@ -395,25 +530,39 @@ impl FileTree {
                 }
             },
             FileTree::Directory { children, path } => {
-                let is_current = current_file.as_ref().map(|f| f == path).unwrap_or(false);
                 let file_name = self.file_name();
                 let mut update_breadcrumbs = false;
 
-                let node = ui
+                let mut node = ui
                     .tree_node_config(file_name)
                     .default_open(is_top)
                     .label::<&str, &str>(file_name)
                     .flags(TreeNodeFlags::SPAN_AVAIL_WIDTH)
-                    .selected(is_current)
-                    .build(|| {
-                        if ui.is_item_clicked() {
-                            *current_file = Some(path.clone());
-                        }
-
-                        for node in children {
-                            update_breadcrumbs |= node.render(ui, current_file, false);
-                        }
-                    });
+                    .selected(is_current);
+
+                if nav.toggle.as_ref() == Some(path) {
+                    node = node.opened(!nav.open.contains(path), Condition::Always);
+                }
+
+                let node = node.build(|| {
+                    if is_current && nav.scroll {
+                        ui.set_scroll_here_y_with_ratio(0.5);
+                    }
+
+                    if ui.is_item_clicked() {
+                        *current_file = Some(path.clone());
+                    }
+
+                    for node in children {
+                        update_breadcrumbs |= node.render(ui, current_file, false, nav);
+                    }
+                });
+
+                if node.is_some() {
+                    nav.open.insert(path.clone());
+                } else {
+                    nav.open.remove(path);
+                }
 
                 if node.is_none() && ui.is_item_clicked() {
                     *current_file = Some(path.clone());
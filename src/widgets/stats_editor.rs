@@ -1,8 +1,10 @@
 use imgui::sys::{igGetCursorPosX, igGetCursorPosY, igGetWindowPos, igSetNextWindowPos, ImVec2};
 use imgui::{Condition, WindowFlags};
 
-use crate::key::Key;
-use crate::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::binding::Binding;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::navigator::highlight;
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
 
 const STAT_EDIT_TAG: &str = "##stats_editor";
 
@@ -29,6 +31,26 @@ impl<'a> Datum<'a> {
     pub fn separator() -> Self {
         Datum::Separator
     }
+
+    fn is_editable(&self) -> bool {
+        !matches!(self, Datum::Separator)
+    }
+
+    /// Adds `steps` to the value, within its bounds.
+    fn step(self, steps: i8) {
+        match self {
+            Datum::Int { value, min, max, .. } => {
+                *value = value.saturating_add(steps.into()).clamp(min, max)
+            },
+            Datum::Float { value, min, max, .. } => {
+                *value = (*value + f32::from(steps)).clamp(min, max)
+            },
+            Datum::Byte { value, min, max, .. } => {
+                *value = value.saturating_add(steps).clamp(min, max)
+            },
+            Datum::Separator => {},
+        }
+    }
 }
 
 pub trait Stats: Send + Sync + 'static {
@@ -40,25 +62,50 @@ pub trait Stats: Send + Sync + 'static {
 
 pub struct StatsEditor<S: Stats> {
     stats: S,
-    key_open: Option<Key>,
+    key_open: Option<Binding>,
     label_open: String,
-    key_close: Option<Key>,
+    key_close: Option<Binding>,
     label_close: String,
+    nav: Option<StatsNav>,
+}
+
+/// Navigation state while the editor is entered from a controller. The
+/// cursor goes through the editable data, then the apply button.
+#[derive(Debug, Default)]
+struct StatsNav {
+    cursor: usize,
+    editing: bool,
 }
 
 impl<S: Stats> StatsEditor<S> {
-    pub fn new(stats: S, key_open: Option<Key>, key_close: Option<Key>) -> Self {
-        let label_open = match key_open {
-            Some(key_open) => format!("Edit stats ({key_open})"),
-            None => "Edit stats".to_string(),
-        };
+    pub fn new(stats: S, key_open: Option<Binding>, key_close: Option<Binding>) -> Self {
+        let label_open = label_with_hotkey("Edit stats", key_open.as_ref());
+        let label_close = label_with_hotkey("Close", key_close.as_ref());
 
-        let label_close = match key_close {
-            Some(key_close) => format!("Close ({key_close})"),
-            None => "Close".to_string(),
+        Self { stats, key_close, label_close, key_open, label_open, nav: None }
+    }
+
+    /// Number of rows the cursor goes through.
+    fn nav_rows(&mut self) -> usize {
+        self.stats.data().map(|data| data.filter(Datum::is_editable).count()).unwrap_or(0) + 1
+    }
+
+    fn move_cursor(&mut self, steps: i8) {
+        let rows = self.nav_rows();
+        let Some(nav) = self.nav.as_mut() else {
+            return;
         };
 
-        Self { stats, key_close, label_close, key_open, label_open }
+        if nav.editing {
+            let cursor = nav.cursor;
+            if let Some(datum) =
+                self.stats.data().and_then(|data| data.filter(Datum::is_editable).nth(cursor))
+            {
+                datum.step(steps);
+            }
+        } else {
+            nav.cursor = (nav.cursor as isize - steps as isize).rem_euclid(rows as isize) as usize;
+        }
     }
 }
 
@@ -75,7 +122,7 @@ impl<S: Stats> Widget for StatsEditor<S> {
         };
 
         if ui.button_with_size(&self.label_open, [button_width, button_height])
-            || (self.key_open.map(|k| k.is_pressed(ui)).unwrap_or(false)
+            || (self.key_open.as_mut().map(|k| k.is_pressed(ui)).unwrap_or(false)
                 && !ui.is_any_item_active())
         {
             self.stats.read();
@@ -107,7 +154,14 @@ impl<S: Stats> Widget for StatsEditor<S> {
         {
             let _tok = ui.push_item_width(150.);
 
+            let nav = self.nav.as_ref();
+            let mut row = 0;
+
             for datum in data {
+                let focused = nav.is_some_and(|nav| datum.is_editable() && nav.cursor == row);
+                let editing = nav.is_some_and(|nav| nav.editing);
+                row += usize::from(datum.is_editable());
+
                 match datum {
                     Datum::Int { label, value, min, max } => {
                         if ui.input_int(label, value).build() {
@@ -126,19 +180,87 @@ impl<S: Stats> Widget for StatsEditor<S> {
                     },
                     Datum::Separator => ui.separator(),
                 }
+
+                if focused {
+                    highlight(ui, editing);
+                }
             }
 
             if ui.button_with_size("Apply", [button_width, button_height]) {
                 self.stats.write();
             }
+            if nav.is_some_and(|nav| nav.cursor == row) {
+                highlight(ui, false);
+            }
 
             if ui.button_with_size(&self.label_close, [button_width, button_height])
-                || (self.key_close.map(|k| k.is_pressed(ui)).unwrap_or(false)
+                || (self.key_close.as_mut().map(|k| k.is_pressed(ui)).unwrap_or(false)
                     && !ui.is_any_item_active())
             {
                 ui.close_current_popup();
                 self.stats.clear();
+                self.nav = None;
             }
         }
     }
+
+    fn action(&mut self) {
+        let rows = self.nav_rows();
+        match self.nav.as_mut() {
+            Some(nav) if nav.cursor == rows - 1 => self.stats.write(),
+            Some(nav) => nav.editing = !nav.editing,
+            None => {},
+        }
+    }
+
+    /// Moves to the previous row, or increments the value being edited.
+    fn cursor_up(&mut self) {
+        self.move_cursor(1);
+    }
+
+    /// Moves to the next row, or decrements the value being edited.
+    fn cursor_down(&mut self) {
+        self.move_cursor(-1);
+    }
+
+    /// Enters the editor if the stats could be read.
+    fn want_enter(&mut self) -> bool {
+        self.stats.read();
+        if self.stats.data().is_none() {
+            return false;
+        }
+
+        self.nav = Some(StatsNav::default());
+        true
+    }
+
+    fn want_exit(&mut self) -> bool {
+        match self.nav.as_mut() {
+            Some(nav) if nav.editing && self.stats.data().is_some() => {
+                nav.editing = false;
+                false
+            },
+            _ => {
+                self.stats.clear();
+                self.nav = None;
+                true
+            },
+        }
+    }
+
+    fn is_entered(&mut self) -> bool {
+        self.nav.is_some() && self.stats.data().is_some()
+    }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot { name: "Edit stats", scope: Scope::Global, binding: &mut self.key_open });
+        visit(HotkeySlot {
+            name: "Close stats editor",
+            scope: Scope::Popup,
+            binding: &mut self.key_close,
+        });
+
+        self.label_open = label_with_hotkey("Edit stats", self.key_open.as_ref());
+        self.label_close = label_with_hotkey("Close", self.key_close.as_ref());
+    }
 }
@@ -1,7 +1,10 @@
 use std::fmt::Write;
 
-use crate::key::Key;
-use crate::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+use crate::binding::Binding;
+use crate::key::InputSource;
+use crate::log::LogEvent;
+use crate::registry::{HotkeySlot, Scope};
+use crate::widgets::{label_with_hotkey, scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
 
 pub trait ReadWrite: Send + Sync + 'static {
     fn read(&mut self) -> bool;
@@ -11,25 +14,33 @@ pub trait ReadWrite: Send + Sync + 'static {
 
 pub struct StoreValue<W: ReadWrite> {
     readwrite: W,
+    name: String,
     label: String,
-    key: Option<Key>,
-    logs: Vec<String>,
+    key: Option<Binding>,
+    logs: Vec<LogEvent>,
 }
 
 impl<W: ReadWrite> StoreValue<W> {
-    pub fn new(write: W, key: Option<Key>) -> Self {
-        let label = write.label();
-        let label = match key {
-            Some(key) => format!("{label} ({key})",),
-            None => label.to_string(),
-        };
+    /// The widget is named after the label of `write` at this point, as the
+    /// label may change later with the value.
+    pub fn new(write: W, key: Option<Binding>) -> Self {
+        let name = write.label().to_string();
+        let label = label_with_hotkey(&name, key.as_ref());
+
+        Self { readwrite: write, name, label, key, logs: Vec::new() }
+    }
 
-        Self { readwrite: write, label, key, logs: Vec::new() }
+    /// Sets the name of the hotkey slot and of the log events, e.g. if the
+    /// label of the value isn't known before it is read.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
     }
 
     fn log_state(&mut self) {
         self.readwrite.read();
-        self.logs.push(format!("{0} triggered", self.readwrite.label()));
+        let message = format!("{} triggered", self.readwrite.label());
+        self.logs.push(LogEvent::info(&self.name, message));
     }
 }
 
@@ -42,10 +53,11 @@ impl<W: ReadWrite> Widget for StoreValue<W> {
         let readable = self.readwrite.read();
         let _token = ui.begin_disabled(!readable);
 
+        // The label of the underlying value may change from frame to frame.
         self.label.clear();
         let label = self.readwrite.label();
-        match self.key {
-            Some(key) => write!(self.label, "{label} ({key})").ok(),
+        match &self.key {
+            Some(key) => write!(self.label, "{label} ({})", key.pretty()).ok(),
             None => write!(self.label, "{label}").ok(),
         };
 
@@ -55,8 +67,8 @@ impl<W: ReadWrite> Widget for StoreValue<W> {
         }
     }
 
-    fn interact(&mut self, ui: &imgui::Ui) {
-        if self.key.map(|key| key.is_pressed(ui)).unwrap_or(false) {
+    fn interact(&mut self, input: &dyn InputSource) {
+        if self.key.as_mut().map(|key| key.is_pressed(input)).unwrap_or(false) {
             self.action();
         }
     }
@@ -67,9 +79,13 @@ impl<W: ReadWrite> Widget for StoreValue<W> {
         self.log_state();
     }
 
-    fn log(&mut self, tx: crossbeam_channel::Sender<String>) {
+    fn log(&mut self, tx: crossbeam_channel::Sender<LogEvent>) {
         self.logs.drain(..).for_each(|log| {
             tx.send(log).ok();
         });
     }
+
+    fn hotkeys(&mut self, visit: &mut dyn FnMut(HotkeySlot<'_>)) {
+        visit(HotkeySlot { name: &self.name, scope: Scope::Global, binding: &mut self.key });
+    }
 }
@@ -0,0 +1,34 @@
+//! Fixtures shared by the integration tests.
+
+// Each test target only uses some of the fixtures.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use practice_tool_core::widgets::flag::Flag;
+
+/// A flag whose clones share the same state, so that a test can keep one to
+/// check after handing the other to a widget.
+#[derive(Clone, Default)]
+pub struct TestFlag(Arc<AtomicBool>);
+
+impl TestFlag {
+    pub fn new(state: bool) -> Self {
+        Self(Arc::new(AtomicBool::new(state)))
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Flag for TestFlag {
+    fn set(&mut self, value: bool) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<bool> {
+        Some(self.is_set())
+    }
+}
@@ -0,0 +1,146 @@
+//! Renders widgets at the display sizes of the three scaling factors and
+//! compares them with the images in `tests/golden`. Needs the `snapshot`
+//! feature. Run with `UPDATE_GOLDENS=1` to update the images after an
+//! intended change.
+
+use std::fs;
+use std::path::PathBuf;
+
+use imgui::sys::ImVec2;
+use practice_tool_core::headless::Harness;
+use practice_tool_core::snapshot::{assert_golden, Tolerance};
+use practice_tool_core::widgets::flag::FlagWidget;
+use practice_tool_core::widgets::radial_menu::{radial_menu, RadialItem, RadialMenu};
+use practice_tool_core::widgets::savefile_manager::SavefileManager;
+use practice_tool_core::widgets::stats_editor::{Datum, Stats, StatsEditor};
+use windows::Win32::UI::Input::XboxController::{XINPUT_GAMEPAD, XINPUT_STATE};
+
+mod common;
+
+use common::TestFlag;
+
+const SIZES: [[f32; 2]; 3] = [[1024., 576.], [1600., 900.], [2400., 1350.]];
+
+fn golden(name: &str, [width, height]: [f32; 2]) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}_{width}x{height}.png"))
+}
+
+fn flag(label: &str) -> Box<FlagWidget<TestFlag>> {
+    Box::new(FlagWidget::new(label, TestFlag::default(), None))
+}
+
+#[test]
+fn test_radial_menu() {
+    for size in SIZES {
+        let mut harness = Harness::new().with_display_size(size);
+        harness.frame(|ui| {
+            radial_menu(
+                ui,
+                &["Quitout", "Warp", "Flags", "Stats"],
+                ImVec2 { x: 0., y: -100. },
+                80.,
+                160.,
+            );
+        });
+        assert_golden(golden("radial_menu", size), &harness.snapshot(), Tolerance::default());
+    }
+}
+
+#[test]
+fn test_radial_menu_submenu() {
+    for size in SIZES {
+        let mut menu = RadialMenu::new("tab".parse().ok(), vec![
+            RadialItem::new("Quitout", flag("Quitout")),
+            RadialItem::submenu("Flags", vec![
+                RadialItem::new("No damage", flag("No damage")),
+                RadialItem::new("One shot", flag("One shot")),
+                RadialItem::new("Inf stamina", flag("Inf stamina")),
+            ]),
+            RadialItem::new("Warp", flag("Warp")),
+            RadialItem::new("Stats", flag("Stats")),
+        ]);
+
+        // Hold the menu open with the stick pointing right, at the submenu.
+        let mut harness = Harness::new().with_display_size(size);
+        harness.press("tab");
+        harness.set_controller_state(Some(XINPUT_STATE {
+            Gamepad: XINPUT_GAMEPAD { sThumbRX: 30000, ..Default::default() },
+            ..Default::default()
+        }));
+        harness.run_closed(&mut menu, 3);
+        assert_golden(golden("radial_submenu", size), &harness.snapshot(), Tolerance::default());
+    }
+}
+
+#[test]
+fn test_savefile_manager() {
+    for size in SIZES {
+        // A fixed directory name, as it is drawn.
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path().join("saves");
+        fs::create_dir_all(dir.join("Any%")).unwrap();
+        fs::write(dir.join("ER0000.sl2"), "ER0000").unwrap();
+        fs::write(dir.join("save1.sl2"), "save1").unwrap();
+        fs::write(dir.join("Any%").join("ER0001.sl2"), "ER0001").unwrap();
+
+        let mut savefile_manager = SavefileManager::new(
+            Some("ctrl+o".parse().unwrap()),
+            Some("escape".parse().unwrap()),
+            dir.join("ER0000.sl2"),
+        );
+
+        let mut harness = Harness::new().with_display_size(size);
+        harness.run(&mut savefile_manager, 1);
+        assert!(harness.click("Load savefile (Ctrl+O)"));
+        harness.run(&mut savefile_manager, 4);
+        assert!(harness.click("saves"));
+        harness.run(&mut savefile_manager, 4);
+        assert!(harness.click("save1.sl2"));
+        harness.run(&mut savefile_manager, 4);
+
+        assert_golden(golden("savefile_manager", size), &harness.snapshot(), Tolerance::default());
+    }
+}
+
+#[test]
+fn test_stats_editor() {
+    struct TestStats {
+        level: i32,
+        hp: i32,
+        runes: i32,
+    }
+
+    impl Stats for TestStats {
+        fn data(&mut self) -> Option<impl Iterator<Item = Datum<'_>>> {
+            Some(
+                [
+                    Datum::int("Level", &mut self.level, 1, 713),
+                    Datum::int("HP", &mut self.hp, 1, 99),
+                    Datum::int("Runes", &mut self.runes, 0, i32::MAX),
+                ]
+                .into_iter(),
+            )
+        }
+
+        fn read(&mut self) {}
+
+        fn write(&mut self) {}
+
+        fn clear(&mut self) {}
+    }
+
+    for size in SIZES {
+        let stats = TestStats { level: 125, hp: 60, runes: 1_000_000 };
+        let mut stats_editor = StatsEditor::new(stats, None, "escape".parse().ok());
+
+        let mut harness = Harness::new().with_display_size(size);
+        harness.run(&mut stats_editor, 1);
+        assert!(harness.click("Edit stats"));
+        harness.run(&mut stats_editor, 4);
+
+        assert_golden(golden("stats_editor", size), &harness.snapshot(), Tolerance::default());
+    }
+}
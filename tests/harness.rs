@@ -1,9 +1,10 @@
 use std::time::Instant;
 
-use imgui::{Context, Ui};
+use imgui::{BackendFlags, Context, Key, Ui};
 use imgui_glow_renderer::glow::HasContext;
 use imgui_glow_renderer::AutoRenderer;
 use imgui_sdl2_support::SdlPlatform;
+use sdl2::controller::{Axis, Button, GameController};
 use sdl2::event::Event;
 use sdl2::video::GLProfile;
 
@@ -47,6 +48,9 @@ pub fn test(mut test_cases: Vec<Box<Test>>) {
     let mut renderer = AutoRenderer::initialize(gl, &mut ctx).unwrap();
     let mut event_pump = sdl.event_pump().unwrap();
 
+    let game_controller_subsystem = sdl.game_controller().unwrap();
+    let mut controllers: Vec<GameController> = Vec::new();
+
     let mut last = Instant::now();
     let mut elapsed = last.elapsed();
 
@@ -54,12 +58,20 @@ pub fn test(mut test_cases: Vec<Box<Test>>) {
         for event in event_pump.poll_iter() {
             platform.handle_event(&mut ctx, &event);
 
-            if let Event::Quit { .. } = event {
-                break 'main;
+            match event {
+                Event::Quit { .. } => break 'main,
+                Event::ControllerDeviceAdded { which, .. } => {
+                    controllers.extend(game_controller_subsystem.open(which).ok())
+                },
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    controllers.retain(|controller| controller.instance_id() != which)
+                },
+                _ => {},
             }
         }
 
         platform.prepare_frame(&mut ctx, &window, &event_pump);
+        feed_gamepad(&mut ctx, controllers.first());
 
         let ui = ctx.new_frame();
 
@@ -82,3 +94,52 @@ pub fn test(mut test_cases: Vec<Box<Test>>) {
         window.gl_swap_window();
     }
 }
+
+/// Feeds a game controller to imgui, which the SDL platform backend doesn't
+/// do, so that controller bindings can be tested with the same config as on
+/// XInput.
+fn feed_gamepad(ctx: &mut Context, controller: Option<&GameController>) {
+    let io = ctx.io_mut();
+    io.backend_flags.set(BackendFlags::HAS_GAMEPAD, controller.is_some());
+
+    let Some(controller) = controller else {
+        return;
+    };
+
+    for (button, key) in [
+        (Button::A, Key::GamepadFaceDown),
+        (Button::B, Key::GamepadFaceRight),
+        (Button::X, Key::GamepadFaceLeft),
+        (Button::Y, Key::GamepadFaceUp),
+        (Button::Back, Key::GamepadBack),
+        (Button::Start, Key::GamepadStart),
+        (Button::LeftStick, Key::GamepadL3),
+        (Button::RightStick, Key::GamepadR3),
+        (Button::LeftShoulder, Key::GamepadL1),
+        (Button::RightShoulder, Key::GamepadR1),
+        (Button::DPadUp, Key::GamepadDpadUp),
+        (Button::DPadDown, Key::GamepadDpadDown),
+        (Button::DPadLeft, Key::GamepadDpadLeft),
+        (Button::DPadRight, Key::GamepadDpadRight),
+    ] {
+        io.add_key_event(key, controller.button(button));
+    }
+
+    // SDL's Y axes point down.
+    let axis = |axis| controller.axis(axis) as f32 / i16::MAX as f32;
+    for (key, value) in [
+        (Key::GamepadL2, axis(Axis::TriggerLeft)),
+        (Key::GamepadR2, axis(Axis::TriggerRight)),
+        (Key::GamepadLStickLeft, -axis(Axis::LeftX)),
+        (Key::GamepadLStickRight, axis(Axis::LeftX)),
+        (Key::GamepadLStickUp, -axis(Axis::LeftY)),
+        (Key::GamepadLStickDown, axis(Axis::LeftY)),
+        (Key::GamepadRStickLeft, -axis(Axis::RightX)),
+        (Key::GamepadRStickRight, axis(Axis::RightX)),
+        (Key::GamepadRStickUp, -axis(Axis::RightY)),
+        (Key::GamepadRStickDown, axis(Axis::RightY)),
+    ] {
+        let value = value.clamp(0., 1.);
+        io.add_key_analog_event(key, value > 0.1, value);
+    }
+}
@@ -0,0 +1,496 @@
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use practice_tool_core::binding::Binding;
+use practice_tool_core::headless::Harness;
+use practice_tool_core::key::InputSource;
+use practice_tool_core::log::Severity;
+use practice_tool_core::registry::hotkey_config;
+use practice_tool_core::widgets::flag::{Flag, FlagWidget};
+use practice_tool_core::widgets::group::Group;
+use practice_tool_core::widgets::hotkey_editor::HotkeyEditor;
+use practice_tool_core::widgets::position::{Position, PositionStorage};
+use practice_tool_core::widgets::radial_menu::{RadialItem, RadialMenu};
+use practice_tool_core::widgets::savefile_manager::SavefileManager;
+use practice_tool_core::widgets::stats_editor::{Datum, Stats, StatsEditor};
+use practice_tool_core::widgets::store_value::{ReadWrite, StoreValue};
+use windows::Win32::UI::Input::XboxController::{
+    XINPUT_GAMEPAD, XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_LEFT_SHOULDER, XINPUT_GAMEPAD_RIGHT_SHOULDER,
+    XINPUT_STATE,
+};
+
+mod common;
+
+use common::TestFlag;
+
+fn label(label: &str, binding: &str) -> String {
+    format!("{label} ({})", binding.parse::<Binding>().unwrap().pretty())
+}
+
+#[test]
+fn test_flag() {
+    let flag = TestFlag::default();
+    let mut widget = FlagWidget::new("test 1", flag.clone(), "ctrl+f | l1+r1+a".parse().ok());
+    let label = label("test 1", "ctrl+f | l1+r1+a");
+
+    let mut harness = Harness::new();
+    harness.run(&mut widget, 1);
+    assert_eq!(harness.is_checked(&label), Some(false));
+
+    harness.tap("ctrl+f");
+    harness.run(&mut widget, 2);
+    assert_eq!(harness.is_checked(&label), Some(true));
+
+    assert!(harness.click(&label));
+    harness.run(&mut widget, 3);
+    assert_eq!(harness.is_checked(&label), Some(false));
+
+    harness.tap_buttons(
+        XINPUT_GAMEPAD_LEFT_SHOULDER | XINPUT_GAMEPAD_RIGHT_SHOULDER | XINPUT_GAMEPAD_A,
+    );
+    harness.run(&mut widget, 2);
+    assert!(flag.is_set());
+
+    assert_eq!(harness.messages(), ["test 1 activated", "test 1 deactivated", "test 1 activated"]);
+    assert!(harness.logs().iter().all(|event| event.source == "test 1"));
+}
+
+#[test]
+fn test_group() {
+    let flags = [TestFlag::default(), TestFlag::default()];
+    let mut group = Group::new("Test group", "escape".parse().unwrap(), vec![
+        Box::new(FlagWidget::new("test 1", flags[0].clone(), None)),
+        Box::new(FlagWidget::new("test 2", flags[1].clone(), None)),
+    ]);
+
+    let mut harness = Harness::new();
+    harness.run(&mut group, 1);
+    assert!(harness.item("Test group").is_some());
+    assert!(harness.item("test 2").is_none());
+
+    assert!(harness.click("Test group"));
+    harness.run(&mut group, 4);
+    assert_eq!(harness.is_checked("test 2"), Some(false));
+
+    assert!(harness.click("test 2"));
+    harness.run(&mut group, 4);
+    assert_eq!(harness.is_checked("test 2"), Some(true));
+    assert!(!flags[0].is_set());
+
+    harness.tap("escape");
+    harness.run(&mut group, 2);
+    assert!(harness.item("test 2").is_none());
+    assert_eq!(harness.messages(), ["test 2 activated"]);
+}
+
+#[test]
+fn test_savefile_manager() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let root = tmp_dir.path().file_name().unwrap().to_str().unwrap().to_string();
+    fs::write(tmp_dir.path().join("ER0000.sl2"), "ER0000").unwrap();
+    fs::write(tmp_dir.path().join("save1.sl2"), "save1").unwrap();
+    fs::create_dir_all(tmp_dir.path().join("Any%")).unwrap();
+    fs::write(tmp_dir.path().join("Any%").join("ER0001.sl2"), "ER0001").unwrap();
+
+    let mut savefile_manager = SavefileManager::new(
+        Some("ctrl+o".parse().unwrap()),
+        Some("escape".parse().unwrap()),
+        tmp_dir.path().join("ER0000.sl2"),
+    );
+    let load = label("Load savefile", "ctrl+o");
+
+    let mut harness = Harness::new();
+    harness.run(&mut savefile_manager, 1);
+
+    assert!(harness.click(&load));
+    harness.run(&mut savefile_manager, 4);
+    assert!(harness.item(&root).is_some());
+
+    assert!(harness.click(&root));
+    harness.run(&mut savefile_manager, 4);
+    assert!(harness.item("Any%").is_some());
+
+    assert!(harness.click("save1.sl2"));
+    harness.run(&mut savefile_manager, 4);
+    assert!(harness.click(&load));
+    harness.run(&mut savefile_manager, 4);
+    assert_eq!(fs::read_to_string(tmp_dir.path().join("ER0000.sl2")).unwrap(), "save1");
+
+    // Directories can't be loaded.
+    assert!(harness.click("Any%"));
+    harness.run(&mut savefile_manager, 4);
+    harness.tap("ctrl+o");
+    harness.run(&mut savefile_manager, 2);
+
+    harness.tap("escape");
+    harness.run(&mut savefile_manager, 2);
+    assert!(harness.item(&root).is_none());
+
+    let severities: Vec<_> = harness.logs().iter().map(|event| event.severity).collect();
+    assert_eq!(harness.messages(), [
+        "Loaded /save1.sl2",
+        "Can't load a directory -- please choose a file."
+    ]);
+    assert_eq!(severities, [Severity::Info, Severity::Warning]);
+}
+
+#[test]
+fn test_position() {
+    /// Moves forward after saving, so that loading moves back.
+    struct TestPosition {
+        current: f32,
+        stored: f32,
+        label_current: String,
+        label_stored: String,
+    }
+
+    impl PositionStorage for TestPosition {
+        fn save(&mut self) {
+            self.stored = self.current;
+            self.current += 1.;
+        }
+
+        fn load(&mut self) {
+            self.current = self.stored;
+        }
+
+        fn display_current(&mut self) -> &str {
+            self.label_current = format!("Current: {:.1}", self.current);
+            &self.label_current
+        }
+
+        fn display_stored(&mut self) -> &str {
+            self.label_stored = format!("Stored: {:.1}", self.stored);
+            &self.label_stored
+        }
+
+        fn is_valid(&self) -> bool {
+            true
+        }
+    }
+
+    let storage = TestPosition {
+        current: 1.,
+        stored: 0.,
+        label_current: String::new(),
+        label_stored: String::new(),
+    };
+    let mut position =
+        Position::new(storage, "h".parse().ok(), "rshift+h".parse().ok()).with_name("Warp");
+
+    let mut harness = Harness::new();
+    harness.run(&mut position, 1);
+    assert!(harness.item("Current: 1.0").is_some());
+    assert!(harness.item("Stored: 0.0").is_some());
+
+    harness.tap("rshift+h");
+    harness.run(&mut position, 3);
+    assert!(harness.item("Current: 2.0").is_some());
+    assert!(harness.item("Stored: 1.0").is_some());
+
+    harness.tap("h");
+    harness.run(&mut position, 3);
+    assert!(harness.item("Current: 1.0").is_some());
+
+    assert!(harness.click(&label("Save", "rshift+h")));
+    harness.run(&mut position, 4);
+    assert!(harness.item("Current: 2.0").is_some());
+
+    assert_eq!(harness.messages(), [
+        "Saved position  Stored: 1.0",
+        "Loaded position Stored: 1.0",
+        "Saved position  Stored: 1.0"
+    ]);
+    assert!(harness.logs().iter().all(|event| event.source == "Warp"));
+    assert_eq!(hotkey_config(&mut position).keys().collect::<Vec<_>>(), ["Warp/Load", "Warp/Save"]);
+}
+
+#[test]
+fn test_stats_editor() {
+    #[derive(Default)]
+    struct TestStats {
+        hp: i32,
+        open: bool,
+        writes: Arc<AtomicUsize>,
+    }
+
+    impl Stats for TestStats {
+        fn data(&mut self) -> Option<impl Iterator<Item = Datum<'_>>> {
+            self.open.then(|| [Datum::int("HP", &mut self.hp, 1, 99)].into_iter())
+        }
+
+        fn read(&mut self) {
+            self.open = true;
+            self.hp = 10;
+        }
+
+        fn write(&mut self) {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn clear(&mut self) {
+            self.open = false;
+        }
+    }
+
+    let stats = TestStats::default();
+    let writes = Arc::clone(&stats.writes);
+    let mut stats_editor = StatsEditor::new(stats, None, "escape".parse().ok());
+
+    let mut harness = Harness::new();
+    harness.run(&mut stats_editor, 1);
+    assert!(harness.item("HP").is_none());
+
+    assert!(harness.click("Edit stats"));
+    harness.run(&mut stats_editor, 4);
+    assert!(harness.item("HP").is_some());
+    assert!(harness.item("10").is_some());
+
+    assert!(harness.click("Apply"));
+    harness.run(&mut stats_editor, 4);
+    assert_eq!(writes.load(Ordering::Relaxed), 1);
+
+    harness.tap("escape");
+    harness.run(&mut stats_editor, 2);
+    assert!(harness.item("HP").is_none());
+    assert_eq!(writes.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_store_value() {
+    struct Quitout(Arc<AtomicUsize>);
+
+    impl ReadWrite for Quitout {
+        fn read(&mut self) -> bool {
+            true
+        }
+
+        fn write(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn label(&self) -> &str {
+            "Quitout"
+        }
+    }
+
+    struct CycleSpeed(Option<usize>, String);
+
+    impl ReadWrite for CycleSpeed {
+        fn read(&mut self) -> bool {
+            self.1 = match self.0 {
+                Some(index) => format!("Speed [{:.1}x]", [1.0, 2.0, 4.0][index]),
+                None => "Speed".to_string(),
+            };
+            self.0.is_some()
+        }
+
+        fn write(&mut self) {
+            self.0 = self.0.map(|index| (index + 1) % 3);
+        }
+
+        fn label(&self) -> &str {
+            &self.1
+        }
+    }
+
+    let quitouts = Arc::new(AtomicUsize::new(0));
+    let mut quitout = StoreValue::new(Quitout(quitouts.clone()), "p".parse().ok());
+    let mut speed =
+        StoreValue::new(CycleSpeed(Some(0), String::new()), "kp8".parse().ok()).with_name("Speed");
+
+    let mut harness = Harness::new();
+    harness.run(&mut quitout, 1);
+    assert!(harness.click(&label("Quitout", "p")));
+    harness.run(&mut quitout, 4);
+    harness.tap("p");
+    harness.run(&mut quitout, 2);
+    assert_eq!(quitouts.load(Ordering::Relaxed), 2);
+
+    // The label follows the value.
+    harness.run(&mut speed, 1);
+    assert!(harness.item(&label("Speed [1.0x]", "kp8")).is_some());
+    harness.tap("kp8");
+    harness.run(&mut speed, 2);
+    assert!(harness.item(&label("Speed [2.0x]", "kp8")).is_some());
+    assert!(harness.click(&label("Speed [2.0x]", "kp8")));
+    harness.run(&mut speed, 4);
+    assert!(harness.item(&label("Speed [4.0x]", "kp8")).is_some());
+
+    // The hotkey slot keeps its name as the label changes.
+    assert_eq!(hotkey_config(&mut quitout).keys().collect::<Vec<_>>(), ["Quitout"]);
+    assert_eq!(hotkey_config(&mut speed).keys().collect::<Vec<_>>(), ["Speed"]);
+
+    assert_eq!(harness.messages(), [
+        "Quitout triggered",
+        "Quitout triggered",
+        "Speed [2.0x] triggered",
+        "Speed [4.0x] triggered"
+    ]);
+    let sources: Vec<_> = harness.logs().iter().map(|event| event.source.as_str()).collect();
+    assert_eq!(sources, ["Quitout", "Quitout", "Speed", "Speed"]);
+
+    // Values that can't be read can't be written.
+    let mut unreadable = StoreValue::new(CycleSpeed(None, String::new()), None);
+    harness.clear_logs();
+    harness.run(&mut unreadable, 1);
+    assert!(harness.click("Speed"));
+    harness.run(&mut unreadable, 4);
+    assert!(harness.messages().is_empty());
+}
+
+#[test]
+fn test_radial_menu() {
+    let flags: Vec<_> = (0..4).map(|_| TestFlag::default()).collect();
+    let items = ["Up", "Right", "Down", "Left"]
+        .into_iter()
+        .zip(&flags)
+        .map(|(label, flag)| {
+            RadialItem::new(label, Box::new(FlagWidget::new(label, flag.clone(), None)))
+        })
+        .collect();
+    let mut menu = RadialMenu::new("tab".parse().ok(), items);
+
+    let mut harness = Harness::new();
+    let center = {
+        let [width, height] = harness.context().io().display_size;
+        [width * 0.5, height * 0.5]
+    };
+    harness.mouse_move(center);
+    harness.run_closed(&mut menu, 1);
+
+    // Pointing the mouse away from the center chooses a slice.
+    harness.press("tab");
+    harness.run_closed(&mut menu, 1);
+    harness.mouse_move([center[0] + 100., center[1]]);
+    harness.run_closed(&mut menu, 1);
+    harness.release("tab");
+    harness.run_closed(&mut menu, 1);
+    assert_eq!(harness.messages(), ["Right activated"]);
+
+    // Near the center, the mouse chooses nothing.
+    harness.press("tab");
+    harness.run_closed(&mut menu, 1);
+    harness.mouse_move([center[0] + 10., center[1] - 10.]);
+    harness.run_closed(&mut menu, 1);
+    harness.release("tab");
+    harness.run_closed(&mut menu, 1);
+    assert_eq!(harness.messages(), ["Right activated"]);
+
+    // The stick wins over the mouse.
+    harness.press("tab");
+    harness.mouse_move([center[0] + 100., center[1] + 110.]);
+    harness.set_controller_state(Some(XINPUT_STATE {
+        dwPacketNumber: 0,
+        Gamepad: XINPUT_GAMEPAD { sThumbRX: -30000, ..Default::default() },
+    }));
+    harness.run_closed(&mut menu, 1);
+    harness.release("tab");
+    harness.run_closed(&mut menu, 1);
+    assert_eq!(harness.messages(), ["Right activated", "Left activated"]);
+    assert!(flags[1].get().unwrap() && flags[3].get().unwrap());
+}
+
+#[test]
+fn test_hotkey_editor() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut editor = HotkeyEditor::new(
+        "escape".parse().ok(),
+        vec![
+            Box::new(FlagWidget::new("a", TestFlag::default(), "f1".parse().ok())),
+            Box::new(FlagWidget::new("b", TestFlag::default(), "f2".parse().ok())),
+        ],
+        tx,
+    );
+    let binding = |editor: &mut HotkeyEditor, name: &str| {
+        hotkey_config(editor)[name].as_ref().map(ToString::to_string)
+    };
+
+    let mut harness = Harness::new();
+    harness.run(&mut editor, 1);
+    assert!(harness.click("Hotkeys"));
+    harness.run(&mut editor, 4);
+
+    // A key rebinds the slot, and the whole config is sent.
+    assert!(harness.click("F1"));
+    harness.run(&mut editor, 4);
+    assert!(harness.item("Press input...").is_some());
+    harness.tap("f3");
+    harness.run(&mut editor, 3);
+    assert_eq!(binding(&mut editor, "a").as_deref(), Some("f3"));
+    let config = rx.try_iter().last().unwrap();
+    assert_eq!(config["a"], "f3".parse().ok());
+    assert_eq!(config["b"], "f2".parse().ok());
+
+    // A conflicting key is rejected.
+    assert!(harness.click("F2"));
+    harness.run(&mut editor, 4);
+    harness.tap("f3");
+    harness.run(&mut editor, 3);
+    assert!(harness.item("F3 is already used by \"a\" (F3)").is_some());
+    assert_eq!(binding(&mut editor, "b").as_deref(), Some("f2"));
+    assert!(rx.try_iter().next().is_none());
+
+    // Escape cancels the capture without closing the editor.
+    assert!(harness.click("F2"));
+    harness.run(&mut editor, 4);
+    harness.tap("escape");
+    harness.run(&mut editor, 3);
+    assert!(harness.item("Press input...").is_none());
+    assert!(harness.item("F2").is_some());
+    assert_eq!(binding(&mut editor, "b").as_deref(), Some("f2"));
+
+    // Controller inputs held together replace the controller alternatives
+    // once released, and keep the keyboard ones.
+    assert!(harness.click("F2"));
+    harness.run(&mut editor, 4);
+    let gamepad = |buttons| XINPUT_STATE {
+        dwPacketNumber: 0,
+        Gamepad: XINPUT_GAMEPAD { wButtons: buttons, ..Default::default() },
+    };
+    harness.set_controller_state(Some(gamepad(XINPUT_GAMEPAD_LEFT_SHOULDER)));
+    harness.run(&mut editor, 2);
+    harness.set_controller_state(Some(gamepad(XINPUT_GAMEPAD_LEFT_SHOULDER | XINPUT_GAMEPAD_A)));
+    harness.run(&mut editor, 2);
+    harness.set_controller_state(Some(gamepad(Default::default())));
+    harness.run(&mut editor, 2);
+    assert_eq!(binding(&mut editor, "b").as_deref(), Some("f2 | l1+a"));
+    assert_eq!(rx.try_iter().last().unwrap()["b"], "f2 | l1+a".parse().ok());
+
+    // Clearing unbinds the slot.
+    let clear = harness.items().iter().filter(|item| item.text == "Clear").nth(1).cloned();
+    harness.mouse_move(clear.unwrap().center());
+    harness.run(&mut editor, 1);
+    harness.mouse_down(imgui::MouseButton::Left);
+    harness.run(&mut editor, 1);
+    harness.mouse_up(imgui::MouseButton::Left);
+    harness.run(&mut editor, 2);
+    assert_eq!(binding(&mut editor, "b"), None);
+    assert_eq!(rx.try_iter().last().unwrap()["b"], None);
+
+    assert_eq!(harness.messages(), [
+        "Bound \"a\" to F3",
+        "Bound \"b\" to F2 / L1+A",
+        "Unbound \"b\""
+    ]);
+}
+
+#[test]
+fn test_key_analog_value() {
+    let mut harness = Harness::new();
+    harness.frame(|ui| {
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::ModCtrl), 0.);
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::GamepadL2), 0.);
+    });
+
+    harness.press("ctrl+a");
+    harness.set_controller_state(Some(XINPUT_STATE {
+        dwPacketNumber: 0,
+        Gamepad: XINPUT_GAMEPAD { bLeftTrigger: 255, ..Default::default() },
+    }));
+    harness.frame(|ui| {
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::ModCtrl), 1.);
+        assert_eq!(InputSource::key_analog_value(ui, imgui::Key::GamepadL2), 1.);
+    });
+}
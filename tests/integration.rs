@@ -2,8 +2,10 @@ use std::fmt::Write;
 use std::fs;
 
 use imgui::sys::ImVec2;
-use practice_tool_core::widgets::flag::{Flag, FlagWidget};
+use practice_tool_core::widgets::flag::FlagWidget;
 use practice_tool_core::widgets::group::Group;
+use practice_tool_core::widgets::hotkey_editor::HotkeyEditor;
+use practice_tool_core::widgets::log_console::LogConsole;
 use practice_tool_core::widgets::nudge_position::{NudgePosition, NudgePositionStorage};
 use practice_tool_core::widgets::position::{Position, PositionStorage};
 use practice_tool_core::widgets::savefile_manager::SavefileManager;
@@ -11,8 +13,11 @@ use practice_tool_core::widgets::stats_editor::{Datum, Stats, StatsEditor};
 use practice_tool_core::widgets::store_value::{ReadWrite, StoreValue};
 use practice_tool_core::widgets::{radial_menu, Widget};
 
+mod common;
 mod harness;
 
+use common::TestFlag;
+
 macro_rules! harness_test {
     ($($t:expr),+) => {
         harness::test(vec![
@@ -21,23 +26,13 @@ macro_rules! harness_test {
     }
 }
 
-struct TestFlag(bool);
-
-impl Flag for TestFlag {
-    fn set(&mut self, value: bool) {
-        self.0 = value;
-    }
-
-    fn get(&self) -> Option<bool> {
-        Some(self.0)
-    }
-}
-
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_flag() {
-    let mut flag1 = FlagWidget::new("test 1", TestFlag(true), "ctrl+f".parse().ok());
-    let mut flag2 = FlagWidget::new("test 2", TestFlag(true), "ctrl+shift+f".parse().ok());
-    let mut flag3 = FlagWidget::new("test 3", TestFlag(true), "ctrl+lalt+rshift+f".parse().ok());
+    let mut flag1 = FlagWidget::new("test 1", TestFlag::new(true), "ctrl+f".parse().ok());
+    let mut flag2 = FlagWidget::new("test 2", TestFlag::new(true), "ctrl+shift+f".parse().ok());
+    let mut flag3 =
+        FlagWidget::new("test 3", TestFlag::new(true), "ctrl+lalt+rshift+f".parse().ok());
 
     harness_test! {
         move |ui| { flag1.render(ui); flag1.interact(ui); },
@@ -47,6 +42,7 @@ fn test_flag() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_savefile_manager() {
     let tmp_dir = tempfile::tempdir().unwrap();
 
@@ -93,10 +89,11 @@ fn test_savefile_manager() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_group() {
-    let flag1 = Box::new(FlagWidget::new("test 1", TestFlag(true), None));
-    let flag2 = Box::new(FlagWidget::new("test 2", TestFlag(true), None));
-    let flag3 = Box::new(FlagWidget::new("test 3", TestFlag(true), None));
+    let flag1 = Box::new(FlagWidget::new("test 1", TestFlag::new(true), None));
+    let flag2 = Box::new(FlagWidget::new("test 2", TestFlag::new(true), None));
+    let flag3 = Box::new(FlagWidget::new("test 3", TestFlag::new(true), None));
 
     let mut group = Group::new("Test group", "escape".parse().unwrap(), vec![flag1, flag2, flag3]);
 
@@ -106,6 +103,67 @@ fn test_group() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
+fn test_hotkey_editor() {
+    let flag1 = Box::new(FlagWidget::new("test 1", TestFlag::new(true), "ctrl+f".parse().ok()));
+    let flag2 =
+        Box::new(FlagWidget::new("test 2", TestFlag::new(true), "f2 | l1+r1+a".parse().ok()));
+    let group = Box::new(Group::new("Test group", "escape".parse().unwrap(), vec![
+        Box::new(FlagWidget::new("test 3", TestFlag::new(true), "ctrl+k, ctrl+f".parse().ok())),
+        Box::new(FlagWidget::new("test 4", TestFlag::new(true), "hold:500ms f4".parse().ok())),
+    ]));
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let (log_tx, log_rx) = crossbeam_channel::unbounded();
+    let mut hotkey_editor = HotkeyEditor::new("escape".parse().ok(), vec![flag1, flag2, group], tx);
+
+    harness_test! {
+        move |ui| {
+            hotkey_editor.render(ui);
+            hotkey_editor.interact(ui);
+            hotkey_editor.log(log_tx.clone());
+
+            for log in log_rx.try_iter() {
+                eprintln!("Received log {log}");
+            }
+
+            for config in rx.try_iter() {
+                eprintln!("Received config {config:?}");
+            }
+        }
+    };
+}
+
+#[test]
+#[ignore = "interactive, runs until the window is closed"]
+fn test_log_console() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut flag = FlagWidget::new("test 1", TestFlag::new(true), "ctrl+f".parse().ok());
+    let mut console = LogConsole::new(
+        rx,
+        "ctrl+l".parse().ok(),
+        "escape".parse().ok(),
+        tmp_dir.path().join("log.txt"),
+    );
+
+    harness_test! {
+        move |ui| {
+            flag.render(ui);
+            flag.interact(ui);
+            flag.log(tx.clone());
+
+            console.render(ui);
+            console.render_closed(ui);
+            console.interact(ui);
+            console.log(tx.clone());
+        }
+    };
+}
+
+#[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_position() {
     static mut X: f64 = 0.0;
 
@@ -172,6 +230,7 @@ fn test_position() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_stats_editor() {
     static mut STATS: (i32, i32, f32) = (10, 10, 10.0);
 
@@ -224,6 +283,7 @@ fn test_stats_editor() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_store_value() {
     static mut QUITOUTS: usize = 0;
     static mut SPEED: f32 = 1.0;
@@ -279,6 +339,7 @@ fn test_store_value() {
 }
 
 #[test]
+#[ignore = "interactive, runs until the window is closed"]
 fn test_radial_menu() {
     harness_test! {
         move |ui| {
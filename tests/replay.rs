@@ -0,0 +1,81 @@
+use std::fs;
+use std::time::Duration;
+
+use practice_tool_core::key::ScriptedInput;
+use practice_tool_core::replay::{InputRecorder, InputReplayer, Recording};
+use practice_tool_core::widgets::navigator::Navigator;
+use practice_tool_core::widgets::savefile_manager::SavefileManager;
+use practice_tool_core::widgets::Widget;
+
+/// Records a sequence of key taps, with an idle frame after each.
+fn record(taps: &[&str]) -> Recording {
+    let mut input = ScriptedInput::new();
+    let mut recorder = InputRecorder::new();
+
+    for tap in taps {
+        input.press_key(&tap.parse().unwrap());
+        recorder.record(&input);
+        input.next_frame(Duration::from_millis(16));
+        input.release_all();
+        recorder.record(&input);
+        input.next_frame(Duration::from_millis(16));
+    }
+
+    recorder.finish()
+}
+
+/// Replays the recording through a bare imgui context, rendering the widget
+/// and interacting with it from the `Ui` on every frame, and returns the logs.
+fn replay(recording: Recording, widget: &mut dyn Widget) -> Vec<String> {
+    let mut ctx = imgui::Context::create();
+    ctx.set_ini_filename(None);
+    ctx.io_mut().display_size = [1280., 720.];
+    ctx.io_mut().delta_time = 1. / 60.;
+    ctx.fonts().build_rgba32_texture();
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut replayer = InputReplayer::new(recording);
+
+    while replayer.feed(ctx.io_mut()).is_some() {
+        let ui = ctx.new_frame();
+        ui.window("replay").build(|| widget.render(ui));
+        widget.interact(ui);
+        widget.log(tx.clone());
+        ctx.render();
+    }
+
+    rx.try_iter().map(String::from).collect()
+}
+
+#[test]
+fn test_savefile_manager() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(tmp_dir.path().join("save1.sl2"), "save1").unwrap();
+
+    let savefile_manager = SavefileManager::new(
+        Some("ctrl+o".parse().unwrap()),
+        Some("escape".parse().unwrap()),
+        tmp_dir.path().join("ER0000.sl2"),
+    );
+    let mut navigator = Navigator::new(vec![Box::new(savefile_manager)]);
+
+    // Nothing is selected yet. Then the popup is entered, the root directory
+    // is opened and its file is loaded, and the popup is closed before loading
+    // the file again.
+    let recording = record(&[
+        "ctrl+o", "escape", "enter", "down", "enter", "down", "enter", "escape", "ctrl+o",
+    ]);
+
+    // The recording survives a round trip through a file.
+    let recording_file = tempfile::NamedTempFile::new().unwrap();
+    recording.save(recording_file.path()).unwrap();
+    let recording = Recording::load(recording_file.path()).unwrap();
+
+    let logs = replay(recording, &mut navigator);
+    assert_eq!(logs, [
+        "No current path! Can't load savefile.",
+        "Loaded /save1.sl2",
+        "Loaded /save1.sl2"
+    ]);
+    assert_eq!(fs::read_to_string(tmp_dir.path().join("ER0000.sl2")).unwrap(), "save1");
+}